@@ -1,10 +1,57 @@
 use std::env;
+use std::path::Path;
+use std::process::Command;
+
+/// Dependencies whose resolved versions are reported by `pyro-thor version`.
+const REPORTED_DEPENDENCIES: [(&str, &str); 3] = [
+    ("redb", "BUILD_REDB_VERSION"),
+    ("bincode", "BUILD_BINCODE_VERSION"),
+    ("serde_json", "BUILD_SERDE_JSON_VERSION"),
+];
 
 fn main() {
     // Set build-time environment variables
     println!("cargo:rustc-env=BUILD_TARGET={}", env::var("TARGET").unwrap_or_else(|_| "unknown".to_string()));
     println!("cargo:rustc-env=BUILD_PROFILE={}", env::var("PROFILE").unwrap_or_else(|_| "unknown".to_string()));
-    
+    println!("cargo:rustc-env=BUILD_GIT_COMMIT={}", git_commit().unwrap_or_else(|| "unknown".to_string()));
+
+    let lockfile = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap_or_default()).join("Cargo.lock");
+    let lockfile = std::fs::read_to_string(&lockfile).unwrap_or_default();
+    for (package, variable) in REPORTED_DEPENDENCIES {
+        println!("cargo:rustc-env={}={}", variable,
+                 locked_version(&lockfile, package).unwrap_or_else(|| "unknown".to_string()));
+    }
+
     // Rerun if build script changes
     println!("cargo:rerun-if-changed=build.rs");
-}
\ No newline at end of file
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    // Pick up new commits; only watched when building from a checkout
+    for git_file in [".git/HEAD", ".git/index"] {
+        if Path::new(git_file).exists() {
+            println!("cargo:rerun-if-changed={}", git_file);
+        }
+    }
+}
+
+/// Commit being built, with `-dirty` when the tree has local changes.
+fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["describe", "--always", "--dirty", "--abbrev=12", "--exclude=*"])
+        .output()
+        .ok()?;
+    let commit = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !commit.is_empty()).then_some(commit)
+}
+
+/// Version of `package` in a Cargo.lock, from the `version` line following
+/// its `name` line.
+fn locked_version(lockfile: &str, package: &str) -> Option<String> {
+    let name_line = format!("name = \"{}\"", package);
+    let mut lines = lockfile.lines();
+    lines.find(|line| line.trim() == name_line)?;
+    lines.next()?
+        .trim()
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')
+        .map(str::to_string)
+}
@@ -0,0 +1,334 @@
+use crate::config::ScanConfig;
+use crate::findings::finding_path;
+use crate::walk::{walk_files, WalkOptions};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+const TAR_BLOCK: usize = 512;
+
+/// Limits for `expand_archives`.
+#[derive(Debug, Clone)]
+pub struct ArchiveOptions {
+    /// Levels of nesting expanded; 1 extracts only archives found on disk.
+    pub max_depth: usize,
+    /// Total bytes written across every extracted archive.
+    pub max_total_bytes: u64,
+    pub walk: WalkOptions,
+    /// Files under these directories are never treated as archives.
+    pub exclude: Vec<PathBuf>,
+}
+
+impl ArchiveOptions {
+    pub fn from_config(config: &ScanConfig) -> Self {
+        Self {
+            max_depth: config.archive_max_depth,
+            max_total_bytes: config.archive_max_total_mb * 1024 * 1024,
+            walk: WalkOptions::from_config(config),
+            exclude: config.exclude_paths.iter().map(PathBuf::from).collect(),
+        }
+    }
+}
+
+/// One archive and the directory its contents were extracted into.
+#[derive(Debug, Clone)]
+pub struct ExtractedArchive {
+    pub root: PathBuf,
+    /// Location of the archive, with `!/` separating each nesting level,
+    /// e.g. `/data/outer.zip!/inner/payload.tar`.
+    pub source: String,
+    /// The outermost archive, which is what exists on disk.
+    pub archive_file: PathBuf,
+}
+
+#[derive(Debug, Default)]
+pub struct ArchiveExpansion {
+    pub extracted: Vec<ExtractedArchive>,
+    pub bytes_extracted: u64,
+    /// Whether `max_total_bytes` stopped extraction early.
+    pub truncated: bool,
+}
+
+impl ArchiveExpansion {
+    /// Adds `archive_file` and `archive_path` to a finding whose file was
+    /// extracted from an archive, so it can be traced back to the original.
+    pub fn annotate(&self, finding: &mut Value) {
+        let Some(path) = finding_path(finding).map(PathBuf::from) else {
+            return;
+        };
+
+        let Some((archive, member)) = self.extracted.iter()
+            .find_map(|archive| path.strip_prefix(&archive.root).ok().map(|member| (archive, member)))
+        else {
+            return;
+        };
+
+        let archive_path = format!("{}!/{}", archive.source, member.to_string_lossy().replace('\\', "/"));
+        let archive_file = archive.archive_file.to_string_lossy().to_string();
+        if let Value::Object(finding) = finding {
+            finding.insert("archive_file".to_string(), Value::String(archive_file));
+            finding.insert("archive_path".to_string(), Value::String(archive_path));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    Gzip,
+}
+
+/// Finds zip, tar and gzip (including `.tar.gz`) files under `scan_path`
+/// by their magic bytes and extracts each into its own directory below
+/// `work_dir`, recursing into archives found inside them up to
+/// `max_depth` levels.
+///
+/// Entries whose names would escape the extraction directory (absolute
+/// paths, `..`) and links are skipped. Once `max_total_bytes` have been
+/// written extraction stops, keeping what was extracted so far; a failure
+/// to read one archive is logged and the rest are still expanded.
+pub fn expand_archives(scan_path: &Path, work_dir: &Path, options: &ArchiveOptions) -> Result<ArchiveExpansion> {
+    std::fs::create_dir_all(work_dir)
+        .context("Failed to create archive extraction directory")?;
+
+    let mut expansion = ArchiveExpansion::default();
+    let mut budget = options.max_total_bytes;
+
+    // (file, display location, outermost archive, nesting level)
+    let mut pending: Vec<(PathBuf, String, Option<PathBuf>, usize)> = walk_files(scan_path, &options.walk)?
+        .into_iter()
+        .filter(|path| !options.exclude.iter().any(|excluded| path.starts_with(excluded)))
+        .map(|path| {
+            let source = path.to_string_lossy().to_string();
+            (path, source, None, 1)
+        })
+        .collect();
+
+    while let Some((path, source, outermost, depth)) = pending.pop() {
+        let Some(kind) = detect_archive(&path) else { continue };
+        if depth > options.max_depth {
+            log::warn!("Not expanding {}: nested deeper than {} archives", source, options.max_depth);
+            continue;
+        }
+        if expansion.truncated {
+            log::warn!("Not expanding {}: archive size limit reached", source);
+            continue;
+        }
+
+        let root = work_dir.join(expansion.extracted.len().to_string());
+        let before = budget;
+        match extract_archive(&path, kind, &root, &mut budget) {
+            Ok(complete) => expansion.truncated |= !complete,
+            Err(e) => {
+                log::warn!("Failed to expand archive {}: {:#}", source, e);
+                continue;
+            }
+        }
+        expansion.bytes_extracted += before - budget;
+        log::info!("Expanded {} ({} bytes)", source, before - budget);
+
+        let archive_file = outermost.unwrap_or_else(|| path.clone());
+        for member in walk_files(&root, &options.walk)? {
+            let relative = member.strip_prefix(&root).unwrap_or(&member);
+            let member_source = format!("{}!/{}", source, relative.to_string_lossy().replace('\\', "/"));
+            pending.push((member, member_source, Some(archive_file.clone()), depth + 1));
+        }
+
+        expansion.extracted.push(ExtractedArchive { root, source, archive_file });
+    }
+
+    if expansion.truncated {
+        log::warn!("Archive expansion stopped at {} bytes (scanning.archive_max_total_mb)",
+                   options.max_total_bytes);
+    }
+    Ok(expansion)
+}
+
+fn detect_archive(path: &Path) -> Option<ArchiveKind> {
+    let mut header = [0u8; 262];
+    let mut file = std::fs::File::open(path).ok()?;
+    let read = read_up_to(&mut file, &mut header).ok()?;
+    archive_kind(&header[..read])
+}
+
+fn archive_kind(header: &[u8]) -> Option<ArchiveKind> {
+    if header.starts_with(b"PK\x03\x04") {
+        Some(ArchiveKind::Zip)
+    } else if header.starts_with(&[0x1f, 0x8b]) {
+        Some(ArchiveKind::Gzip)
+    } else if header.len() >= 262 && &header[257..262] == b"ustar" {
+        Some(ArchiveKind::Tar)
+    } else {
+        None
+    }
+}
+
+/// Extracts one archive into `root`; returns `false` if the byte budget ran out.
+fn extract_archive(path: &Path, kind: ArchiveKind, root: &Path, budget: &mut u64) -> Result<bool> {
+    std::fs::create_dir_all(root)?;
+    let file = std::fs::File::open(path)?;
+
+    match kind {
+        ArchiveKind::Zip => extract_zip(file, root, budget),
+        ArchiveKind::Tar => extract_tar(file, root, budget),
+        ArchiveKind::Gzip => {
+            let mut decoder = flate2::read::GzDecoder::new(file);
+            let mut header = vec![0u8; TAR_BLOCK];
+            let read = read_up_to(&mut decoder, &mut header)?;
+            header.truncate(read);
+
+            let stream = std::io::Cursor::new(header.clone()).chain(decoder);
+            if archive_kind(&header) == Some(ArchiveKind::Tar) {
+                extract_tar(stream, root, budget)
+            } else {
+                // A single compressed file, named after the archive
+                let name = path.file_stem().map_or_else(|| "content".into(), |stem| stem.to_os_string());
+                write_limited(stream, &root.join(name), budget)
+            }
+        }
+    }
+}
+
+fn extract_zip(file: std::fs::File, root: &Path, budget: &mut u64) -> Result<bool> {
+    let mut archive = zip::ZipArchive::new(file)?;
+
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index)?;
+        let Some(relative) = entry.enclosed_name().map(Path::to_path_buf) else {
+            log::warn!("Skipping archive entry with unsafe path: {}", entry.name());
+            continue;
+        };
+
+        if entry.is_dir() {
+            std::fs::create_dir_all(root.join(relative))?;
+        } else if !write_limited(entry, &root.join(relative), budget)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Reads a ustar/GNU tar stream. Only regular files and directories are
+/// extracted; links and special files are skipped.
+fn extract_tar<R: Read>(mut reader: R, root: &Path, budget: &mut u64) -> Result<bool> {
+    let mut long_name: Option<String> = None;
+
+    loop {
+        let mut header = [0u8; TAR_BLOCK];
+        if read_up_to(&mut reader, &mut header)? < TAR_BLOCK || header.iter().all(|b| *b == 0) {
+            return Ok(true);
+        }
+
+        let size = parse_octal(&header[124..136])
+            .context("Invalid size in tar header")?;
+        let padded = size.div_ceil(TAR_BLOCK as u64) * TAR_BLOCK as u64;
+        let mut data = (&mut reader).take(padded);
+
+        let name = long_name.take().unwrap_or_else(|| {
+            let name = c_string(&header[0..100]);
+            let prefix = c_string(&header[345..500]);
+            if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                format!("{}/{}", prefix, name)
+            } else {
+                name
+            }
+        });
+
+        match header[156] {
+            b'0' | 0 | b'7' => match safe_relative_path(&name) {
+                Some(relative) => {
+                    if !write_limited((&mut data).take(size), &root.join(relative), budget)? {
+                        return Ok(false);
+                    }
+                }
+                None => log::warn!("Skipping archive entry with unsafe path: {}", name),
+            },
+            b'5' => {
+                if let Some(relative) = safe_relative_path(&name) {
+                    std::fs::create_dir_all(root.join(relative))?;
+                }
+            }
+            // GNU long name for the next entry
+            b'L' => {
+                let mut buffer = Vec::new();
+                (&mut data).take(size).read_to_end(&mut buffer)?;
+                long_name = Some(c_string(&buffer));
+            }
+            // PAX extended header; only its `path` record matters here
+            b'x' => {
+                let mut buffer = Vec::new();
+                (&mut data).take(size).read_to_end(&mut buffer)?;
+                long_name = String::from_utf8_lossy(&buffer)
+                    .lines()
+                    .find_map(|record| record.split_once(" path=").map(|(_, path)| path.to_string()));
+            }
+            _ => {}
+        }
+
+        std::io::copy(&mut data, &mut std::io::sink())?;
+    }
+}
+
+/// Copies `reader` to `path` unless that would exceed `budget`, in which
+/// case the partial file is removed and `false` returned.
+fn write_limited<R: Read>(reader: R, path: &Path, budget: &mut u64) -> Result<bool> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut output = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    let written = std::io::copy(&mut reader.take(*budget + 1), &mut output)?;
+    output.flush()?;
+
+    if written > *budget {
+        drop(output);
+        let _ = std::fs::remove_file(path);
+        *budget = 0;
+        return Ok(false);
+    }
+    *budget -= written;
+    Ok(true)
+}
+
+/// `name` as a path made only of normal components, or `None` if it is
+/// absolute or climbs out with `..`.
+fn safe_relative_path(name: &str) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+fn parse_octal(field: &[u8]) -> Result<u64> {
+    let text = c_string(field);
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).with_context(|| format!("not an octal number: {:?}", text))
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+/// Fills as much of `buffer` as the reader provides; short only at EOF.
+fn read_up_to<R: Read>(reader: &mut R, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
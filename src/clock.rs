@@ -0,0 +1,90 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, Mutex};
+
+/// Environment variable that pins every clock to an RFC 3339 timestamp, so
+/// audit entries, detection times and rule timestamps are reproducible.
+pub const FIXED_TIME_ENV: &str = "PYRO_FIXED_TIME";
+
+/// Source of the current time for everything that records or compares
+/// timestamps, so expiry, decay and audit behaviour can be driven without
+/// waiting on the wall clock.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Clock shared between the executor, the scanner and the ReDB hook.
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The wall clock.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl MockClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self { now: Arc::new(Mutex::new(start)) }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    pub fn advance(&self, by: chrono::Duration) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        *now += by;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// The wall clock, or a `MockClock` stopped at `PYRO_FIXED_TIME` when set.
+pub fn from_env() -> Result<SharedClock> {
+    match std::env::var(FIXED_TIME_ENV) {
+        Ok(fixed) => {
+            let start = DateTime::parse_from_rfc3339(fixed.trim())
+                .with_context(|| format!("{} is not an RFC 3339 timestamp: {}", FIXED_TIME_ENV, fixed))?
+                .with_timezone(&Utc);
+            log::warn!("Using fixed time {} from {}", start.to_rfc3339(), FIXED_TIME_ENV);
+            Ok(Arc::new(MockClock::new(start)))
+        }
+        Err(_) => Ok(system()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_clock_clones_share_their_time() {
+        let start = Utc::now();
+        let clock = MockClock::new(start);
+        let shared: SharedClock = Arc::new(clock.clone());
+
+        clock.advance(chrono::Duration::hours(2));
+        assert_eq!(shared.now(), start + chrono::Duration::hours(2));
+
+        clock.set(start);
+        assert_eq!(shared.now(), start);
+    }
+}
@@ -0,0 +1,48 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+
+pub fn command() -> Command {
+    Command::new("audit")
+        .about("Review the database audit log")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("tail")
+                .about("Show the most recent database changes")
+                .arg(
+                    Arg::new("lines")
+                        .short('n')
+                        .long("lines")
+                        .value_name("N")
+                        .help("Number of entries to show")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("20"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print entries as NDJSON")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("tail", sub)) => {
+            let limit = *sub.get_one::<usize>("lines").unwrap();
+
+            let hook = super::open_database(matches).await?;
+            for entry in hook.audit_tail(limit).await? {
+                if sub.get_flag("json") {
+                    println!("{}", serde_json::to_string(&entry)?);
+                } else {
+                    println!("{:>6}  {}  {:<12} {:<22} {}  {}",
+                             entry.sequence, entry.timestamp.to_rfc3339(), entry.actor,
+                             entry.operation, entry.target, entry.detail);
+                }
+            }
+            Ok(())
+        }
+        _ => unreachable!("subcommand_required is set"),
+    }
+}
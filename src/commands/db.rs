@@ -0,0 +1,437 @@
+use anyhow::{Context, Result};
+use clap::{Arg, ArgMatches, Command};
+use std::path::Path;
+
+use crate::hooks::{prune_missing_rules, sync_directories, DeletedRule, ExportFormat, MergeStrategy, SyncOptions, SyncStatus, YaraRulesRedbHook};
+
+pub fn command() -> Command {
+    Command::new("db")
+        .about("Maintain the ReDB YARA rules database")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("merge")
+                .about("Import rules, metadata and intel from another database")
+                .arg(
+                    Arg::new("other")
+                        .value_name("OTHER_DB")
+                        .help("Database file to merge from")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("strategy")
+                        .long("strategy")
+                        .value_name("STRATEGY")
+                        .help("Conflict resolution for records present in both databases")
+                        .value_parser(["newer", "existing", "incoming"])
+                        .default_value("newer"),
+                ),
+        )
+        .subcommand(
+            Command::new("sync")
+                .about("Store the YARA rules from one or more directories")
+                .arg(
+                    Arg::new("directory")
+                        .value_name("DIR")
+                        .help("Directories of .yar/.yara files; rules are tagged root:<name of DIR>")
+                        .num_args(1..)
+                        .default_value("custom-signatures/yara"),
+                )
+                .arg(
+                    Arg::new("manifest")
+                        .long("manifest")
+                        .value_name("FILE")
+                        .help("Write the per-rule sync manifest as JSON"),
+                )
+                .arg(
+                    Arg::new("author")
+                        .long("author")
+                        .value_name("AUTHOR")
+                        .help("Author recorded on synced rules")
+                        .default_value("Auto-imported"),
+                )
+                .arg(
+                    Arg::new("default-severity")
+                        .long("default-severity")
+                        .value_name("SEVERITY")
+                        .help("Severity of rules without a severity meta entry or severity_from_tags match")
+                        .value_parser(["info", "low", "medium", "high", "critical"])
+                        .default_value("medium"),
+                )
+                .arg(
+                    Arg::new("prune-missing")
+                        .long("prune-missing")
+                        .help("Delete stored rules whose file in DIR no longer exists")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("prune")
+                .about("Delete stored rules whose file in a rules directory no longer exists")
+                .arg(
+                    Arg::new("directory")
+                        .value_name("DIR")
+                        .help("Directory the rules were synced from")
+                        .default_value("custom-signatures/yara"),
+                )
+                .arg(super::dry_run_arg()),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Show record counts")
+                .arg(
+                    Arg::new("by-source")
+                        .long("by-source")
+                        .help("Also break counts down by rule source and intel feed")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("verify")
+                .about("Check every record deserializes and every rule matches its stored hash")
+                .arg(
+                    Arg::new("repair")
+                        .long("repair")
+                        .help("Move unreadable records into the corrupt_records table")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Write all rules, metadata and intel to a file")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("Output file, or - for stdout")
+                        .required(true),
+                )
+                .arg(format_arg()),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Load records written by `db export`")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("Export file, or - for stdin")
+                        .required(true),
+                )
+                .arg(format_arg()),
+        )
+        .subcommand(
+            Command::new("delete")
+                .about("Delete every rule matching one criterion, with its metadata")
+                .arg(
+                    Arg::new("tag")
+                        .long("tag")
+                        .value_name("TAG")
+                        .help("Rules carrying this tag"),
+                )
+                .arg(
+                    Arg::new("feed")
+                        .long("feed")
+                        .value_name("SOURCE")
+                        .help("Rules from this source, as shown by `db stats --by-source`"),
+                )
+                .arg(
+                    Arg::new("severity")
+                        .long("severity")
+                        .value_name("SEVERITY")
+                        .value_parser(["info", "low", "medium", "high", "critical"]),
+                )
+                .group(
+                    clap::ArgGroup::new("criterion")
+                        .args(["tag", "feed", "severity"])
+                        .required(true),
+                )
+                .arg(super::dry_run_arg()),
+        )
+        .subcommand(
+            Command::new("migrate-blobs")
+                .about("Move inline rule bodies into the deduplicated blob table"),
+        )
+        .subcommand(
+            Command::new("migrate-intel")
+                .about("Key threat intel by indicator type and value, merging records of the same pair"),
+        )
+        .subcommand(
+            Command::new("mitre-map")
+                .about("Set rules' MITRE ATT&CK ids from a rule-name mapping file")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("JSON object or CSV (rule_name,technique[,tactic]) mapping")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("name-conflicts")
+                .about("List YARA rule names declared by more than one stored rule")
+                .arg(
+                    Arg::new("rename")
+                        .long("rename")
+                        .help("Keep each name on its oldest rule and suffix the others' declarations")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+}
+
+/// Prints deleted (or, in a dry run, deletable) rules and their count.
+fn print_deleted_rules(deleted: &[DeletedRule], dry_run: bool) {
+    for rule in deleted {
+        println!("{:<36} {:<40} {:<9} {}", rule.id, rule.name, rule.severity, rule.source);
+    }
+    if dry_run {
+        println!("{} rules would be deleted (dry run)", deleted.len());
+    } else {
+        println!("Deleted {} rules", deleted.len());
+    }
+}
+
+/// Points at `db verify` when lenient reads skipped anything.
+fn warn_skipped(hook: &YaraRulesRedbHook) {
+    let skipped = hook.skipped_records();
+    if !skipped.is_empty() {
+        eprintln!("Skipped {} unreadable record(s); run `db verify --repair` to list and quarantine them",
+                  skipped.len());
+    }
+}
+
+fn format_arg() -> Arg {
+    Arg::new("format")
+        .long("format")
+        .value_name("FORMAT")
+        .help("ndjson streams one record per line; json is a single document for small databases")
+        .value_parser(["ndjson", "json"])
+        .default_value("ndjson")
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("merge", sub)) => {
+            let other = Path::new(sub.get_one::<String>("other").unwrap());
+            let strategy: MergeStrategy = sub.get_one::<String>("strategy").unwrap().parse()?;
+
+            let hook = super::open_database(matches).await?;
+            let report = hook.merge_database(other, strategy).await?;
+
+            println!("{:<14} {:>8} {:>9} {:>14}", "category", "added", "replaced", "kept existing");
+            for (category, counts) in [
+                ("rules", report.rules),
+                ("metadata", report.metadata),
+                ("threat intel", report.threat_intel),
+            ] {
+                println!("{:<14} {:>8} {:>9} {:>14}", category, counts.added, counts.replaced, counts.kept_existing);
+            }
+            Ok(())
+        }
+        Some(("sync", sub)) => {
+            let directories: Vec<String> = sub.get_many::<String>("directory").unwrap().cloned().collect();
+            let options = SyncOptions {
+                default_author: sub.get_one::<String>("author").unwrap().clone(),
+                default_severity: sub.get_one::<String>("default-severity").unwrap().parse()?,
+                prune_missing: sub.get_flag("prune-missing"),
+                ..Default::default()
+            };
+
+            let hook = super::open_database(matches).await?;
+            let report = sync_directories(&hook, &directories, &options).await;
+
+            println!("{:<8} {:<40} {:<32}", "status", "rule", "hash");
+            for entry in report.directories.iter().flat_map(|report| &report.entries)
+                .filter(|entry| entry.status != SyncStatus::Skipped) {
+                println!("{:<8} {:<40} {:<32}", entry.status.as_str(), entry.name,
+                         entry.hash.as_deref().or(entry.error.as_deref()).unwrap_or_default());
+            }
+            for failed in report.failed_directories() {
+                println!("{:<8} {:<40} {}", "failed", failed.directory, failed.error.as_deref().unwrap_or_default());
+            }
+            println!("{} added, {} updated, {} unchanged, {} failed, {} pruned",
+                     report.count(SyncStatus::Added), report.count(SyncStatus::Updated),
+                     report.count(SyncStatus::Skipped), report.count(SyncStatus::Failed),
+                     report.count(SyncStatus::Pruned));
+
+            if let Some(manifest) = sub.get_one::<String>("manifest") {
+                std::fs::write(manifest, serde_json::to_string_pretty(&report)?)
+                    .with_context(|| format!("Failed to write sync manifest: {}", manifest))?;
+            }
+
+            let failed = report.failed_directories().count();
+            if failed > 0 {
+                return Err(anyhow::anyhow!("{} of {} directories could not be synced", failed, directories.len()));
+            }
+            Ok(())
+        }
+        Some(("prune", sub)) => {
+            let directory = sub.get_one::<String>("directory").unwrap();
+            let dry_run = sub.get_flag("dry-run");
+
+            let hook = super::open_database(matches).await?;
+            let deleted = prune_missing_rules(&hook, directory, dry_run).await?;
+            print_deleted_rules(&deleted, dry_run);
+            warn_skipped(&hook);
+            Ok(())
+        }
+        Some(("stats", sub)) => {
+            let hook = super::open_database(matches).await?;
+            let stats = hook.get_database_stats().await?;
+
+            println!("Database:      {}", stats.database_path);
+            println!("Rules:         {}", stats.yara_rules_count);
+            println!("Metadata:      {}", stats.metadata_entries_count);
+            println!("Threat intel:  {}", stats.threat_intel_count);
+            if stats.rule_blob_count > 0 {
+                println!("Rule blobs:    {} (dedup ratio {:.2})", stats.rule_blob_count, stats.dedup_ratio());
+                println!("Blob bytes:    {} stored, {} uncompressed ({} of {} blobs compressed)",
+                         stats.blob_bytes.stored, stats.blob_bytes.uncompressed,
+                         stats.blob_bytes.compressed_blobs, stats.rule_blob_count);
+            }
+
+            if sub.get_flag("by-source") {
+                println!();
+                println!("{:<50} {:>8} {:>8}  last updated", "source", "rules", "intel");
+                for (source, counts) in hook.stats_by_source().await? {
+                    let last_updated = counts.last_updated
+                        .map_or("-".to_string(), |t| t.format("%Y-%m-%d %H:%M").to_string());
+                    println!("{:<50} {:>8} {:>8}  {}", source, counts.rules, counts.threat_intel, last_updated);
+                }
+            }
+            warn_skipped(&hook);
+            Ok(())
+        }
+        Some(("verify", sub)) => {
+            // Unreadable rules are reported below, so hash checks skip them
+            let hook = super::open_database(matches).await?.with_skip_corrupt_records(true);
+
+            let mut corrupt = hook.find_corrupt_records().await?;
+            for record in &corrupt {
+                println!("CORRUPT {} {}: {}", record.table, record.key, record.error);
+            }
+            if sub.get_flag("repair") && !corrupt.is_empty() {
+                let moved = hook.quarantine_corrupt_records(&corrupt).await?;
+                println!("Moved {} corrupt record(s) into corrupt_records", moved);
+                corrupt.clear();
+            }
+
+            let mismatches = hook.verify_rule_hashes().await?;
+            for mismatch in &mismatches {
+                println!("MISMATCH {} ({}): stored {} actual {}",
+                         mismatch.name, mismatch.rule_id, mismatch.stored_hash, mismatch.actual_hash);
+            }
+
+            if corrupt.is_empty() && mismatches.is_empty() {
+                println!("All records readable and rule hashes verified");
+                return Ok(());
+            }
+            Err(anyhow::anyhow!("{} unreadable record(s), {} rule(s) failed hash verification",
+                                corrupt.len(), mismatches.len()))
+        }
+        Some(("export", sub)) => {
+            let file = sub.get_one::<String>("file").unwrap();
+            let format: ExportFormat = sub.get_one::<String>("format").unwrap().parse()?;
+
+            let hook = super::open_database(matches).await?;
+            let counts = if file == "-" {
+                hook.export_database(std::io::stdout().lock(), format).await?
+            } else {
+                let output = std::fs::File::create(file)
+                    .with_context(|| format!("Failed to create export file: {}", file))?;
+                hook.export_database(output, format).await?
+            };
+
+            eprintln!("Exported {} rules, {} metadata entries, {} indicators",
+                      counts.rules, counts.metadata, counts.threat_intel);
+            warn_skipped(&hook);
+            Ok(())
+        }
+        Some(("import", sub)) => {
+            let file = sub.get_one::<String>("file").unwrap();
+            let format: ExportFormat = sub.get_one::<String>("format").unwrap().parse()?;
+
+            let hook = super::open_database(matches).await?;
+            let counts = if file == "-" {
+                hook.import_database(std::io::stdin().lock(), format).await?
+            } else {
+                let input = std::fs::File::open(file)
+                    .with_context(|| format!("Failed to open import file: {}", file))?;
+                hook.import_database(std::io::BufReader::new(input), format).await?
+            };
+
+            println!("Imported {} rules, {} metadata entries, {} indicators",
+                     counts.rules, counts.metadata, counts.threat_intel);
+            Ok(())
+        }
+        Some(("delete", sub)) => {
+            let dry_run = sub.get_flag("dry-run");
+
+            let hook = super::open_database(matches).await?;
+            let deleted = if let Some(tag) = sub.get_one::<String>("tag") {
+                hook.delete_rules_by_tag(tag, dry_run).await?
+            } else if let Some(feed) = sub.get_one::<String>("feed") {
+                hook.delete_rules_by_source(feed, dry_run).await?
+            } else {
+                let severity = sub.get_one::<String>("severity").unwrap().parse()?;
+                hook.delete_rules_by_severity(severity, dry_run).await?
+            };
+
+            print_deleted_rules(&deleted, dry_run);
+            warn_skipped(&hook);
+            Ok(())
+        }
+        Some(("migrate-blobs", _)) => {
+            let hook = super::open_database(matches).await?;
+            let migrated = hook.migrate_rule_blobs().await?;
+            let stats = hook.get_database_stats().await?;
+
+            println!("Moved {} rule bodies into blobs", migrated);
+            println!("{} blob-backed rules share {} blobs (dedup ratio {:.2})",
+                     stats.blob_backed_rules, stats.rule_blob_count, stats.dedup_ratio());
+            Ok(())
+        }
+        Some(("migrate-intel", _)) => {
+            let hook = super::open_database(matches).await?;
+            let migration = hook.migrate_intel_keys().await?;
+
+            println!("Re-keyed {} indicators, merged {} into records of the same type and value",
+                     migration.rekeyed, migration.merged);
+            Ok(())
+        }
+        Some(("mitre-map", sub)) => {
+            let file = Path::new(sub.get_one::<String>("file").unwrap());
+
+            let hook = super::open_database(matches).await?;
+            let report = hook.apply_mitre_mapping(file).await?;
+
+            println!("{} mapping entries: {} rules updated, {} already up to date, {} entries matched no rule",
+                     report.entries, report.rules_updated, report.rules_unchanged,
+                     report.unmatched_entries.len());
+            for name in &report.unmatched_entries {
+                println!("  no rule named {}", name);
+            }
+            Ok(())
+        }
+        Some(("name-conflicts", sub)) => {
+            let hook = super::open_database(matches).await?;
+
+            if sub.get_flag("rename") {
+                let renamed = hook.resolve_name_conflicts().await?;
+                for rename in &renamed {
+                    println!("RENAMED {} ({}): {} -> {}", rename.name, rename.id, rename.from, rename.to);
+                }
+                println!("Renamed {} conflicting declaration(s)", renamed.len());
+                return Ok(());
+            }
+
+            let conflicts = hook.find_name_conflicts().await?;
+            for conflict in &conflicts {
+                println!("CONFLICT {}: {}", conflict.name, conflict.rule_ids.join(", "));
+            }
+            if conflicts.is_empty() {
+                println!("Every YARA rule name is declared once");
+                return Ok(());
+            }
+            Err(anyhow::anyhow!("{} YARA rule name(s) declared more than once; `db name-conflicts --rename` resolves them",
+                                conflicts.len()))
+        }
+        _ => unreachable!("subcommand_required is set"),
+    }
+}
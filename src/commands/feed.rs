@@ -0,0 +1,68 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+
+use crate::hooks::{diff_feed, ExportFormat, FeedStatus};
+
+pub fn command() -> Command {
+    Command::new("feed")
+        .about("Compare the database with upstream rule feeds")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("diff")
+                .about("Show how the database differs from a rule bundle at a URL, without changing it")
+                .arg(
+                    Arg::new("url")
+                        .value_name("URL")
+                        .help("Rule bundle in `db export` format")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Layout of the bundle")
+                        .value_parser(["ndjson", "json"])
+                        .default_value("json"),
+                )
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .help("Also list unchanged rules")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print the comparison as JSON")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("diff", sub)) => {
+            let url = sub.get_one::<String>("url").unwrap();
+            let format: ExportFormat = sub.get_one::<String>("format").unwrap().parse()?;
+
+            let hook = super::open_database(matches).await?;
+            let diff = diff_feed(&hook, url, format).await?;
+
+            if sub.get_flag("json") {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+                return Ok(());
+            }
+
+            println!("{:<10} {:<40} {:<32} {:<32}", "status", "rule", "upstream hash", "local hash");
+            for entry in diff.entries.iter().filter(|entry| sub.get_flag("all") || entry.status != FeedStatus::Unchanged) {
+                println!("{:<10} {:<40} {:<32} {:<32}", entry.status.as_str(), entry.name,
+                         entry.upstream_hash.as_deref().unwrap_or("-"), entry.local_hash.as_deref().unwrap_or("-"));
+            }
+            println!("{} new upstream, {} modified, {} local only, {} unchanged",
+                     diff.count(FeedStatus::New), diff.count(FeedStatus::Modified),
+                     diff.count(FeedStatus::LocalOnly), diff.count(FeedStatus::Unchanged));
+            Ok(())
+        }
+        _ => unreachable!("subcommand_required is set"),
+    }
+}
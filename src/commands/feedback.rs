@@ -0,0 +1,48 @@
+use anyhow::Result;
+use clap::{Arg, ArgGroup, ArgMatches, Command};
+use log::info;
+
+pub fn command() -> Command {
+    Command::new("feedback")
+        .about("Record an analyst verdict on a rule's finding")
+        .arg(
+            Arg::new("rule-id")
+                .value_name("RULE_ID")
+                .help("Id of the rule that produced the finding")
+                .required(true),
+        )
+        .arg(
+            Arg::new("false-positive")
+                .long("false-positive")
+                .help("The finding was a false positive")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("true-positive")
+                .long("true-positive")
+                .help("The finding was a genuine detection")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .group(
+            ArgGroup::new("verdict")
+                .args(["false-positive", "true-positive"])
+                .required(true),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let rule_id = matches.get_one::<String>("rule-id").unwrap();
+    let hook = super::open_database(matches).await?;
+
+    let metadata = if matches.get_flag("false-positive") {
+        hook.record_false_positive(rule_id).await?
+    } else {
+        hook.record_true_positive(rule_id).await?
+    };
+    let feedback = hook.get_rule_feedback(rule_id).await?;
+
+    info!("Rule {}: {} true positives, {} false positives, false positive rate {:.1}%",
+          rule_id, feedback.true_positives, feedback.false_positives,
+          metadata.false_positive_rate * 100.0);
+    Ok(())
+}
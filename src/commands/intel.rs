@@ -0,0 +1,193 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use log::info;
+
+use std::path::Path;
+
+use crate::hooks::indicator_query::IndicatorQuery;
+use crate::hooks::{fetch_taxii, import_hash_list};
+
+pub fn command() -> Command {
+    Command::new("intel")
+        .about("Manage threat intelligence indicators")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("taxii-pull")
+                .about("Import indicators from a TAXII 2.1 collection")
+                .arg(
+                    Arg::new("server")
+                        .long("server")
+                        .value_name("URL")
+                        .help("TAXII API root URL")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("collection")
+                        .long("collection")
+                        .value_name("ID")
+                        .help("TAXII collection identifier")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("api-key")
+                        .long("api-key")
+                        .value_name("KEY")
+                        .help("Bearer token for the TAXII server"),
+                ),
+        )
+        .subcommand(
+            Command::new("search")
+                .about("Find indicators by IP or CIDR range, domain pattern or substring")
+                .arg(
+                    Arg::new("query")
+                        .value_name("QUERY")
+                        .help("e.g. 10.0.0.0/8, *.evil.com, evil.com or part of a hash")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("type")
+                        .long("type")
+                        .value_name("TYPE")
+                        .help("Only indicators of this type (sha256, filename, ipv4, domain, ...)"),
+                )
+                .arg(
+                    Arg::new("exact")
+                        .long("exact")
+                        .help("Only indicators whose value is exactly QUERY; a keyed lookup instead of \
+                               a scan of every indicator")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print indicators as NDJSON")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("prune")
+                .about("Remove indicators that haven't been seen recently")
+                .arg(
+                    Arg::new("days")
+                        .long("days")
+                        .value_name("DAYS")
+                        .help("Remove indicators last seen more than this many days ago")
+                        .value_parser(clap::value_parser!(i64).range(0..))
+                        .required(true),
+                )
+                .arg(super::dry_run_arg()),
+        )
+        .subcommand(
+            Command::new("delete")
+                .about("Delete one indicator")
+                .arg(
+                    Arg::new("id")
+                        .value_name("ID")
+                        .help("Indicator id, <type>:<value> as in `intel search --json` output")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("import-hashes")
+                .about("Import a list of SHA-256 hashes as indicators")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("File with one SHA-256 hash per line")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("generate-rule")
+                        .long("generate-rule")
+                        .help("Also store YARA rules matching the hashes")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("taxii-pull", sub)) => {
+            let server = sub.get_one::<String>("server").unwrap();
+            let collection = sub.get_one::<String>("collection").unwrap();
+            let api_key = sub.get_one::<String>("api-key").map(String::as_str);
+
+            let hook = super::open_database(matches).await?;
+            let report = fetch_taxii(&hook, server, collection, api_key).await?;
+
+            info!("Fetched {} objects from {}: {} new indicators, {} merged, {} skipped",
+                  report.objects_fetched, report.collection, report.indicators_added,
+                  report.indicators_merged, report.objects_skipped);
+            Ok(())
+        }
+        Some(("search", sub)) => {
+            let query = sub.get_one::<String>("query").unwrap();
+            let indicator_type = sub.get_one::<String>("type").map(String::as_str);
+
+            let hook = super::open_database(matches).await?;
+            let mut indicators = match (IndicatorQuery::parse(query), indicator_type) {
+                _ if sub.get_flag("exact") => hook.get_threat_intel_exact(query).await?
+                    .into_iter()
+                    .filter(|indicator| indicator_type.is_none_or(|wanted| indicator.indicator_type.eq_ignore_ascii_case(wanted.trim())))
+                    .collect(),
+                (IndicatorQuery::Network(_), None) => hook.get_indicators_in_cidr(query).await?,
+                _ => hook.get_threat_intel_by_value(query, indicator_type).await?,
+            };
+            indicators.sort_by(|a, b| a.indicator_type.cmp(&b.indicator_type).then(a.value.cmp(&b.value)));
+
+            for indicator in &indicators {
+                if sub.get_flag("json") {
+                    println!("{}", serde_json::to_string(indicator)?);
+                } else {
+                    println!("{:<8} {:<48} {:>5.2}  {}", indicator.indicator_type, indicator.value,
+                             indicator.confidence, indicator.source_feeds.join(","));
+                }
+            }
+            info!("{} indicators match {}", indicators.len(), query);
+            Ok(())
+        }
+        Some(("prune", sub)) => {
+            let days = *sub.get_one::<i64>("days").unwrap();
+            let dry_run = sub.get_flag("dry-run");
+
+            let hook = super::open_database(matches).await?;
+            let removed = hook.cleanup_old_indicators(days, dry_run).await?;
+            for indicator in &removed {
+                println!("{:<36} {:<8} {:<48} {}", indicator.id, indicator.indicator_type, indicator.value,
+                         indicator.last_seen.format("%Y-%m-%d %H:%M"));
+            }
+            if dry_run {
+                println!("{} indicators would be removed (dry run)", removed.len());
+            } else {
+                println!("Removed {} indicators", removed.len());
+            }
+            Ok(())
+        }
+        Some(("delete", sub)) => {
+            let id = sub.get_one::<String>("id").unwrap();
+
+            let hook = super::open_database(matches).await?;
+            if !hook.delete_threat_intel(id).await? {
+                return Err(anyhow::anyhow!("No indicator with id {}", id));
+            }
+
+            println!("Deleted indicator {}", id);
+            Ok(())
+        }
+        Some(("import-hashes", sub)) => {
+            let file = Path::new(sub.get_one::<String>("file").unwrap());
+
+            let hook = super::open_database(matches).await?;
+            let report = import_hash_list(&hook, file, sub.get_flag("generate-rule")).await?;
+
+            info!("Imported hashes from {}: {} new indicators, {} merged, {} duplicates, {} invalid lines",
+                  file.display(), report.indicators_added, report.indicators_merged,
+                  report.duplicates, report.invalid_lines);
+            for rule in &report.rules_generated {
+                info!("Generated rule: {}", rule);
+            }
+            Ok(())
+        }
+        _ => unreachable!("subcommand_required is set"),
+    }
+}
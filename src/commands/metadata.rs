@@ -0,0 +1,50 @@
+use anyhow::{Context, Result};
+use clap::{Arg, ArgMatches, Command};
+
+use crate::hooks::MetadataFormat;
+
+pub fn command() -> Command {
+    Command::new("metadata")
+        .about("Export rule detection metrics for dashboards")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("export")
+                .about("Write every rule's detection counts, false positive rate and derived rates")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("Output file, or - for stdout")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("json is one array of objects; csv has a header row and one row per rule")
+                        .value_parser(["json", "csv"])
+                        .default_value("json"),
+                ),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("export", sub)) => {
+            let file = sub.get_one::<String>("file").unwrap();
+            let format: MetadataFormat = sub.get_one::<String>("format").unwrap().parse()?;
+
+            let hook = super::open_database(matches).await?;
+            let rows = if file == "-" {
+                hook.export_metadata(std::io::stdout().lock(), format).await?
+            } else {
+                let output = std::fs::File::create(file)
+                    .with_context(|| format!("Failed to create metadata export file: {}", file))?;
+                hook.export_metadata(output, format).await?
+            };
+
+            eprintln!("Exported metadata of {} rules", rows);
+            Ok(())
+        }
+        _ => unreachable!("subcommand_required is set"),
+    }
+}
@@ -0,0 +1,61 @@
+pub mod audit;
+pub mod db;
+pub mod feed;
+pub mod feedback;
+pub mod intel;
+pub mod metadata;
+pub mod package;
+pub mod rule;
+pub mod show_command;
+pub mod tui;
+pub mod validate_results;
+pub mod version;
+
+use anyhow::Result;
+use clap::{Arg, ArgMatches};
+
+use crate::config::PyroConfig;
+use crate::hooks::{initialize_yara_rules_hook, YaraRulesRedbHook};
+
+/// Dispatches a subcommand; scans run when no subcommand is given.
+pub async fn run(name: &str, matches: &ArgMatches) -> Result<()> {
+    match name {
+        "audit" => audit::run(matches).await,
+        "db" => db::run(matches).await,
+        "feed" => feed::run(matches).await,
+        "feedback" => feedback::run(matches).await,
+        "intel" => intel::run(matches).await,
+        "metadata" => metadata::run(matches).await,
+        "package" => package::run(matches).await,
+        "rule" => rule::run(matches).await,
+        "show-command" => show_command::run(matches).await,
+        "tui" => tui::run(matches).await,
+        "validate-results" => validate_results::run(matches).await,
+        "version" => version::run(matches).await,
+        other => Err(anyhow::anyhow!("Unknown subcommand: {}", other)),
+    }
+}
+
+/// Opens the `--db` database with the `database` settings from `--config`.
+async fn open_database(matches: &ArgMatches) -> Result<YaraRulesRedbHook> {
+    let config = PyroConfig::load(matches.get_one::<String>("config").unwrap())?;
+    let db_path = matches.get_one::<String>("db").unwrap();
+
+    Ok(initialize_yara_rules_hook(db_path).await?
+        .with_integrity_policy(config.database.integrity_policy)
+        .with_duplicate_name_policy(config.database.duplicate_rule_names)
+        .with_content_dedup(config.database.dedup_rule_bodies)
+        .with_rule_compression(config.database.compress_rule_bodies)
+        .with_severity_from_tags(config.database.severity_from_tags.clone())
+        .with_skip_corrupt_records(config.database.skip_corrupt_records)
+        .with_clock(crate::clock::from_env()?))
+}
+
+/// `--dry-run` for commands that delete records: they list what would go
+/// and leave the database alone.
+fn dry_run_arg() -> Arg {
+    Arg::new("dry-run")
+        .long("dry-run")
+        .help("List what would be deleted without changing the database")
+        .action(clap::ArgAction::SetTrue)
+}
@@ -0,0 +1,82 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use std::path::Path;
+
+use crate::config::PyroConfig;
+use crate::executor::PyroExecutor;
+use crate::package::{PackageMetadata, THOR_PACKAGE_FILE};
+
+pub fn command() -> Command {
+    Command::new("package")
+        .about("Inspect and update the cached Thor package")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("info")
+                .about("Show the cached package and whether the server has a newer one")
+                .arg(
+                    Arg::new("offline")
+                        .long("offline")
+                        .help("Do not contact the Pyro server")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("update")
+                .about("Download the package if the server has a newer version")
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .help("Download even if the cached package is current")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let config = PyroConfig::load(matches.get_one::<String>("config").unwrap())?;
+    let executor = PyroExecutor::new(config)?;
+    let package = Path::new(THOR_PACKAGE_FILE);
+
+    match matches.subcommand() {
+        Some(("info", sub)) => {
+            if !package.exists() {
+                println!("No cached Thor package at {}", package.display());
+                return Ok(());
+            }
+
+            let metadata = PackageMetadata::for_package(package).await?;
+            println!("Package:     {}", package.display());
+            println!("Version:     {}", metadata.version.as_deref().or(metadata.etag.as_deref()).unwrap_or("unknown"));
+            println!("SHA-256:     {}", metadata.sha256);
+            println!("Size:        {} bytes", metadata.size);
+            if let Some(downloaded_at) = metadata.downloaded_at {
+                println!("Downloaded:  {}", downloaded_at.to_rfc3339());
+            }
+
+            if !sub.get_flag("offline") {
+                let status = match executor.check_package_update(&metadata).await {
+                    Ok(Some(true)) => "newer version available (run `pyro-thor package update`)".to_string(),
+                    Ok(Some(false)) => "up to date".to_string(),
+                    Ok(None) => "unknown (server sent no version metadata)".to_string(),
+                    Err(e) => format!("unavailable ({})", e),
+                };
+                println!("Server:      {}", status);
+            }
+            Ok(())
+        }
+        Some(("update", sub)) => {
+            let cached = if package.exists() && !sub.get_flag("force") {
+                Some(PackageMetadata::for_package(package).await?)
+            } else {
+                None
+            };
+
+            match executor.download_thor_package(cached.as_ref()).await? {
+                Some(metadata) => println!("Downloaded Thor package ({} bytes, sha256 {})", metadata.size, metadata.sha256),
+                None => println!("Thor package is already up to date"),
+            }
+            Ok(())
+        }
+        _ => unreachable!("subcommand_required is set"),
+    }
+}
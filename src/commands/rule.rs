@@ -0,0 +1,253 @@
+use anyhow::{Context, Result};
+use clap::{Arg, ArgGroup, ArgMatches, Command};
+use std::io::Read;
+
+use crate::hooks::yara_rules_redb::content_hash;
+use crate::hooks::yara_source::{split_rules, validate_rule_content};
+use crate::hooks::YaraRule;
+use crate::severity::Severity;
+
+pub fn command() -> Command {
+    Command::new("rule")
+        .about("Manage individual YARA rules")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("add")
+                .about("Store a single YARA rule read from stdin")
+                .arg(
+                    Arg::new("name")
+                        .long("name")
+                        .value_name("NAME")
+                        .help("Rule name; defaults to the name in the rule source"),
+                )
+                .arg(
+                    Arg::new("severity")
+                        .long("severity")
+                        .value_name("SEVERITY")
+                        .value_parser(["info", "low", "medium", "high", "critical"])
+                        .default_value("medium"),
+                )
+                .arg(
+                    Arg::new("tag")
+                        .long("tag")
+                        .value_name("TAG")
+                        .help("Tag to attach; may be repeated")
+                        .action(clap::ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("tier")
+                        .long("tier")
+                        .value_name("TIER")
+                        .help("Cheapest scan tier that runs the rule")
+                        .value_parser(["quick", "standard", "deep"])
+                        .default_value("standard"),
+                )
+                .arg(
+                    Arg::new("author")
+                        .long("author")
+                        .value_name("AUTHOR")
+                        .default_value("Unknown"),
+                )
+                .arg(
+                    Arg::new("unchecked")
+                        .long("unchecked")
+                        .help("Store a draft that isn't well-formed YARA yet; needs --name when no rule \
+                               declaration can be found")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("list")
+                .about("List stored rules a page at a time, in id order")
+                .arg(
+                    Arg::new("offset")
+                        .long("offset")
+                        .value_name("N")
+                        .help("Number of rules to skip")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_name("N")
+                        .help("Maximum number of rules to list")
+                        .value_parser(clap::builder::RangedU64ValueParser::<usize>::new().range(1..))
+                        .default_value("100"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print rules as NDJSON, including their source")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("search")
+                .about("List the rules with a tag or covering a MITRE ATT&CK technique or tactic")
+                .arg(
+                    Arg::new("tag")
+                        .long("tag")
+                        .value_name("TAG")
+                        .help("Tag to look for, compared case-insensitively"),
+                )
+                .arg(
+                    Arg::new("technique")
+                        .long("technique")
+                        .value_name("ID")
+                        .help("ATT&CK technique id, e.g. T1059.001; sub-techniques match only their own id"),
+                )
+                .arg(
+                    Arg::new("tactic")
+                        .long("tactic")
+                        .value_name("ID")
+                        .help("ATT&CK tactic id, e.g. TA0002"),
+                )
+                .group(
+                    ArgGroup::new("criterion")
+                        .args(["tag", "technique", "tactic"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print rules as NDJSON, including their source")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("delete")
+                .about("Delete a rule by id, with its metadata")
+                .arg(
+                    Arg::new("id")
+                        .value_name("ID")
+                        .help("Rule id, as printed by `rule add` and shown in the audit log")
+                        .required(true),
+                ),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("add", sub)) => {
+            let mut content = String::new();
+            std::io::stdin().read_to_string(&mut content)
+                .context("Failed to read rule from stdin")?;
+
+            let hook = super::open_database(matches).await?;
+            let rule = stdin_rule(sub, content, hook.now())?;
+            if sub.get_flag("unchecked") {
+                hook.store_yara_rule_unchecked(&rule).await?;
+            } else {
+                hook.store_yara_rule(&rule).await?;
+            }
+
+            println!("{}", rule.id);
+            Ok(())
+        }
+        Some(("list", sub)) => {
+            let offset = *sub.get_one::<usize>("offset").unwrap();
+            let limit = *sub.get_one::<usize>("limit").unwrap();
+
+            let hook = super::open_database(matches).await?;
+            let rules = hook.list_yara_rules_paged(offset, limit).await?;
+            print_rules(&rules, sub.get_flag("json"))?;
+            if rules.len() == limit {
+                eprintln!("More rules may follow; continue with --offset {}", offset + limit);
+            }
+            Ok(())
+        }
+        Some(("search", sub)) => {
+            let hook = super::open_database(matches).await?;
+            let (rules, criterion) = if let Some(tag) = sub.get_one::<String>("tag") {
+                (hook.search_rules_by_tag(tag).await?, format!("tagged {}", tag))
+            } else if let Some(technique) = sub.get_one::<String>("technique") {
+                (hook.get_rules_by_mitre_technique(technique).await?, format!("covering technique {}", technique))
+            } else {
+                let tactic = sub.get_one::<String>("tactic").unwrap();
+                (hook.get_rules_by_mitre_tactic(tactic).await?, format!("covering tactic {}", tactic))
+            };
+            print_rules(&rules, sub.get_flag("json"))?;
+            eprintln!("{} rules {}", rules.len(), criterion);
+            Ok(())
+        }
+        Some(("delete", sub)) => {
+            let id = sub.get_one::<String>("id").unwrap();
+
+            let hook = super::open_database(matches).await?;
+            if !hook.delete_yara_rule(id).await? {
+                return Err(anyhow::anyhow!("No rule with id {}", id));
+            }
+
+            println!("Deleted rule {}", id);
+            Ok(())
+        }
+        _ => unreachable!("subcommand_required is set"),
+    }
+}
+
+/// Prints one line per rule: NDJSON with `json`, otherwise id, severity,
+/// name and source in columns.
+fn print_rules(rules: &[YaraRule], json: bool) -> Result<()> {
+    for rule in rules {
+        if json {
+            println!("{}", serde_json::to_string(rule)?);
+        } else {
+            println!("{:<36} {:<8} {:<40} {}", rule.id, rule.severity, rule.name, rule.source);
+        }
+    }
+    Ok(())
+}
+
+/// Builds the rule to store, rejecting input that isn't exactly one
+/// structurally well-formed YARA rule unless `--unchecked` is given.
+fn stdin_rule(matches: &ArgMatches, content: String, now: chrono::DateTime<chrono::Utc>) -> Result<YaraRule> {
+    let source = split_rules(&content).unwrap_or_default();
+    if !matches.get_flag("unchecked") {
+        validate_rule_content(&content).context("Rule source failed the YARA structure check")?;
+        if source.rules.len() > 1 {
+            return Err(anyhow::anyhow!(
+                "Expected one rule on stdin, found {}; use `db sync` for rule files", source.rules.len()
+            ));
+        }
+    }
+
+    let severity: Severity = matches.get_one::<String>("severity").unwrap().parse()?;
+    let name = matches.get_one::<String>("name").cloned()
+        .or_else(|| source.rules.first().map(|block| block.name.clone()))
+        .context("No rule name found on stdin; pass --name")?;
+
+    Ok(YaraRule {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        hash: content_hash(&content),
+        content,
+        author: matches.get_one::<String>("author").unwrap().clone(),
+        description: "Added from stdin".to_string(),
+        tags: matches.get_many::<String>("tag").map(|tags| tags.cloned().collect()).unwrap_or_default(),
+        severity: severity.to_string(),
+        created_at: now,
+        updated_at: now,
+        version: "1.0".to_string(),
+        source: "stdin".to_string(),
+        mitre_tactics: vec![],
+        mitre_techniques: vec![],
+        threat_actors: vec![],
+        malware_families: vec![],
+        scan_tier: matches.get_one::<String>("tier").unwrap().parse()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_limit_must_be_positive() {
+        let parse = |limit: &str| command().try_get_matches_from(["rule", "list", "--limit", limit]);
+        assert!(parse("0").is_err());
+        let matches = parse("10").unwrap();
+        let (_, list) = matches.subcommand().unwrap();
+        assert_eq!(list.get_one::<usize>("limit"), Some(&10));
+    }
+}
@@ -0,0 +1,161 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use std::path::{Path, PathBuf};
+
+use crate::config::PyroConfig;
+use crate::platform::PlatformInfo;
+use crate::scanner::{existing_exclude_paths, thor_args, ThorInvocation, ARCHIVE_EXTRACT_DIR, REDB_EXPORT_DIR, THOR_OUTPUT_FILE};
+
+/// Stands in for the per-scan temp dir, which only exists during a scan.
+const TEMP_PLACEHOLDER: &str = "<temp>";
+
+pub fn command() -> Command {
+    Command::new("show-command")
+        .about("Print the Thor command line a scan would run, without preparing anything")
+        .arg(
+            Arg::new("scan-path")
+                .short('p')
+                .long("path")
+                .value_name("PATH")
+                .help("Path to scan; repeat for several")
+                .action(clap::ArgAction::Append)
+                .default_value("/"),
+        )
+        .arg(
+            Arg::new("redb-enabled")
+                .long("redb-enabled")
+                .help("Show the command for a scan with ReDB enabled")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("enterprise-mode")
+                .long("enterprise-mode")
+                .help("Show the command for an enterprise mode scan")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("thor-output-file")
+                .long("thor-output-file")
+                .help("Show the command with Thor writing its own results file")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let mut config = PyroConfig::load(matches.get_one::<String>("config").unwrap())?;
+    if matches.get_flag("thor-output-file") {
+        config.scanning.thor_output_file = true;
+    }
+
+    let (binary, invocation) = planned_invocation(
+        &config,
+        &matches.get_many::<String>("scan-path").unwrap().cloned().collect::<Vec<_>>(),
+        matches.get_flag("enterprise-mode"),
+        matches.get_flag("redb-enabled"),
+    );
+
+    println!("{}", quoted_argv(&config, binary, &invocation).join(" "));
+
+    if config.scanning.expand_archives {
+        eprintln!("Note: with archive expansion, `--path {}/{}` is added when the scan path contains archives",
+                  TEMP_PLACEHOLDER, ARCHIVE_EXTRACT_DIR);
+    }
+    Ok(())
+}
+
+/// The Thor binary and arguments `run_scan` would use for this config,
+/// worked out without the package: relative config paths are shown where
+/// they resolve in a freshly extracted one.
+fn planned_invocation(config: &PyroConfig, scan_paths: &[String], enterprise_mode: bool, redb_enabled: bool) -> (PathBuf, ThorInvocation) {
+    let temp_path = Path::new(TEMP_PLACEHOLDER);
+    let in_package = |configured: &str| {
+        (!configured.is_empty()).then(|| match Path::new(configured) {
+            path if path.is_absolute() => path.to_path_buf(),
+            path => temp_path.join("Thor").join(path),
+        })
+    };
+
+    let custom_signatures = if redb_enabled && config.database.export_rules {
+        Some(temp_path.join(REDB_EXPORT_DIR))
+    } else {
+        in_package(&config.thor.rules_path)
+    };
+
+    let invocation = ThorInvocation {
+        license: in_package(&config.thor.license_path),
+        custom_signatures,
+        template: in_package(&config.thor.config_path),
+        enterprise_mode,
+        redb_optimized: redb_enabled,
+        json_file: config.scanning.thor_output_file.then(|| temp_path.join(THOR_OUTPUT_FILE)),
+        scan_paths: scan_paths.iter().map(PathBuf::from).collect(),
+        exclude_paths: existing_exclude_paths(
+            &config.scanning.exclude_paths.iter().map(PathBuf::from).collect::<Vec<_>>()
+        ),
+        max_file_size: config.scanning.max_file_size_bytes(),
+        rebase_dir: temp_path.to_path_buf(),
+    };
+
+    let binary = temp_path.join("Thor").join(PlatformInfo::detect().get_thor_binary_name());
+    (binary, invocation)
+}
+
+/// `binary` followed by the Thor arguments for `invocation`, each quoted
+/// for a POSIX shell.
+fn quoted_argv(config: &PyroConfig, binary: PathBuf, invocation: &ThorInvocation) -> Vec<String> {
+    std::iter::once(binary.into_os_string())
+        .chain(thor_args(&config.thor, invocation))
+        .map(|arg| shell_quote(&arg.to_string_lossy()))
+        .collect()
+}
+
+/// Quotes an argument for a POSIX shell when it contains anything special.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty() && arg.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The values following each occurrence of `flag` in `argv`.
+    fn values<'a>(argv: &'a [String], flag: &str) -> Vec<&'a str> {
+        argv.windows(2)
+            .filter(|pair| pair[0] == flag)
+            .map(|pair| pair[1].as_str())
+            .collect()
+    }
+
+    #[test]
+    fn default_config_plans_license_rules_paths_and_exclusions() {
+        let excluded = tempfile::tempdir().unwrap();
+        let excluded = excluded.path().to_str().unwrap().to_string();
+        let mut config = PyroConfig::default();
+        config.scanning.exclude_paths = vec![excluded.clone(), "/no/such/excluded/dir".to_string()];
+
+        let scan_paths = ["/srv/data".to_string(), "/srv/My Files".to_string()];
+        let (binary, invocation) = planned_invocation(&config, &scan_paths, false, false);
+        let argv = quoted_argv(&config, binary, &invocation);
+
+        assert!(argv[0].starts_with("'<temp>/Thor/thor-lite"), "{}", argv[0]);
+        assert_eq!(values(&argv, "--licensefile"), ["'<temp>/Thor/thor-lite-license.lic'"]);
+        assert_eq!(values(&argv, "--customsigs"), ["'<temp>/Thor/custom-signatures'"]);
+        assert_eq!(values(&argv, "--exclude-path"), [excluded.as_str()]);
+        assert_eq!(values(&argv, "--path"), ["/srv/data", "'/srv/My Files'"]);
+        assert_eq!(values(&argv, "--rebase-dir"), ["'<temp>'"]);
+    }
+
+    #[test]
+    fn only_special_arguments_are_quoted() {
+        assert_eq!(shell_quote("--path"), "--path");
+        assert_eq!(shell_quote("/srv/My Files"), "'/srv/My Files'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+use clap::{Arg, ArgMatches, Command};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::config::{PyroConfig, RiskConfig};
+use crate::findings::{parse_findings, Finding};
+use crate::hooks::indicator_query::wildcard_match;
+use crate::results_file;
+use crate::tui::Browser;
+
+pub fn command() -> Command {
+    Command::new("tui")
+        .about("Browse stored rules and the latest scan's findings in the terminal")
+        .arg(
+            Arg::new("results")
+                .long("results")
+                .value_name("FILE")
+                .help("Results file to browse; defaults to the newest file in the current \
+                       directory matching scanning.result_file_pattern"),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let config = PyroConfig::load(matches.get_one::<String>("config").unwrap())?;
+    let hook = super::open_database(matches).await?;
+    let rules = hook.list_yara_rules().await?;
+
+    let results = match matches.get_one::<String>("results") {
+        Some(file) => Some(PathBuf::from(file)),
+        None => latest_results_file(Path::new("."), &config.scanning.result_file_pattern)?,
+    };
+    let findings = match &results {
+        Some(path) => read_findings(path, &config.risk)?,
+        None => Vec::new(),
+    };
+
+    let browser = Browser::new(rules, findings, results.map(|path| path.display().to_string()));
+    tokio::task::spawn_blocking(move || browser.run()).await?
+}
+
+/// Most recently modified regular file in `directory` whose name matches
+/// `pattern`.
+fn latest_results_file(directory: &Path, pattern: &str) -> Result<Option<PathBuf>> {
+    let mut latest: Option<(std::time::SystemTime, PathBuf)> = None;
+    for entry in std::fs::read_dir(directory)
+        .with_context(|| format!("Failed to read {}", directory.display()))?
+    {
+        let entry = entry?;
+        if !wildcard_match(pattern.as_bytes(), entry.file_name().to_string_lossy().as_bytes())
+            || !entry.file_type()?.is_file()
+        {
+            continue;
+        }
+        let modified = entry.metadata()?.modified()?;
+        if latest.as_ref().is_none_or(|(newest, _)| modified > *newest) {
+            latest = Some((modified, entry.path()));
+        }
+    }
+    Ok(latest.map(|(_, path)| path))
+}
+
+/// Every finding in a results file, which may hold several documents back
+/// to back.
+fn read_findings(path: &Path, risk_config: &RiskConfig) -> Result<Vec<Finding>> {
+    let reader = results_file::open(path)
+        .with_context(|| format!("Failed to open results file: {}", path.display()))?;
+
+    let mut findings = Vec::new();
+    for document in serde_json::Deserializer::from_reader(reader).into_iter::<Value>() {
+        let document = document
+            .with_context(|| format!("{} is not valid JSON", path.display()))?;
+        findings.extend(parse_findings(document).into_iter().map(|finding| finding.with_risk(risk_config)));
+    }
+    Ok(findings)
+}
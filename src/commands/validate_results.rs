@@ -0,0 +1,67 @@
+use anyhow::{Context, Result};
+use clap::{Arg, ArgMatches, Command};
+use serde_json::Value;
+use std::path::Path;
+
+use crate::results_file;
+use crate::results_schema::{ResultsSchema, Violation};
+
+pub fn command() -> Command {
+    Command::new("validate-results")
+        .about("Check a results file against the embedded results schema")
+        .arg(
+            Arg::new("file")
+                .value_name("FILE")
+                .help("Results file, plain or gzipped")
+                .required(true),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("Print violations as NDJSON")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let file = matches.get_one::<String>("file").unwrap();
+    let json = matches.get_flag("json");
+
+    let schema = ResultsSchema::embedded();
+    let reader = results_file::open(Path::new(file))
+        .with_context(|| format!("Failed to open results file: {}", file))?;
+
+    // A results file may hold several documents back to back, as streamed
+    // Thor output does; each is checked on its own
+    let mut violations: Vec<(usize, Violation)> = Vec::new();
+    let mut documents = 0;
+    for document in serde_json::Deserializer::from_reader(reader).into_iter::<Value>() {
+        let document = document
+            .with_context(|| format!("{} is not valid JSON after {} document(s)", file, documents))?;
+        documents += 1;
+        violations.extend(schema.validate(&document).into_iter().map(|violation| (documents, violation)));
+    }
+    if documents == 0 {
+        return Err(anyhow::anyhow!("{} contains no results document", file));
+    }
+
+    for (document, violation) in &violations {
+        if json {
+            println!("{}", serde_json::json!({
+                "document": document,
+                "path": violation.path,
+                "message": violation.message,
+            }));
+        } else if documents > 1 {
+            println!("document {}: {}: {}", document, violation.path, violation.message);
+        } else {
+            println!("{}: {}", violation.path, violation.message);
+        }
+    }
+
+    if violations.is_empty() {
+        eprintln!("{}: {} document(s) match the results schema", file, documents);
+        return Ok(());
+    }
+    Err(anyhow::anyhow!("{} schema violation(s) in {}", violations.len(), file))
+}
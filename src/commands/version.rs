@@ -0,0 +1,63 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use serde::Serialize;
+
+use crate::hooks::yara_rules_redb::RULE_RECORD_VERSION;
+
+/// Identifies the exact build, for correlating field reports with binaries.
+#[derive(Debug, Serialize)]
+struct BuildInfo {
+    version: &'static str,
+    target: &'static str,
+    profile: &'static str,
+    git_commit: &'static str,
+    redb_version: &'static str,
+    bincode_version: &'static str,
+    serde_json_version: &'static str,
+    /// Layout of the bincode rule records stored in ReDB.
+    rule_record_version: u32,
+}
+
+impl BuildInfo {
+    fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            target: env!("BUILD_TARGET"),
+            profile: env!("BUILD_PROFILE"),
+            git_commit: env!("BUILD_GIT_COMMIT"),
+            redb_version: env!("BUILD_REDB_VERSION"),
+            bincode_version: env!("BUILD_BINCODE_VERSION"),
+            serde_json_version: env!("BUILD_SERDE_JSON_VERSION"),
+            rule_record_version: RULE_RECORD_VERSION,
+        }
+    }
+}
+
+pub fn command() -> Command {
+    Command::new("version")
+        .about("Show build details: version, target, profile, git commit and storage formats")
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("Print build details as a JSON object")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let info = BuildInfo::current();
+    if matches.get_flag("json") {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
+    println!("pyro-thor {}", info.version);
+    println!("Target:       {}", info.target);
+    println!("Profile:      {}", info.profile);
+    println!("Git commit:   {}", info.git_commit);
+    println!("redb:         {}", info.redb_version);
+    println!("bincode:      {}", info.bincode_version);
+    println!("serde_json:   {}", info.serde_json_version);
+    println!("Rule records: v{}", info.rule_record_version);
+    Ok(())
+}
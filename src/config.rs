@@ -1,22 +1,52 @@
+use crate::error::{ErrorCategory, PyroError};
+use crate::hooks::{DuplicateNamePolicy, IntegrityPolicy};
+use crate::severity::Severity;
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
 
+/// Shipped `thor.license_path`. Unlike a path the user chose, it may be
+/// missing from the package, and the scan then runs without a license.
+pub const DEFAULT_LICENSE_PATH: &str = "thor-lite-license.lic";
+
+/// Shipped `thor.rules_path`, skipped the same way when absent.
+pub const DEFAULT_RULES_PATH: &str = "custom-signatures";
+
+/// Shipped `thor.config_path`, skipped the same way when absent.
+pub const DEFAULT_THOR_CONFIG_PATH: &str = "config/thor.yml";
+
+/// Largest accepted `scanning.max_file_size_mb`: 1 TiB.
+const MAX_FILE_SIZE_MB_LIMIT: u64 = 1024 * 1024;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PyroConfig {
     pub thor: ThorConfig,
     pub pyro: PyroServerConfig,
     pub scanning: ScanConfig,
+    #[serde(default)]
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub gate: GateConfig,
+    #[serde(default)]
+    pub risk: RiskConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThorConfig {
     pub binary_path: String,
     pub license_path: String,
+    /// Custom signatures directory passed to Thor. Replaced by the ReDB
+    /// export when `database.export_rules` is set.
     pub rules_path: String,
+    /// Thor YAML config (scan template) passed to Thor.
     pub config_path: String,
     pub flags: Vec<String>,
+    /// Exit codes that mean the scan completed. Non-zero ones are logged as
+    /// warnings; any other code fails the scan.
+    #[serde(default = "default_success_exit_codes")]
+    pub success_exit_codes: Vec<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,15 +54,350 @@ pub struct PyroServerConfig {
     pub endpoint: String,
     pub api_key: Option<String>,
     pub timeout_seconds: u64,
+    /// Findings below this severity are left out of the upload (the local
+    /// results file is always complete). Findings whose Thor level has no
+    /// severity mapping are always uploaded.
+    #[serde(default)]
+    pub upload_min_severity: Option<Severity>,
+    /// Sends findings to different URLs by severity. When set, findings
+    /// are uploaded to every route whose filter they match instead of to
+    /// `/api/scan-results`; findings matching no route are not uploaded.
+    #[serde(default)]
+    pub upload_routes: Vec<UploadRoute>,
+    /// Proxy for all Pyro server requests, e.g. `http://proxy:3128`.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Extra headers sent with every Pyro server request.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// PEM file of CA certificates trusted for the Pyro server in addition
+    /// to the system store, e.g. an internal CA.
+    #[serde(default)]
+    pub ca_cert_path: Option<String>,
+    /// DANGER, lab use only: accept any server certificate, including
+    /// self-signed, expired or wrong-host ones. Disables TLS protection
+    /// against interception.
+    #[serde(default)]
+    pub danger_accept_invalid_certs: bool,
+    /// Finding keys renamed before upload (Thor name -> server name); keys
+    /// not listed are sent unchanged. The local results file keeps Thor's
+    /// names.
+    #[serde(default)]
+    pub upload_field_map: HashMap<String, String>,
+    /// How long an in-flight upload may keep going after Ctrl-C before it is
+    /// aborted and its results spooled to `spool_dir`.
+    #[serde(default = "default_upload_shutdown_grace_seconds")]
+    pub upload_shutdown_grace_seconds: u64,
+    /// Results whose upload was interrupted; re-sent before the next upload.
+    #[serde(default = "default_spool_dir")]
+    pub spool_dir: String,
+    #[serde(default)]
+    pub http_version: HttpVersion,
+    /// How long an unused connection stays in the pool; 0 keeps it until
+    /// the server closes it.
+    #[serde(default = "default_pool_idle_timeout_seconds")]
+    pub pool_idle_timeout_seconds: u64,
+    /// Idle connections kept per host. Uploads to one server rarely need
+    /// more than a few; 0 disables reuse.
+    #[serde(default = "default_pool_max_idle_per_host")]
+    pub pool_max_idle_per_host: usize,
+    /// TCP keep-alive interval for server connections; 0 leaves it unset.
+    #[serde(default = "default_tcp_keepalive_seconds")]
+    pub tcp_keepalive_seconds: u64,
+    /// Abort the Thor package download when no data arrives for this long;
+    /// 0 relies on `timeout_seconds` alone.
+    #[serde(default = "default_download_idle_timeout_seconds")]
+    pub download_idle_timeout_seconds: u64,
+    /// Expected SHA-256 of the downloaded Thor package, in hex. When unset,
+    /// the download only has to open as a zip archive.
+    #[serde(default)]
+    pub package_sha256: Option<String>,
+    /// How many times to retry the Thor package download after a
+    /// connection error, timeout, 5xx or 429 response (0 disables retries).
+    #[serde(default = "default_download_retries")]
+    pub download_retries: u32,
+    /// Wait before the first download retry, doubled for each one after.
+    #[serde(default = "default_download_retry_base_delay_ms")]
+    pub download_retry_base_delay_ms: u64,
+}
+
+/// One severity-filtered upload destination.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadRoute {
+    /// Shown in logs and used as the route's spool subdirectory.
+    pub name: String,
+    /// URL the route's findings are posted to; a path such as
+    /// `/api/collections/soc` is relative to `endpoint`.
+    pub url: String,
+    /// Lowest severity taken by this route; unset has no lower bound.
+    #[serde(default)]
+    pub min_severity: Option<Severity>,
+    /// Highest severity taken by this route; unset has no upper bound.
+    #[serde(default)]
+    pub max_severity: Option<Severity>,
+    /// Whether findings whose Thor level has no severity mapping go here.
+    #[serde(default)]
+    pub include_unmapped: bool,
+}
+
+impl UploadRoute {
+    /// Whether a finding of `severity` (`None` when unmapped) goes to this
+    /// route.
+    pub fn accepts(&self, severity: Option<Severity>) -> bool {
+        match severity {
+            Some(severity) => self.min_severity.is_none_or(|min| severity >= min)
+                && self.max_severity.is_none_or(|max| severity <= max),
+            None => self.include_unmapped,
+        }
+    }
+}
+
+/// HTTP version used for Pyro server requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpVersion {
+    /// HTTP/2 when the server offers it during the TLS handshake, otherwise
+    /// HTTP/1.1. Plain `http://` endpoints always use HTTP/1.1.
+    #[default]
+    Auto,
+    /// Always HTTP/1.1.
+    Http1,
+    /// HTTP/2 without negotiation, including over plain `http://` (h2c).
+    /// Fails against servers that don't speak HTTP/2.
+    Http2,
+}
+
+/// Format of the results file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Thor's JSON documents, pretty-printed unless `scanning.pretty_output`
+    /// is off or `--compact` is given.
+    #[default]
+    Json,
+    /// One row per finding, with a column for each top-level field.
+    Csv,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            other => Err(anyhow::anyhow!(
+                "Invalid output format '{}': expected json or csv", other
+            )),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanConfig {
-    pub output_format: String, // "json", "csv", "xml"
+    pub output_format: OutputFormat,
+    /// Base directory for the scan's temp dir; the system default when unset.
     pub temp_dir: Option<String>,
+    /// Directories the Thor package may be extracted under, checked after
+    /// resolving symlinks. Empty allows any location.
+    #[serde(default)]
+    pub allowed_extract_roots: Vec<String>,
     pub cleanup: bool,
     pub exclude_paths: Vec<String>,
+    /// Files larger than this are skipped by Thor; 0 leaves Thor's default.
     pub max_file_size_mb: u64,
+    /// Thor output larger than this is streamed to the results file instead of
+    /// being parsed in memory.
+    #[serde(default = "default_max_parse_buffer_mb")]
+    pub max_parse_buffer_mb: u64,
+    /// Threads used to extract the Thor package; 0 uses one per CPU.
+    #[serde(default)]
+    pub extract_workers: usize,
+    /// Largest uncompressed size allowed for one Thor package entry.
+    #[serde(default = "default_package_max_entry_mb")]
+    pub package_max_entry_mb: u64,
+    /// Largest total uncompressed size allowed for the Thor package.
+    #[serde(default = "default_package_max_total_mb")]
+    pub package_max_total_mb: u64,
+    /// How many times to re-run Thor after a retriable failure (0 disables retries).
+    #[serde(default)]
+    pub scan_retries: u32,
+    #[serde(default = "default_scan_retry_delay_seconds")]
+    pub scan_retry_delay_seconds: u64,
+    /// Thor exit codes treated as transient.
+    #[serde(default)]
+    pub retriable_exit_codes: Vec<i32>,
+    /// Case-insensitive stderr fragments treated as transient.
+    #[serde(default = "default_retriable_error_patterns")]
+    pub retriable_error_patterns: Vec<String>,
+    /// Rewrite the results file as indented JSON with sorted keys. Costs a
+    /// reparse, and a second pass over the file when output was streamed.
+    #[serde(default = "default_true")]
+    pub pretty_output: bool,
+    /// Read results from a file Thor writes (`--jsonfile`) instead of stdout.
+    #[serde(default)]
+    pub thor_output_file: bool,
+    /// Gzip the results file, appending `.gz` to its name.
+    #[serde(default)]
+    pub compress_output: bool,
+    /// How deep target discovery descends below a scan root.
+    #[serde(default = "default_max_walk_depth")]
+    pub max_walk_depth: usize,
+    /// Skip symlinks that resolve outside the scan root during target discovery.
+    #[serde(default = "default_true")]
+    pub skip_symlinks_outside_root: bool,
+    /// Exit code per worst finding severity when `--fail-on-match` is set.
+    /// Severities without an entry fall back to exit code 1.
+    #[serde(default = "default_severity_exit_codes")]
+    pub severity_exit_codes: BTreeMap<Severity, i32>,
+    /// Program and arguments run after results are written, with
+    /// `PYRO_RESULTS_PATH`, `PYRO_SCAN_UUID`, `PYRO_FINDINGS_COUNT`,
+    /// `PYRO_MAX_RISK`, `PYRO_THOR_EXIT_CODE` and `PYRO_SCAN_INCOMPLETE` set.
+    #[serde(default)]
+    pub post_scan_command: Vec<String>,
+    #[serde(default = "default_post_scan_timeout_seconds")]
+    pub post_scan_timeout_seconds: u64,
+    /// Fail the scan when the post-scan command fails or times out.
+    #[serde(default)]
+    pub post_scan_required: bool,
+    /// Extract archives found under the scan path and scan their contents.
+    #[serde(default)]
+    pub expand_archives: bool,
+    #[serde(default = "default_archive_max_depth")]
+    pub archive_max_depth: usize,
+    /// Cap on the bytes extracted from all archives together.
+    #[serde(default = "default_archive_max_total_mb")]
+    pub archive_max_total_mb: u64,
+    /// After each scan, keep at most this many result files matching
+    /// `result_file_pattern` in the output directory; 0 keeps them all.
+    #[serde(default)]
+    pub max_result_files: usize,
+    /// After each scan, delete result files matching `result_file_pattern`
+    /// last modified more than this many days ago; 0 keeps them all.
+    #[serde(default)]
+    pub result_retention_days: u64,
+    /// File names in the output directory that retention applies to; `*`
+    /// matches any run of characters.
+    #[serde(default = "default_result_file_pattern")]
+    pub result_file_pattern: String,
+    /// Report Thor's progress while it scans; `--quiet` turns this off.
+    #[serde(default = "default_true")]
+    pub progress: bool,
+    /// How often progress is logged when stderr is not a terminal.
+    #[serde(default = "default_progress_interval_seconds")]
+    pub progress_interval_seconds: u64,
+    /// Stop Thor after this many seconds and report the partial results;
+    /// 0 lets it run to completion. `--time-budget` overrides it.
+    #[serde(default)]
+    pub time_budget_seconds: u64,
+    /// Exit code of a scan the time budget cut short, unless
+    /// `--fail-on-match` already exits non-zero.
+    #[serde(default = "default_partial_exit_code")]
+    pub partial_exit_code: i32,
+    /// Fail the scan when one Thor run takes longer than this many seconds,
+    /// stopping Thor and discarding its results; unset waits indefinitely.
+    #[serde(default)]
+    pub scan_timeout_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    /// Whether rule reads recompute and check the content hash (off, warn, error).
+    #[serde(default)]
+    pub integrity_policy: IntegrityPolicy,
+    /// What storing a rule does when its content declares a YARA rule name
+    /// another stored rule already declares (reject, rename).
+    #[serde(default)]
+    pub duplicate_rule_names: DuplicateNamePolicy,
+    /// With ReDB enabled, export the stored rules and scan with them in place
+    /// of `thor.rules_path`.
+    #[serde(default)]
+    pub export_rules: bool,
+    /// Store each distinct rule body once, shared by every rule with that
+    /// content. `db migrate-blobs` converts rules stored before enabling it.
+    #[serde(default)]
+    pub dedup_rule_bodies: bool,
+    /// Store rule bodies zstd-compressed (and deduplicated) in the blob
+    /// table. `db migrate-blobs` compresses rules stored before enabling it.
+    #[serde(default)]
+    pub compress_rule_bodies: bool,
+    /// Severity for synced rules by YARA tag, used when a rule's `meta:`
+    /// section has no `severity`. The most severe matching tag wins.
+    #[serde(default)]
+    pub severity_from_tags: BTreeMap<String, Severity>,
+    /// Skip and log records that fail to deserialize when iterating tables,
+    /// instead of failing the whole read. `db verify --repair` removes them.
+    #[serde(default)]
+    pub skip_corrupt_records: bool,
+}
+
+/// Finding count limits per severity; the run fails when any is exceeded.
+/// Unset limits are unbounded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GateConfig {
+    #[serde(default)]
+    pub max_critical: Option<u64>,
+    #[serde(default)]
+    pub max_high: Option<u64>,
+    #[serde(default)]
+    pub max_medium: Option<u64>,
+    #[serde(default)]
+    pub max_low: Option<u64>,
+}
+
+/// How a finding's Thor score becomes its 0-100 `risk_score`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskConfig {
+    /// Thor score that maps to a risk of 100. Lower scores scale linearly
+    /// and higher ones are capped. Thor's default alert threshold is 100.
+    #[serde(default = "default_risk_score_ceiling")]
+    pub score_ceiling: f64,
+    /// Risk for findings without a usable score, by severity. Findings with
+    /// neither a score nor a mapped severity get no risk score.
+    #[serde(default = "default_severity_risk")]
+    pub severity_risk: BTreeMap<Severity, u32>,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            score_ceiling: default_risk_score_ceiling(),
+            severity_risk: default_severity_risk(),
+        }
+    }
+}
+
+impl RiskConfig {
+    /// Risk of a finding with Thor score `score` and `severity`, 0-100.
+    /// Negative and non-finite scores count as missing.
+    pub fn risk_score(&self, score: Option<f64>, severity: Option<Severity>) -> Option<u32> {
+        match score.filter(|score| score.is_finite() && *score >= 0.0) {
+            Some(score) if self.score_ceiling > 0.0 => Some((score / self.score_ceiling * 100.0).round().min(100.0) as u32),
+            _ => severity.and_then(|severity| self.severity_risk.get(&severity)).map(|risk| (*risk).min(100)),
+        }
+    }
+}
+
+impl ScanConfig {
+    /// `max_file_size_mb` in bytes, or `None` when it is 0.
+    pub fn max_file_size_bytes(&self) -> Option<u64> {
+        (self.max_file_size_mb > 0).then(|| self.max_file_size_mb * 1024 * 1024)
+    }
+}
+
+impl GateConfig {
+    /// Each configured limit as (config key, severity, maximum).
+    pub fn thresholds(&self) -> Vec<(&'static str, Severity, u64)> {
+        [
+            ("max_critical", Severity::Critical, self.max_critical),
+            ("max_high", Severity::High, self.max_high),
+            ("max_medium", Severity::Medium, self.max_medium),
+            ("max_low", Severity::Low, self.max_low),
+        ]
+        .into_iter()
+        .filter_map(|(key, severity, max)| max.map(|max| (key, severity, max)))
+        .collect()
+    }
 }
 
 impl Default for PyroConfig {
@@ -40,9 +405,10 @@ impl Default for PyroConfig {
         Self {
             thor: ThorConfig {
                 binary_path: get_default_thor_binary(),
-                license_path: "thor-lite-license.lic".to_string(),
-                rules_path: "custom-signatures".to_string(),
-                config_path: "config/thor.yml".to_string(),
+                license_path: DEFAULT_LICENSE_PATH.to_string(),
+                rules_path: DEFAULT_RULES_PATH.to_string(),
+                config_path: DEFAULT_THOR_CONFIG_PATH.to_string(),
+                success_exit_codes: default_success_exit_codes(),
                 flags: vec![
                     "--utc".to_string(),
                     "--rfc3339".to_string(),
@@ -59,10 +425,28 @@ impl Default for PyroConfig {
                 endpoint: "http://localhost:8080".to_string(),
                 api_key: None,
                 timeout_seconds: 300,
+                upload_min_severity: None,
+                upload_routes: Vec::new(),
+                proxy: None,
+                headers: HashMap::new(),
+                ca_cert_path: None,
+                danger_accept_invalid_certs: false,
+                upload_field_map: HashMap::new(),
+                upload_shutdown_grace_seconds: default_upload_shutdown_grace_seconds(),
+                spool_dir: default_spool_dir(),
+                http_version: HttpVersion::default(),
+                pool_idle_timeout_seconds: default_pool_idle_timeout_seconds(),
+                pool_max_idle_per_host: default_pool_max_idle_per_host(),
+                tcp_keepalive_seconds: default_tcp_keepalive_seconds(),
+                download_idle_timeout_seconds: default_download_idle_timeout_seconds(),
+                package_sha256: None,
+                download_retries: default_download_retries(),
+                download_retry_base_delay_ms: default_download_retry_base_delay_ms(),
             },
             scanning: ScanConfig {
-                output_format: "json".to_string(),
+                output_format: OutputFormat::Json,
                 temp_dir: None,
+                allowed_extract_roots: Vec::new(),
                 cleanup: true,
                 exclude_paths: vec![
                     "/proc".to_string(),
@@ -71,25 +455,130 @@ impl Default for PyroConfig {
                     "C:\\Windows\\System32".to_string(),
                 ],
                 max_file_size_mb: 100,
+                max_parse_buffer_mb: default_max_parse_buffer_mb(),
+                extract_workers: 0,
+                package_max_entry_mb: default_package_max_entry_mb(),
+                package_max_total_mb: default_package_max_total_mb(),
+                scan_retries: 0,
+                scan_retry_delay_seconds: default_scan_retry_delay_seconds(),
+                retriable_exit_codes: Vec::new(),
+                retriable_error_patterns: default_retriable_error_patterns(),
+                pretty_output: true,
+                thor_output_file: false,
+                compress_output: false,
+                max_walk_depth: default_max_walk_depth(),
+                skip_symlinks_outside_root: true,
+                severity_exit_codes: default_severity_exit_codes(),
+                post_scan_command: Vec::new(),
+                post_scan_timeout_seconds: default_post_scan_timeout_seconds(),
+                post_scan_required: false,
+                expand_archives: false,
+                archive_max_depth: default_archive_max_depth(),
+                archive_max_total_mb: default_archive_max_total_mb(),
+                max_result_files: 0,
+                result_retention_days: 0,
+                result_file_pattern: default_result_file_pattern(),
+                progress: true,
+                progress_interval_seconds: default_progress_interval_seconds(),
+                time_budget_seconds: 0,
+                partial_exit_code: default_partial_exit_code(),
+                scan_timeout_seconds: None,
             },
+            database: DatabaseConfig::default(),
+            gate: GateConfig::default(),
+            risk: RiskConfig::default(),
         }
     }
 }
 
 impl PyroConfig {
+    /// Reads the config file, or creates it with the defaults when missing,
+    /// then applies the environment overrides and validates the result.
     pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
-        if !path.as_ref().exists() {
+        let path = path.as_ref();
+        let mut config = if !path.exists() {
             log::warn!("Config file not found, creating default config");
             let default_config = Self::default();
-            default_config.save(&path)?;
-            return Ok(default_config);
-        }
+            default_config.save(path)?;
+            default_config
+        } else {
+            let content = fs::read_to_string(path).with_context(|| {
+                PyroError::new(ErrorCategory::Config, format!("Failed to read config file: {}", path.display()))
+            })?;
+            serde_yaml::from_str(&content).with_context(|| {
+                PyroError::new(ErrorCategory::Config, format!("Invalid config file: {}", path.display()))
+            })?
+        };
 
-        let content = fs::read_to_string(path)?;
-        let config: PyroConfig = serde_yaml::from_str(&content)?;
+        config.apply_env_overrides()
+            .context(PyroError::new(ErrorCategory::Config, "Invalid config override in the environment"))?;
+        config.validate().with_context(|| {
+            PyroError::new(ErrorCategory::Config, format!("Invalid config file: {}", path.display()))
+        })?;
         Ok(config)
     }
 
+    /// Replaces config values with those of the environment variables
+    /// `PYRO_ENDPOINT`, `PYRO_API_KEY`, `PYRO_TIMEOUT_SECONDS` and
+    /// `PYRO_SCAN_OUTPUT_FORMAT` that are set and not empty, so containers
+    /// can point a shared config file elsewhere.
+    pub fn apply_env_overrides(&mut self) -> anyhow::Result<()> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+        if let Some(endpoint) = var("PYRO_ENDPOINT") {
+            self.pyro.endpoint = endpoint;
+        }
+        if let Some(api_key) = var("PYRO_API_KEY") {
+            self.pyro.api_key = Some(api_key);
+        }
+        if let Some(timeout) = var("PYRO_TIMEOUT_SECONDS") {
+            self.pyro.timeout_seconds = timeout.trim().parse()
+                .with_context(|| format!("PYRO_TIMEOUT_SECONDS must be a whole number of seconds, got {:?}", timeout))?;
+        }
+        if let Some(format) = var("PYRO_SCAN_OUTPUT_FORMAT") {
+            self.scanning.output_format = format.trim().parse()
+                .context("Invalid PYRO_SCAN_OUTPUT_FORMAT")?;
+        }
+        Ok(())
+    }
+
+    /// Checks values that parse but can't work, reporting every problem
+    /// found with the field it concerns. `output_format` needs no check
+    /// here: anything but a supported format already fails to parse.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut problems = Vec::new();
+
+        match reqwest::Url::parse(&self.pyro.endpoint) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(url) => problems.push(format!(
+                "pyro.endpoint must be an http or https URL, got scheme {:?}", url.scheme()
+            )),
+            Err(e) => problems.push(format!(
+                "pyro.endpoint {:?} is not a valid URL: {}", self.pyro.endpoint, e
+            )),
+        }
+        if self.pyro.timeout_seconds == 0 {
+            problems.push("pyro.timeout_seconds must be greater than 0".to_string());
+        }
+        if self.scanning.max_file_size_mb > MAX_FILE_SIZE_MB_LIMIT {
+            problems.push(format!(
+                "scanning.max_file_size_mb is {}, over the {} MB (1 TiB) limit (0 leaves Thor's default)",
+                self.scanning.max_file_size_mb, MAX_FILE_SIZE_MB_LIMIT
+            ));
+        }
+        for path in &self.scanning.exclude_paths {
+            if !is_absolute_path(path) {
+                problems.push(format!("scanning.exclude_paths entry {:?} is not an absolute path", path));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("{}", problems.join("; ")))
+        }
+    }
+
     pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
         let content = serde_yaml::to_string(self)?;
         fs::write(path, content)?;
@@ -97,6 +586,131 @@ impl PyroConfig {
     }
 }
 
+pub fn default_max_walk_depth() -> usize {
+    32
+}
+
+fn default_severity_exit_codes() -> BTreeMap<Severity, i32> {
+    BTreeMap::from([
+        (Severity::Critical, 20),
+        (Severity::High, 15),
+        (Severity::Medium, 10),
+    ])
+}
+
+fn default_post_scan_timeout_seconds() -> u64 {
+    60
+}
+
+fn default_result_file_pattern() -> String {
+    "scan_results*.json*".to_string()
+}
+
+fn default_partial_exit_code() -> i32 {
+    crate::error::EXIT_PARTIAL
+}
+
+fn default_progress_interval_seconds() -> u64 {
+    60
+}
+
+fn default_archive_max_depth() -> usize {
+    3
+}
+
+fn default_archive_max_total_mb() -> u64 {
+    1024
+}
+
+fn default_upload_shutdown_grace_seconds() -> u64 {
+    5
+}
+
+fn default_spool_dir() -> String {
+    "upload-spool".to_string()
+}
+
+/// Absolute as a Unix or a Windows path (`/x`, `C:\x`, `C:/x` or
+/// `\\server\share`), whichever platform this runs on, since one
+/// config may list exclusions for both.
+fn is_absolute_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with('/')
+        || path.starts_with("\\\\")
+        || (bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && matches!(bytes[2], b'\\' | b'/'))
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_parse_buffer_mb() -> u64 {
+    64
+}
+
+fn default_risk_score_ceiling() -> f64 {
+    100.0
+}
+
+/// Thor's default level thresholds: notice at 40, warning at 60, alert at 100.
+fn default_severity_risk() -> BTreeMap<Severity, u32> {
+    BTreeMap::from([
+        (Severity::Critical, 100),
+        (Severity::High, 60),
+        (Severity::Medium, 40),
+        (Severity::Low, 20),
+        (Severity::Info, 0),
+    ])
+}
+
+fn default_download_idle_timeout_seconds() -> u64 {
+    30
+}
+
+fn default_download_retries() -> u32 {
+    3
+}
+
+fn default_download_retry_base_delay_ms() -> u64 {
+    1000
+}
+
+fn default_pool_idle_timeout_seconds() -> u64 {
+    90
+}
+
+fn default_pool_max_idle_per_host() -> usize {
+    4
+}
+
+fn default_tcp_keepalive_seconds() -> u64 {
+    60
+}
+
+fn default_success_exit_codes() -> Vec<i32> {
+    vec![0]
+}
+
+fn default_package_max_entry_mb() -> u64 {
+    512
+}
+
+fn default_package_max_total_mb() -> u64 {
+    2048
+}
+
+fn default_scan_retry_delay_seconds() -> u64 {
+    10
+}
+
+fn default_retriable_error_patterns() -> Vec<String> {
+    vec![
+        "being used by another process".to_string(),
+        "resource temporarily unavailable".to_string(),
+        "permission denied".to_string(),
+    ]
+}
+
 fn get_default_thor_binary() -> String {
     let arch = std::env::consts::ARCH;
     let os = std::env::consts::OS;
@@ -105,4 +719,111 @@ fn get_default_thor_binary() -> String {
         "windows" => format!("thor-lite_{}.exe", arch),
         _ => format!("thor-lite_{}", arch),
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serializes the tests that set the `PYRO_*` environment variables.
+    static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    const ENV_OVERRIDES: [&str; 4] = ["PYRO_ENDPOINT", "PYRO_API_KEY", "PYRO_TIMEOUT_SECONDS", "PYRO_SCAN_OUTPUT_FORMAT"];
+
+    /// Loads a saved default config with `vars` set, clearing them afterwards.
+    fn load_with_env(vars: &[(&str, &str)]) -> anyhow::Result<PyroConfig> {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        PyroConfig::default().save(&path).unwrap();
+
+        let clear = || ENV_OVERRIDES.iter().for_each(|name| std::env::remove_var(name));
+        clear();
+        for (name, value) in vars {
+            std::env::set_var(name, value);
+        }
+        let loaded = PyroConfig::load(&path);
+        clear();
+        loaded
+    }
+
+    #[test]
+    fn env_vars_override_the_config_file() {
+        let config = load_with_env(&[
+            ("PYRO_ENDPOINT", "https://pyro.internal"),
+            ("PYRO_API_KEY", "secret"),
+            ("PYRO_TIMEOUT_SECONDS", " 90 "),
+            ("PYRO_SCAN_OUTPUT_FORMAT", "csv"),
+        ]).unwrap();
+        assert_eq!(config.pyro.endpoint, "https://pyro.internal");
+        assert_eq!(config.pyro.api_key.as_deref(), Some("secret"));
+        assert_eq!(config.pyro.timeout_seconds, 90);
+        assert_eq!(config.scanning.output_format, OutputFormat::Csv);
+    }
+
+    #[test]
+    fn unset_or_empty_env_vars_leave_the_config_alone() {
+        let defaults = PyroConfig::default();
+        let config = load_with_env(&[("PYRO_API_KEY", "")]).unwrap();
+        assert_eq!(config.pyro.endpoint, defaults.pyro.endpoint);
+        assert_eq!(config.pyro.api_key, defaults.pyro.api_key);
+        assert_eq!(config.pyro.timeout_seconds, defaults.pyro.timeout_seconds);
+        assert_eq!(config.scanning.output_format, defaults.scanning.output_format);
+    }
+
+    #[test]
+    fn invalid_env_values_are_errors() {
+        let error = load_with_env(&[("PYRO_TIMEOUT_SECONDS", "ninety")]).unwrap_err();
+        assert!(format!("{:#}", error).contains("PYRO_TIMEOUT_SECONDS must be a whole number"), "{:#}", error);
+
+        let error = load_with_env(&[("PYRO_SCAN_OUTPUT_FORMAT", "xml")]).unwrap_err();
+        assert!(format!("{:#}", error).contains("Invalid PYRO_SCAN_OUTPUT_FORMAT"), "{:#}", error);
+    }
+
+    fn validation_error(config: &PyroConfig) -> String {
+        config.validate().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        PyroConfig::default().validate().unwrap();
+    }
+
+    #[test]
+    fn endpoint_must_be_an_http_url() {
+        let mut config = PyroConfig::default();
+        config.pyro.endpoint = "pyro.example".to_string();
+        assert!(validation_error(&config).contains("pyro.endpoint \"pyro.example\" is not a valid URL"));
+
+        config.pyro.endpoint = "ftp://pyro.example".to_string();
+        assert!(validation_error(&config).contains("pyro.endpoint must be an http or https URL"));
+    }
+
+    #[test]
+    fn timeout_must_be_positive() {
+        let mut config = PyroConfig::default();
+        config.pyro.timeout_seconds = 0;
+        assert!(validation_error(&config).contains("pyro.timeout_seconds must be greater than 0"));
+    }
+
+    #[test]
+    fn max_file_size_must_be_reasonable() {
+        let mut config = PyroConfig::default();
+        config.scanning.max_file_size_mb = MAX_FILE_SIZE_MB_LIMIT + 1;
+        assert!(validation_error(&config).contains("scanning.max_file_size_mb"));
+    }
+
+    #[test]
+    fn exclude_paths_must_be_absolute() {
+        let mut config = PyroConfig::default();
+        config.scanning.exclude_paths = vec!["/proc".to_string(), "C:\\Temp".to_string(), "tmp".to_string()];
+        assert_eq!(validation_error(&config), "scanning.exclude_paths entry \"tmp\" is not an absolute path");
+    }
+
+    #[test]
+    fn output_format_must_be_supported() {
+        let mut yaml = serde_yaml::to_value(PyroConfig::default()).unwrap();
+        yaml["scanning"]["output_format"] = "xml".into();
+        let error = serde_yaml::from_value::<PyroConfig>(yaml).unwrap_err();
+        assert!(error.to_string().contains("unknown variant `xml`"), "{}", error);
+    }
+}
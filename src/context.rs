@@ -0,0 +1,87 @@
+use crate::hooks::ScanTier;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Per-scan state shared by the executor, the scanner and anything that
+/// reports on a scan, so they all agree on what is being scanned and how.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanContext {
+    pub scan_uuid: String,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    /// Paths Thor scans in one run, each passed with `--path`.
+    pub scan_paths: Vec<String>,
+    pub output_path: String,
+    pub enterprise_mode: bool,
+    /// ReDB database used for rule optimization, when enabled.
+    pub redb_path: Option<String>,
+    /// Fail the scan when ReDB can't be opened instead of scanning without it.
+    pub redb_required: bool,
+    /// Caller-supplied tags (ticket, operator, ...) embedded in each finding
+    /// as `scan_metadata` and so uploaded with the results.
+    pub metadata: HashMap<String, String>,
+    /// Limits exported ReDB rules to this scan tier and cheaper ones.
+    pub scan_tier: Option<ScanTier>,
+    /// How long Thor may run before it is stopped and its partial results
+    /// are kept.
+    pub time_budget: Option<std::time::Duration>,
+}
+
+impl ScanContext {
+    pub fn new(scan_paths: &[String], output_path: &str, started_at: chrono::DateTime<chrono::Utc>) -> Self {
+        Self {
+            scan_uuid: uuid::Uuid::new_v4().to_string(),
+            started_at,
+            scan_paths: scan_paths.to_vec(),
+            output_path: output_path.to_string(),
+            enterprise_mode: false,
+            redb_path: None,
+            redb_required: false,
+            metadata: HashMap::new(),
+            scan_tier: None,
+            time_budget: None,
+        }
+    }
+
+    /// Uses a caller-supplied scan identifier instead of a generated one.
+    pub fn with_scan_uuid(mut self, scan_uuid: Option<&str>) -> Self {
+        if let Some(scan_uuid) = scan_uuid {
+            self.scan_uuid = scan_uuid.to_string();
+        }
+        self
+    }
+
+    pub fn with_enterprise_mode(mut self, enabled: bool) -> Self {
+        self.enterprise_mode = enabled;
+        self
+    }
+
+    pub fn with_redb(mut self, redb_path: Option<&str>) -> Self {
+        self.redb_path = redb_path.map(str::to_string);
+        self
+    }
+
+    pub fn with_redb_required(mut self, required: bool) -> Self {
+        self.redb_required = required;
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: HashMap<String, String>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn with_scan_tier(mut self, scan_tier: Option<ScanTier>) -> Self {
+        self.scan_tier = scan_tier;
+        self
+    }
+
+    pub fn with_time_budget(mut self, time_budget: Option<std::time::Duration>) -> Self {
+        self.time_budget = time_budget;
+        self
+    }
+
+    /// Time since the scan started, as of `now`.
+    pub fn elapsed(&self, now: chrono::DateTime<chrono::Utc>) -> chrono::Duration {
+        now - self.started_at
+    }
+}
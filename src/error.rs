@@ -0,0 +1,120 @@
+use serde::Serialize;
+use std::path::Path;
+
+use crate::scanner::ThorExitError;
+
+/// Exit code of a scan the time budget cut short, unless
+/// `scanning.partial_exit_code` says otherwise.
+pub const EXIT_PARTIAL: i32 = 3;
+
+/// Broad class of a failure, which picks the exit code and is reported by
+/// `--error-json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorCategory {
+    /// The config file is missing, unreadable or invalid.
+    Config,
+    /// The Pyro server or another remote could not be reached or refused
+    /// the request.
+    Network,
+    /// Preparing or running Thor failed.
+    Scan,
+    /// The ReDB database could not be opened, read or written.
+    Database,
+    /// Anything else, including exceeded detection gates.
+    Other,
+}
+
+impl ErrorCategory {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorCategory::Other => 1,
+            ErrorCategory::Config => 4,
+            ErrorCategory::Network => 5,
+            ErrorCategory::Scan => 6,
+            ErrorCategory::Database => 7,
+        }
+    }
+
+    /// Category of `error`: that of the outermost `PyroError` context, or
+    /// failing that, guessed from the errors in its chain.
+    pub fn of(error: &anyhow::Error) -> Self {
+        if let Some(tagged) = error.downcast_ref::<PyroError>() {
+            return tagged.category;
+        }
+
+        for cause in error.chain() {
+            if cause.is::<ThorExitError>() {
+                return ErrorCategory::Scan;
+            }
+            if cause.is::<reqwest::Error>() {
+                return ErrorCategory::Network;
+            }
+            if cause.is::<redb::Error>()
+                || cause.is::<redb::DatabaseError>()
+                || cause.is::<redb::TransactionError>()
+                || cause.is::<redb::TableError>()
+                || cause.is::<redb::StorageError>()
+                || cause.is::<redb::CommitError>()
+            {
+                return ErrorCategory::Database;
+            }
+            if cause.is::<serde_yaml::Error>() {
+                return ErrorCategory::Config;
+            }
+        }
+        ErrorCategory::Other
+    }
+}
+
+/// Context that tags an error with its category, added with
+/// `.context(PyroError::new(category, message))` where the failing step
+/// says more than the underlying error type does.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct PyroError {
+    pub category: ErrorCategory,
+    pub message: String,
+}
+
+impl PyroError {
+    pub fn new(category: ErrorCategory, message: impl Into<String>) -> Self {
+        Self { category, message: message.into() }
+    }
+}
+
+/// The machine-readable error object `--error-json` writes.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub category: ErrorCategory,
+    pub exit_code: i32,
+    /// The outermost error message.
+    pub message: String,
+    /// The messages of the errors that caused it, outermost first.
+    pub context: Vec<String>,
+}
+
+impl ErrorReport {
+    pub fn new(error: &anyhow::Error) -> Self {
+        let category = ErrorCategory::of(error);
+        let mut messages = error.chain().map(|cause| cause.to_string());
+        Self {
+            category,
+            exit_code: category.exit_code(),
+            message: messages.next().unwrap_or_default(),
+            context: messages.collect(),
+        }
+    }
+
+    /// Writes the report as one line of JSON to stderr for `-`, otherwise
+    /// to the file `target`.
+    pub fn write(&self, target: &str) -> anyhow::Result<()> {
+        let json = serde_json::to_string(self)?;
+        if target == "-" {
+            eprintln!("{}", json);
+        } else {
+            std::fs::write(Path::new(target), json + "\n")?;
+        }
+        Ok(())
+    }
+}
@@ -1,40 +1,194 @@
-use crate::config::PyroConfig;
-use crate::scanner::ThorScanner;
+use crate::archive::ArchiveOptions;
+use crate::clock::SharedClock;
+use crate::config::{HttpVersion, OutputFormat, PyroConfig, PyroServerConfig, UploadRoute};
+use crate::context::ScanContext;
+use crate::error::{ErrorCategory, PyroError};
+use crate::findings::{parse_findings, Finding};
+use crate::package::{verify_package, PackageMetadata, THOR_PACKAGE_FILE};
+use crate::results_file;
+use crate::retention::RetentionPolicy;
+use crate::scanner::{ExtractLimits, RetryPolicy, ScanResults, ThorScanner};
+use crate::severity::Severity;
 use anyhow::{Context, Result};
 use serde_json::Value;
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tokio_util::io::{ReaderStream, SyncIoBridge};
+use tokio_util::sync::CancellationToken;
+
+/// Longest wait between Thor package download retries.
+const MAX_DOWNLOAD_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(60);
 
 pub struct PyroExecutor {
     config: PyroConfig,
+    /// Shared by every request so connections and TLS sessions are reused.
+    client: reqwest::Client,
+    /// Cancelled when the process is asked to shut down.
+    shutdown: CancellationToken,
+    clock: SharedClock,
+}
+
+/// Where an upload is posted, and where it waits if shutdown interrupts it.
+struct UploadTarget {
+    url: String,
+    spool_dir: PathBuf,
+}
+
+/// Builds the client used for all Pyro server requests from the `pyro` config.
+fn build_http_client(config: &PyroServerConfig) -> Result<reqwest::Client> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    for (name, value) in &config.headers {
+        let name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("Invalid header name in pyro.headers: {}", name))?;
+        let value = reqwest::header::HeaderValue::from_str(value)
+            .with_context(|| format!("Invalid value for header {} in pyro.headers", name))?;
+        headers.insert(name, value);
+    }
+
+    let mut builder = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(config.timeout_seconds))
+        .default_headers(headers)
+        .pool_idle_timeout((config.pool_idle_timeout_seconds > 0)
+            .then(|| std::time::Duration::from_secs(config.pool_idle_timeout_seconds)))
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .tcp_keepalive((config.tcp_keepalive_seconds > 0)
+            .then(|| std::time::Duration::from_secs(config.tcp_keepalive_seconds)));
+
+    builder = match config.http_version {
+        HttpVersion::Auto => builder,
+        HttpVersion::Http1 => builder.http1_only(),
+        HttpVersion::Http2 => builder.http2_prior_knowledge(),
+    };
+
+    if let Some(ca_cert_path) = &config.ca_cert_path {
+        let pem = std::fs::read(ca_cert_path)
+            .with_context(|| format!("Failed to read pyro.ca_cert_path: {}", ca_cert_path))?;
+        let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("Invalid PEM certificates in {}", ca_cert_path))?;
+        if certificates.is_empty() {
+            return Err(anyhow::anyhow!("No certificates found in pyro.ca_cert_path: {}", ca_cert_path));
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    if config.danger_accept_invalid_certs {
+        log::warn!("⚠️  pyro.danger_accept_invalid_certs is set: Pyro server certificates are NOT verified");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    if let Some(proxy) = &config.proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)
+            .with_context(|| format!("Invalid proxy URL: {}", proxy))?);
+    }
+
+    builder.build().context("Failed to create HTTP client")
+}
+
+/// What a completed scan produced, without having to re-read the results.
+#[derive(Debug)]
+pub struct ScanOutcome {
+    pub scan_uuid: String,
+    pub findings_count: u64,
+    pub by_severity: BTreeMap<Severity, u64>,
+    pub duration: std::time::Duration,
+    pub output_path: String,
+    /// The CSV copy of the results, with `scanning.output_format: csv`.
+    pub csv_path: Option<String>,
+    /// Whether the results were sent to the Pyro server.
+    pub uploaded: bool,
+    pub thor_exit_code: Option<i32>,
+    /// The time budget stopped Thor before it covered the whole scan path.
+    pub incomplete: bool,
+    results: ScanResults,
+}
+
+impl ScanOutcome {
+    /// Full scan results, including the per-rule counts.
+    pub fn results(&self) -> &ScanResults {
+        &self.results
+    }
+
+    /// The parsed Thor output; `None` when it was streamed to `output_path`.
+    pub fn raw_value(&self) -> Option<&Value> {
+        self.results.value.as_ref()
+    }
 }
 
 impl PyroExecutor {
-    pub fn new(config: PyroConfig) -> Self {
-        Self { config }
+    pub fn new(config: PyroConfig) -> Result<Self> {
+        let client = build_http_client(&config.pyro)?;
+        Ok(Self { config, client, shutdown: CancellationToken::new(), clock: crate::clock::system() })
+    }
+
+    /// Lets uploads react to shutdown (Ctrl-C) by cancelling `shutdown`.
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = shutdown;
+        self
     }
 
-    pub async fn execute_scan(&self, scan_path: &str, output_path: &str) -> Result<Value> {
-        self.execute_scan_with_options(scan_path, output_path, false, false).await
+    /// Replaces the wall clock for scan timing, package metadata and the
+    /// ReDB hook the scanner opens.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
     }
 
-    pub async fn execute_enterprise_scan(&self, scan_path: &str, output_path: &str, redb_enabled: bool) -> Result<Value> {
-        self.execute_scan_with_options(scan_path, output_path, true, redb_enabled).await
+    /// Replaces the HTTP client, e.g. to point requests at a test server.
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
     }
 
-    async fn execute_scan_with_options(&self, scan_path: &str, output_path: &str, enterprise_mode: bool, redb_enabled: bool) -> Result<Value> {
-        if enterprise_mode {
-            log::info!("🚀 Starting Pyro Thor Enterprise scan execution");
+    pub async fn execute_scan(&self, ctx: &ScanContext) -> Result<ScanOutcome> {
+        if ctx.enterprise_mode {
+            log::info!("🚀 Starting Pyro Thor Enterprise scan execution ({})", ctx.scan_uuid);
         } else {
-            log::info!("Starting Pyro Thor scan execution");
+            log::info!("Starting Pyro Thor scan execution ({})", ctx.scan_uuid);
         }
         
         let mut scanner = ThorScanner::new(self.config.thor.clone())
-            .with_enterprise_mode(enterprise_mode);
+            .with_max_buffer_bytes(self.config.scanning.max_parse_buffer_mb * 1024 * 1024)
+            .with_extract_workers(self.config.scanning.extract_workers)
+            .with_extract_limits(ExtractLimits {
+                max_entry_bytes: self.config.scanning.package_max_entry_mb * 1024 * 1024,
+                max_total_bytes: self.config.scanning.package_max_total_mb * 1024 * 1024,
+            })
+            .with_retry_policy(RetryPolicy {
+                max_retries: self.config.scanning.scan_retries,
+                delay: std::time::Duration::from_secs(self.config.scanning.scan_retry_delay_seconds),
+                exit_codes: self.config.scanning.retriable_exit_codes.clone(),
+                error_patterns: self.config.scanning.retriable_error_patterns.clone(),
+            })
+            .with_clock(self.clock.clone())
+            .with_redb_rule_export(self.config.database.export_rules)
+            .with_pretty_output(self.config.scanning.pretty_output)
+            .with_thor_output_file(self.config.scanning.thor_output_file)
+            .with_progress(self.config.scanning.progress.then(|| {
+                std::time::Duration::from_secs(self.config.scanning.progress_interval_seconds)
+            }))
+            .with_risk_config(self.config.risk.clone())
+            .with_compressed_output(self.config.scanning.compress_output)
+            .with_temp_base(self.config.scanning.temp_dir.as_ref().map(PathBuf::from))
+            .with_allowed_extract_roots(
+                self.config.scanning.allowed_extract_roots.iter().map(PathBuf::from).collect()
+            )
+            .with_exclude_paths(self.config.scanning.exclude_paths.iter().map(PathBuf::from).collect())
+            .with_max_file_size(self.config.scanning.max_file_size_bytes())
+            .with_scan_timeout(self.config.scanning.scan_timeout_seconds.map(std::time::Duration::from_secs))
+            .with_archive_expansion(
+                self.config.scanning.expand_archives.then(|| ArchiveOptions::from_config(&self.config.scanning))
+            );
         
         // Enable ReDB optimization if requested
-        if redb_enabled {
-            scanner.enable_redb_optimization("yara_rules.redb").await
-                .context("Failed to enable ReDB optimization")?;
+        if let Some(redb_path) = &ctx.redb_path {
+            if let Err(e) = scanner.enable_redb_optimization(redb_path, &self.config.database).await {
+                if ctx.redb_required {
+                    return Err(e.context("Failed to enable ReDB optimization"));
+                }
+                log::warn!("⚠️  Failed to enable ReDB optimization, scanning without it: {:#}", e);
+            }
         }
         
         // Prepare environment
@@ -43,20 +197,91 @@ impl PyroExecutor {
 
         // Download Thor package if needed
         let thor_package_path = self.ensure_thor_package().await
-            .context("Failed to ensure Thor package availability")?;
+            .context(PyroError::new(ErrorCategory::Network, "Failed to ensure Thor package availability"))?;
 
         // Extract Thor package
         scanner.extract_thor_package(&thor_package_path, &temp_path).await
             .context("Failed to extract Thor package")?;
 
         // Run the scan
-        let results = scanner.run_scan(scan_path, output_path).await
+        let results = scanner.run_scan(ctx).await
             .context("Failed to run Thor scan")?;
 
+        log::info!("Scan produced {} findings ({} without a mapped severity)",
+                  results.summary.total, results.summary.unmapped);
+        for (rule, count) in results.summary.top_rules(10) {
+            log::info!("  {:>6}  {}", count, rule);
+        }
+
         // Send results to Pyro server if configured
+        let uploaded = self.config.pyro.api_key.is_some();
         if let Some(api_key) = &self.config.pyro.api_key {
-            self.send_results_to_pyro(&results, api_key).await
-                .context("Failed to send results to Pyro server")?;
+            self.retry_spooled_uploads(api_key).await;
+
+            match &results.value {
+                Some(value) => {
+                    let filtered;
+                    let payload = match self.config.pyro.upload_min_severity {
+                        Some(min_severity) => {
+                            filtered = filter_findings_by_severity(value, min_severity);
+                            &filtered
+                        }
+                        None => value,
+                    };
+
+                    if self.config.pyro.upload_routes.is_empty() {
+                        self.send_results_to_pyro(payload, api_key, ctx, &self.default_upload_target()).await
+                            .context(PyroError::new(ErrorCategory::Network, "Failed to send results to Pyro server"))?;
+                    } else {
+                        self.send_routed_results(payload, api_key, ctx).await?;
+                    }
+                }
+                None => {
+                    if self.config.pyro.upload_min_severity.is_some() {
+                        log::warn!("Results were streamed to disk; uploading all findings without severity filtering");
+                    }
+                    if !self.config.pyro.upload_routes.is_empty() {
+                        log::warn!("Results were streamed to disk; uploading all findings to /api/scan-results without upload_routes");
+                    }
+                    if !self.config.pyro.upload_field_map.is_empty() {
+                        log::warn!("Results were streamed to disk; uploading them without upload_field_map renaming");
+                    }
+                    self.send_results_file_to_pyro(&ctx.output_path, api_key, ctx, &self.default_upload_target()).await
+                        .context(PyroError::new(ErrorCategory::Network, "Failed to send results to Pyro server"))?;
+                }
+            }
+        }
+
+        // The JSON results file stays, for quarantine, `validate-results` and
+        // the TUI; the CSV is written next to it
+        let csv_path = match self.config.scanning.output_format {
+            OutputFormat::Json => None,
+            OutputFormat::Csv => {
+                let csv_path = results_file::csv_path(&ctx.output_path);
+                let rows = results_file::convert_to_csv(Path::new(&ctx.output_path), Path::new(&csv_path), self.config.scanning.compress_output)
+                    .context("Failed to write scan results as CSV")?;
+                log::info!("Wrote {} findings as CSV to {}", rows, csv_path);
+                Some(csv_path)
+            }
+        };
+
+        if !self.config.scanning.post_scan_command.is_empty() {
+            if let Err(e) = self.run_post_scan_command(ctx, &results, csv_path.as_deref()).await {
+                if self.config.scanning.post_scan_required {
+                    return Err(e);
+                }
+                log::warn!("Post-scan command failed: {:#}", e);
+            }
+        }
+
+        if let Some(policy) = RetentionPolicy::from_config(&self.config.scanning) {
+            let output = Path::new(&ctx.output_path);
+            let directory = output.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            match policy.enforce(directory, output, self.clock.now()) {
+                Ok(deleted) if !deleted.is_empty() => log::info!("Removed {} old results file(s)", deleted.len()),
+                Ok(_) => {}
+                Err(e) => log::warn!("Failed to apply result retention: {:#}", e),
+            }
         }
 
         // Cleanup
@@ -65,17 +290,66 @@ impl PyroExecutor {
                 .context("Failed to cleanup temporary files")?;
         }
 
-        if enterprise_mode {
-            log::info!("🎯 Enterprise scan execution completed successfully");
+        if ctx.enterprise_mode {
+            log::info!("🎯 Enterprise scan execution completed successfully in {}s", ctx.elapsed(self.clock.now()).num_seconds());
         } else {
-            log::info!("Scan execution completed successfully");
+            log::info!("Scan execution completed successfully in {}s", ctx.elapsed(self.clock.now()).num_seconds());
         }
-        Ok(results)
+
+        Ok(ScanOutcome {
+            scan_uuid: ctx.scan_uuid.clone(),
+            findings_count: results.summary.total,
+            by_severity: results.summary.by_severity.clone(),
+            duration: ctx.elapsed(self.clock.now()).to_std().unwrap_or_default(),
+            output_path: ctx.output_path.clone(),
+            csv_path,
+            uploaded,
+            thor_exit_code: results.thor_exit_code,
+            incomplete: results.incomplete,
+            results,
+        })
+    }
+
+    /// Runs `scanning.post_scan_command` with the scan details in its
+    /// environment, logging its output and killing it after the timeout.
+    async fn run_post_scan_command(&self, ctx: &ScanContext, results: &ScanResults, csv_path: Option<&str>) -> Result<()> {
+        let (program, args) = self.config.scanning.post_scan_command.split_first()
+            .context("Post-scan command is empty")?;
+        log::info!("Running post-scan command: {}", program);
+
+        let mut cmd = tokio::process::Command::new(program);
+        cmd.args(args)
+            .env("PYRO_RESULTS_PATH", &ctx.output_path)
+            .env("PYRO_SCAN_UUID", &ctx.scan_uuid)
+            .env("PYRO_FINDINGS_COUNT", results.summary.total.to_string())
+            .env("PYRO_MAX_RISK", results.summary.risk.max.map_or_else(String::new, |risk| risk.to_string()))
+            .env("PYRO_THOR_EXIT_CODE", results.thor_exit_code.map_or_else(String::new, |code| code.to_string()))
+            .env("PYRO_SCAN_INCOMPLETE", if results.incomplete { "1" } else { "0" })
+            .env("PYRO_CSV_PATH", csv_path.unwrap_or_default())
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true);
+
+        let timeout = std::time::Duration::from_secs(self.config.scanning.post_scan_timeout_seconds);
+        let output = tokio::time::timeout(timeout, cmd.output()).await
+            .map_err(|_| anyhow::anyhow!("Post-scan command timed out after {}s", timeout.as_secs()))?
+            .with_context(|| format!("Failed to run post-scan command: {}", program))?;
+
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            log::info!("[post-scan] {}", line);
+        }
+        for line in String::from_utf8_lossy(&output.stderr).lines() {
+            log::warn!("[post-scan] {}", line);
+        }
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("Post-scan command exited with {}", output.status));
+        }
+        Ok(())
     }
 
     async fn ensure_thor_package(&self) -> Result<std::path::PathBuf> {
         // Check if Thor package exists locally
-        let local_package = Path::new("Custom.DFIR.Yara.AllRules.zip");
+        let local_package = Path::new(THOR_PACKAGE_FILE);
         
         if local_package.exists() {
             log::info!("Using local Thor package: {}", local_package.display());
@@ -83,59 +357,297 @@ impl PyroExecutor {
         }
 
         // Try to download from Pyro server
+        self.download_thor_package(None).await?;
+        Ok(local_package.to_path_buf())
+    }
+
+    /// Downloads the Thor package, conditionally on `cached` when given.
+    ///
+    /// Returns `None` when the server reports the cached package is current.
+    pub async fn download_thor_package(&self, cached: Option<&PackageMetadata>) -> Result<Option<PackageMetadata>> {
+        self.download_thor_package_to(Path::new(THOR_PACKAGE_FILE), cached).await
+    }
+
+    async fn download_thor_package_to(&self, local_package: &Path, cached: Option<&PackageMetadata>) -> Result<Option<PackageMetadata>> {
         log::info!("Downloading Thor package from Pyro server: {}", self.config.pyro.endpoint);
         
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(self.config.pyro.timeout_seconds))
-            .build()
-            .context("Failed to create HTTP client")?;
+        let max_retries = self.config.pyro.download_retries;
+        let mut retry = 0;
+        let response = loop {
+            let mut request = self.with_conditional_headers(self.client.get(self.package_url()), cached);
+
+            if let Some(api_key) = &self.config.pyro.api_key {
+                request = request.header("Authorization", format!("Bearer {}", api_key));
+            }
+
+            let outcome = tokio::select! {
+                _ = self.shutdown.cancelled() => return Err(anyhow::anyhow!("Thor package download cancelled by shutdown")),
+                response = request.send() => response,
+            };
+
+            // Connection failures, timeouts, 5xx and 429 may clear up;
+            // other 4xx won't
+            let failure = match &outcome {
+                Ok(response) if response.status().is_server_error()
+                    || response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS => format!("HTTP {}", response.status()),
+                Err(e) if e.is_connect() || e.is_timeout() => e.to_string(),
+                _ => break outcome.context("Failed to download Thor package")?,
+            };
+            if retry >= max_retries {
+                break outcome.context("Failed to download Thor package")?;
+            }
+
+            retry += 1;
+            let delay = self.download_retry_delay(retry);
+            log::warn!("Thor package download failed ({}), retrying in {}ms (retry {}/{})",
+                      failure, delay.as_millis(), retry, max_retries);
+            tokio::select! {
+                _ = self.shutdown.cancelled() => return Err(anyhow::anyhow!("Thor package download cancelled by shutdown")),
+                _ = tokio::time::sleep(delay) => {}
+            }
+        };
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            log::info!("Cached Thor package is up to date");
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to download Thor package: HTTP {}", 
+                response.status()
+            ));
+        }
+
+        let headers = response.headers().clone();
+        // Downloaded next to the package and renamed into place once
+        // complete, so an interrupted download never replaces a good one
+        let partial = local_package.with_extension("zip.part");
+        let downloaded = self.save_package_body(response, &partial).await
+            .and_then(|(sha256, size)| {
+                verify_package(&partial, &sha256, self.config.pyro.package_sha256.as_deref())?;
+                Ok((sha256, size))
+            });
+        let (sha256, size) = match downloaded {
+            Ok(downloaded) => downloaded,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&partial).await;
+                return Err(e);
+            }
+        };
+
+        tokio::fs::rename(&partial, local_package).await
+            .context("Failed to save Thor package")?;
+        let metadata = PackageMetadata::from_response(&headers, sha256, size, self.clock.now());
+        metadata.save(local_package).await?;
+
+        log::info!("Thor package downloaded successfully");
+        Ok(Some(metadata))
+    }
+
+    /// Writes the package body to `path` as it arrives and returns its
+    /// SHA-256 and size. Gives up when nothing arrives for
+    /// `download_idle_timeout_seconds`, which catches connections that
+    /// trickle or hang half-open long before the overall request timeout,
+    /// and when shutdown is requested.
+    async fn save_package_body(&self, mut response: reqwest::Response, path: &Path) -> Result<(String, u64)> {
+        use sha2::Digest;
+        use tokio::io::AsyncWriteExt;
+
+        let idle_timeout = self.config.pyro.download_idle_timeout_seconds;
+        let mut file = tokio::fs::File::create(path).await
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut hasher = sha2::Sha256::new();
+        let mut size = 0u64;
+
+        loop {
+            let next_chunk = async {
+                if idle_timeout == 0 {
+                    return Ok(response.chunk().await);
+                }
+                tokio::time::timeout(std::time::Duration::from_secs(idle_timeout), response.chunk()).await
+                    .map_err(|_| anyhow::anyhow!(
+                        "Thor package download stalled: no data for {}s after {} bytes", idle_timeout, size
+                    ))
+            };
+            let chunk = tokio::select! {
+                _ = self.shutdown.cancelled() => {
+                    return Err(anyhow::anyhow!("Thor package download cancelled by shutdown"));
+                }
+                chunk = next_chunk => chunk?.context("Failed to read Thor package bytes")?,
+            };
+            let Some(chunk) = chunk else { break };
+
+            hasher.update(&chunk);
+            size += chunk.len() as u64;
+            file.write_all(&chunk).await
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+
+        file.flush().await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok((format!("{:x}", hasher.finalize()), size))
+    }
+
+    /// Asks the server whether a newer package than `cached` is available.
+    ///
+    /// Uses a conditional HEAD request; returns `None` when the server gives no
+    /// validators to compare against.
+    pub async fn check_package_update(&self, cached: &PackageMetadata) -> Result<Option<bool>> {
+        let mut request = self.with_conditional_headers(self.client.head(self.package_url()), Some(cached));
 
-        let url = format!("{}/api/tools/Custom.DFIR.Yara.AllRules.zip", self.config.pyro.endpoint);
-        
-        let mut request = client.get(&url);
-        
         if let Some(api_key) = &self.config.pyro.api_key {
             request = request.header("Authorization", format!("Bearer {}", api_key));
         }
 
         let response = request.send().await
-            .context("Failed to download Thor package")?;
+            .context("Failed to query Thor package metadata")?;
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(Some(false));
+        }
 
         if !response.status().is_success() {
             return Err(anyhow::anyhow!(
-                "Failed to download Thor package: HTTP {}", 
+                "Failed to query Thor package metadata: HTTP {}", 
                 response.status()
             ));
         }
 
-        let bytes = response.bytes().await
-            .context("Failed to read Thor package bytes")?;
+        let remote = PackageMetadata::from_download(&[], response.headers(), self.clock.now());
+        let newer = match (&cached.etag, &remote.etag, &cached.last_modified, &remote.last_modified) {
+            (Some(local), Some(remote), _, _) => Some(local != remote),
+            (_, _, Some(local), Some(remote)) => Some(local != remote),
+            _ => None,
+        };
+        Ok(newer)
+    }
 
-        tokio::fs::write(local_package, bytes).await
-            .context("Failed to save Thor package")?;
+    /// Wait before download retry `retry` (1-based): the base delay,
+    /// doubled for each earlier retry, capped at `MAX_DOWNLOAD_RETRY_DELAY`.
+    fn download_retry_delay(&self, retry: u32) -> std::time::Duration {
+        let base = std::time::Duration::from_millis(self.config.pyro.download_retry_base_delay_ms);
+        base.saturating_mul(1 << (retry - 1).min(16)).min(MAX_DOWNLOAD_RETRY_DELAY)
+    }
 
-        log::info!("Thor package downloaded successfully");
-        Ok(local_package.to_path_buf())
+    fn package_url(&self) -> String {
+        format!("{}/api/tools/{}", self.config.pyro.endpoint, THOR_PACKAGE_FILE)
     }
 
-    async fn send_results_to_pyro(&self, results: &Value, api_key: &str) -> Result<()> {
-        log::info!("Sending scan results to Pyro server");
+    fn with_conditional_headers(&self, mut request: reqwest::RequestBuilder, cached: Option<&PackageMetadata>) -> reqwest::RequestBuilder {
+        if let Some(cached) = cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header("If-None-Match", etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header("If-Modified-Since", last_modified);
+            }
+        }
+        request
+    }
 
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(self.config.pyro.timeout_seconds))
-            .build()
-            .context("Failed to create HTTP client")?;
+    /// Uploads to each of `pyro.upload_routes` the findings its severity
+    /// filter accepts; a finding may go to several routes. Every route is
+    /// attempted, and the first failure is returned once all have been.
+    async fn send_routed_results(&self, results: &Value, api_key: &str, ctx: &ScanContext) -> Result<()> {
+        let findings = match results {
+            Value::Array(_) => parse_findings(results.clone()),
+            finding => vec![Finding::from_value(finding.clone())],
+        };
+        let mut routed = vec![false; findings.len()];
+        let mut failure = None;
 
-        let url = format!("{}/api/scan-results", self.config.pyro.endpoint);
+        for route in &self.config.pyro.upload_routes {
+            let mut batch = Vec::new();
+            for (index, finding) in findings.iter().enumerate() {
+                if route.accepts(finding.severity) {
+                    routed[index] = true;
+                    batch.push(finding.raw.clone());
+                }
+            }
+            if batch.is_empty() {
+                log::info!("No findings for upload route {}", route.name);
+                continue;
+            }
 
-        let response = client
-            .post(&url)
+            log::info!("Uploading {} findings to route {}", batch.len(), route.name);
+            if let Err(e) = self.send_results_to_pyro(&Value::Array(batch), api_key, ctx, &self.route_upload_target(route)).await {
+                log::error!("Upload to route {} failed: {:#}", route.name, e);
+                failure.get_or_insert(e.context(PyroError::new(
+                    ErrorCategory::Network,
+                    format!("Failed to send results to upload route {}", route.name),
+                )));
+            }
+        }
+
+        let unrouted = routed.iter().filter(|routed| !**routed).count();
+        if unrouted > 0 {
+            log::warn!("{} of {} findings matched no upload route and were not uploaded", unrouted, findings.len());
+        }
+        failure.map_or(Ok(()), Err)
+    }
+
+    fn default_upload_target(&self) -> UploadTarget {
+        UploadTarget {
+            url: format!("{}/api/scan-results", self.config.pyro.endpoint),
+            spool_dir: PathBuf::from(&self.config.pyro.spool_dir),
+        }
+    }
+
+    /// A route's uploads spool to a subdirectory named after it, so a later
+    /// retry goes back to the same route.
+    fn route_upload_target(&self, route: &UploadRoute) -> UploadTarget {
+        let url = if route.url.starts_with('/') {
+            format!("{}{}", self.config.pyro.endpoint, route.url)
+        } else {
+            route.url.clone()
+        };
+        UploadTarget { url, spool_dir: Path::new(&self.config.pyro.spool_dir).join(&route.name) }
+    }
+
+    async fn send_results_to_pyro(&self, results: &Value, api_key: &str, ctx: &ScanContext, target: &UploadTarget) -> Result<()> {
+        log::info!("Sending scan results to {}", target.url);
+
+        let remapped;
+        let results = if self.config.pyro.upload_field_map.is_empty() {
+            results
+        } else {
+            remapped = remap_fields(results, &self.config.pyro.upload_field_map);
+            &remapped
+        };
+
+        let request = self.client
+            .post(&target.url)
             .header("Authorization", format!("Bearer {}", api_key))
             .header("Content-Type", "application/json")
-            .json(results)
-            .send()
-            .await
-            .context("Failed to send results to Pyro server")?;
+            .json(results);
+
+        let Some(response) = self.send_unless_shutdown(request).await? else {
+            let spooled = target.spool_dir.join(format!("{}.json", ctx.scan_uuid));
+            self.spool_upload(target, &spooled, |path| Ok(std::fs::write(path, serde_json::to_vec(results)?)?))?;
+            return Err(anyhow::anyhow!("Upload cancelled by shutdown; results spooled to {}", spooled.display()));
+        };
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to send results to Pyro server: HTTP {}", 
+                response.status()
+            ));
+        }
+
+        log::info!("Scan results sent to Pyro server successfully");
+        Ok(())
+    }
+
+    /// Uploads a results file as a streamed request body without loading it.
+    async fn send_results_file_to_pyro(&self, results_path: &str, api_key: &str, ctx: &ScanContext, target: &UploadTarget) -> Result<()> {
+        log::info!("Streaming scan results file to {}: {}", target.url, results_path);
+
+        let Some(response) = self.post_results_file(Path::new(results_path), api_key, &target.url).await? else {
+            let spooled = target.spool_dir.join(format!("{}.ndjson", ctx.scan_uuid));
+            self.spool_upload(target, &spooled, |path| Ok(std::fs::copy(results_path, path).map(|_| ())?))?;
+            return Err(anyhow::anyhow!("Upload cancelled by shutdown; results spooled to {}", spooled.display()));
+        };
 
         if !response.status().is_success() {
             return Err(anyhow::anyhow!(
@@ -147,4 +659,241 @@ impl PyroExecutor {
         log::info!("Scan results sent to Pyro server successfully");
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Streams a results file to the server; `None` if shutdown cut it short.
+    ///
+    /// Gzipped results files are decompressed on the fly, so the server
+    /// receives the same body either way.
+    async fn post_results_file(&self, path: &Path, api_key: &str, url: &str) -> Result<Option<reqwest::Response>> {
+        let compressed = crate::results_file::is_compressed(path)?;
+        let name = if compressed { path.with_extension("") } else { path.to_path_buf() };
+        let content_type = match name.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => "application/json",
+            _ => "application/x-ndjson",
+        };
+
+        let body = if compressed {
+            decompressing_body(path)
+        } else {
+            let file = tokio::fs::File::open(path).await
+                .context("Failed to open scan results file")?;
+            reqwest::Body::from(file)
+        };
+
+        let request = self.client
+            .post(url)
+            .header("Authorization", format!("Bearer {}", api_key))
+            .header("Content-Type", content_type)
+            .body(body);
+
+        self.send_unless_shutdown(request).await
+    }
+
+    /// Sends `request`, dropping it if shutdown is requested and it hasn't
+    /// completed within `pyro.upload_shutdown_grace_seconds` afterwards.
+    /// Returns `None` in that case.
+    async fn send_unless_shutdown(&self, request: reqwest::RequestBuilder) -> Result<Option<reqwest::Response>> {
+        let grace = std::time::Duration::from_secs(self.config.pyro.upload_shutdown_grace_seconds);
+        let shutdown_expired = async {
+            self.shutdown.cancelled().await;
+            log::warn!("Shutdown requested, giving the upload {}s to finish", grace.as_secs());
+            tokio::time::sleep(grace).await;
+        };
+
+        tokio::select! {
+            response = request.send() => Ok(Some(response.context("Failed to send results to Pyro server")?)),
+            _ = shutdown_expired => Ok(None),
+        }
+    }
+
+    fn spool_upload(&self, target: &UploadTarget, path: &Path, write: impl FnOnce(&Path) -> Result<()>) -> Result<()> {
+        std::fs::create_dir_all(&target.spool_dir)
+            .context("Failed to create upload spool directory")?;
+        write(path).context("Failed to spool results for a later upload")?;
+        log::warn!("Spooled unsent results to {}", path.display());
+        Ok(())
+    }
+
+    /// Re-sends results spooled by an earlier interrupted upload, deleting
+    /// each one once the server accepts it. Failures leave the file for the
+    /// next run.
+    async fn retry_spooled_uploads(&self, api_key: &str) {
+        let targets = std::iter::once(self.default_upload_target())
+            .chain(self.config.pyro.upload_routes.iter().map(|route| self.route_upload_target(route)));
+        for target in targets {
+            if !self.retry_spooled_target(&target, api_key).await {
+                return;
+            }
+        }
+    }
+
+    /// Retries the spooled uploads of one target; `false` if shutdown cut
+    /// it short.
+    async fn retry_spooled_target(&self, target: &UploadTarget, api_key: &str) -> bool {
+        let Ok(entries) = std::fs::read_dir(&target.spool_dir) else { return true };
+
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if !path.is_file() {
+                continue;
+            }
+            log::info!("Retrying spooled upload: {}", path.display());
+
+            match self.post_results_file(&path, api_key, &target.url).await {
+                Ok(Some(response)) if response.status().is_success() => {
+                    if let Err(e) = std::fs::remove_file(&path) {
+                        log::warn!("Uploaded spooled results but failed to remove {}: {}", path.display(), e);
+                    }
+                }
+                Ok(Some(response)) => log::warn!("Spooled upload {} rejected: HTTP {}", path.display(), response.status()),
+                Ok(None) => return false,
+                Err(e) => log::warn!("Spooled upload {} failed: {:#}", path.display(), e),
+            }
+        }
+        true
+    }
+}
+/// A request body that streams the decompressed contents of a gzipped file.
+///
+/// Decompression runs on a blocking thread feeding an in-memory pipe; a read
+/// error ends the body early and is logged.
+fn decompressing_body(path: &Path) -> reqwest::Body {
+    let (reader, writer) = tokio::io::duplex(64 * 1024);
+    let path = path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let copied = crate::results_file::open(&path).and_then(|mut file| {
+            let mut writer = SyncIoBridge::new(writer);
+            std::io::copy(&mut file, &mut writer)?;
+            writer.shutdown()?;
+            Ok(())
+        });
+        if let Err(e) = copied {
+            log::error!("Failed to decompress {} for upload: {:#}", path.display(), e);
+        }
+    });
+
+    reqwest::Body::wrap_stream(ReaderStream::new(reader))
+}
+
+/// Renames the keys of every finding in a results document according to
+/// `field_map`. A renamed key replaces any existing key of the new name.
+fn remap_fields(results: &Value, field_map: &HashMap<String, String>) -> Value {
+    match results {
+        Value::Array(findings) => Value::Array(findings.iter().map(|f| remap_fields(f, field_map)).collect()),
+        Value::Object(finding) => {
+            let mut remapped: serde_json::Map<String, Value> = finding.iter()
+                .filter(|(key, _)| !field_map.contains_key(*key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            for (key, value) in finding {
+                if let Some(renamed) = field_map.get(key) {
+                    remapped.insert(renamed.clone(), value.clone());
+                }
+            }
+            Value::Object(remapped)
+        }
+        other => other.clone(),
+    }
+}
+
+/// Drops findings below `min_severity` from a results document.
+///
+/// Findings without a mapped severity are kept so nothing is silently lost.
+fn filter_findings_by_severity(results: &Value, min_severity: Severity) -> Value {
+    let keep = |finding: &Finding| {
+        finding.severity.is_none_or(|severity| severity >= min_severity)
+    };
+
+    match results {
+        Value::Array(_) => {
+            let findings = parse_findings(results.clone());
+            let total = findings.len();
+            let kept: Vec<Value> = findings.into_iter().filter(keep).map(|finding| finding.raw).collect();
+            log::info!("Uploading {} of {} findings at or above {} severity",
+                      kept.len(), total, min_severity);
+            Value::Array(kept)
+        }
+        finding => {
+            let finding = Finding::from_value(finding.clone());
+            if keep(&finding) {
+                return finding.raw;
+            }
+            log::info!("Skipping upload of finding below {} severity", min_severity);
+            Value::Array(Vec::new())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::http_server;
+    use std::io::Write;
+
+    #[tokio::test]
+    async fn package_download_retries_server_errors() {
+        let mut package = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        package.start_file("Thor/thor-lite", zip::write::FileOptions::default()).unwrap();
+        package.write_all(b"binary").unwrap();
+        let package = package.finish().unwrap().into_inner();
+
+        let (endpoint, served) = http_server(vec![
+            (503, Vec::new()),
+            (503, Vec::new()),
+            (200, package.clone()),
+        ]).await;
+        let mut config = PyroConfig::default();
+        config.pyro.endpoint = endpoint;
+        config.pyro.download_retries = 3;
+        config.pyro.download_retry_base_delay_ms = 1;
+        let executor = PyroExecutor::new(config).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let local_package = dir.path().join(THOR_PACKAGE_FILE);
+        let metadata = executor.download_thor_package_to(&local_package, None).await.unwrap().unwrap();
+
+        assert_eq!(served.load(std::sync::atomic::Ordering::SeqCst), 3);
+        assert_eq!(std::fs::read(&local_package).unwrap(), package);
+        assert_eq!(metadata.sha256, crate::package::sha256_hex(&package));
+    }
+
+    #[tokio::test]
+    async fn http2_uploads_share_one_connection() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        // h2c: HTTP/2 without TLS, as `http2_prior_knowledge` speaks it
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = connections.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                accepted.fetch_add(1, Ordering::SeqCst);
+                let service = hyper::service::service_fn(|request: hyper::Request<hyper::Body>| async move {
+                    hyper::body::to_bytes(request.into_body()).await?;
+                    Ok::<_, hyper::Error>(hyper::Response::new(hyper::Body::empty()))
+                });
+                tokio::spawn(hyper::server::conn::Http::new().http2_only(true).serve_connection(stream, service));
+            }
+        });
+
+        let mut config = PyroConfig::default();
+        config.pyro.endpoint = endpoint.clone();
+        config.pyro.http_version = HttpVersion::Http2;
+        let executor = PyroExecutor::new(config).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let results = dir.path().join("results.ndjson");
+        std::fs::write(&results, "{\"finding\":1}\n").unwrap();
+        let url = format!("{}/api/scan-results", endpoint);
+        for _ in 0..2 {
+            let response = executor.post_results_file(&results, "key", &url).await.unwrap().unwrap();
+            assert!(response.status().is_success());
+            assert_eq!(response.version(), reqwest::Version::HTTP_2);
+        }
+
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::config::RiskConfig;
+use crate::severity::Severity;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Finding counts accumulated one finding at a time, so they can be computed
+/// while results are streamed without keeping the whole document in memory.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FindingsSummary {
+    pub total: u64,
+    pub by_severity: BTreeMap<Severity, u64>,
+    /// Findings whose level has no `Severity` mapping.
+    pub unmapped: u64,
+    /// Number of findings each YARA rule contributed to.
+    pub by_rule: BTreeMap<String, u64>,
+    pub risk: RiskSummary,
+    #[serde(skip)]
+    risk_config: RiskConfig,
+}
+
+/// Aggregate of the findings' `risk_score`s; findings without one are not
+/// counted.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RiskSummary {
+    pub max: Option<u32>,
+    /// Findings that have a risk score.
+    pub scored: u64,
+    pub sum: u64,
+}
+
+impl RiskSummary {
+    pub fn record(&mut self, risk: u32) {
+        self.max = self.max.max(Some(risk));
+        self.scored += 1;
+        self.sum += u64::from(risk);
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.scored > 0).then(|| self.sum as f64 / self.scored as f64)
+    }
+}
+
+impl FindingsSummary {
+    /// An empty summary scoring findings' risk with `risk_config`.
+    pub fn new(risk_config: RiskConfig) -> Self {
+        Self { risk_config, ..Self::default() }
+    }
+
+    pub fn from_results(results: &Value, risk_config: RiskConfig) -> Self {
+        let mut summary = Self::new(risk_config);
+        summary.record_document(results);
+        summary
+    }
+
+    /// Records a top-level document: each element of an array, or the value itself.
+    pub fn record_document(&mut self, document: &Value) {
+        match document {
+            Value::Array(findings) => findings.iter().for_each(|f| self.record(f)),
+            finding => self.record(finding),
+        }
+    }
+
+    pub fn record(&mut self, finding: &Value) {
+        self.total += 1;
+        let severity = Severity::of_finding(finding);
+        match severity {
+            Some(severity) => *self.by_severity.entry(severity).or_insert(0) += 1,
+            None => self.unmapped += 1,
+        }
+        if let Some(risk) = self.risk_config.risk_score(finding_score(finding), severity) {
+            self.risk.record(risk);
+        }
+        for rule in finding_rules(finding) {
+            *self.by_rule.entry(rule).or_insert(0) += 1;
+        }
+    }
+
+    /// Most severe level with at least one finding.
+    pub fn highest_severity(&self) -> Option<Severity> {
+        self.by_severity.iter()
+            .rev()
+            .find(|(_, count)| **count > 0)
+            .map(|(severity, _)| *severity)
+    }
+
+    /// Rules ordered by descending match count.
+    pub fn top_rules(&self, limit: usize) -> Vec<(&str, u64)> {
+        let mut rules: Vec<(&str, u64)> = self.by_rule.iter().map(|(rule, count)| (rule.as_str(), *count)).collect();
+        rules.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        rules.truncate(limit);
+        rules
+    }
+}
+
+/// A Thor finding with the fields consumers need pulled out. Thor's output
+/// differs between versions, so every field may be missing; `raw` keeps the
+/// finding as Thor wrote it for anything not modeled here.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub path: Option<String>,
+    /// Matched YARA rules, as `finding_rules` reads them.
+    pub rules: Vec<String>,
+    pub severity: Option<Severity>,
+    pub matched_strings: Vec<String>,
+    /// Thor's score, as `finding_score` reads it.
+    pub score: Option<f64>,
+    /// 0-100 risk from `score`, or from `severity` when there is no score;
+    /// see `RiskConfig`. Computed with the default mapping until `with_risk`.
+    pub risk_score: Option<u32>,
+    pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    pub module: Option<String>,
+    /// Archive on disk that `path` was extracted from, with `--expand-archives`.
+    pub archive_file: Option<String>,
+    #[serde(skip)]
+    pub raw: Value,
+}
+
+impl Finding {
+    pub fn from_value(raw: Value) -> Self {
+        let string = |key: &str| raw.get(key).and_then(Value::as_str).map(str::to_string);
+        let score = finding_score(&raw);
+        let severity = Severity::of_finding(&raw);
+        Self {
+            path: finding_path(&raw).map(str::to_string),
+            rules: finding_rules(&raw),
+            severity,
+            matched_strings: matched_strings(&raw),
+            score,
+            risk_score: RiskConfig::default().risk_score(score, severity),
+            timestamp: ["time", "timestamp"].iter()
+                .find_map(|key| raw.get(*key).and_then(Value::as_str))
+                .and_then(parse_timestamp),
+            module: string("module"),
+            archive_file: string("archive_file"),
+            raw,
+        }
+    }
+
+    /// Recomputes `risk_score` with the configured mapping.
+    pub fn with_risk(mut self, risk_config: &RiskConfig) -> Self {
+        self.risk_score = risk_config.risk_score(self.score, self.severity);
+        self
+    }
+}
+
+/// Findings in a results document: each element of an array, or the value
+/// itself.
+pub fn parse_findings(document: Value) -> Vec<Finding> {
+    match document {
+        Value::Array(findings) => findings.into_iter().map(Finding::from_value).collect(),
+        finding => vec![Finding::from_value(finding)],
+    }
+}
+
+/// Strings a YARA match hit on: a `matched_strings` array, `matched` arrays
+/// in `reasons`, or Thor's classic `matched_N` fields.
+fn matched_strings(finding: &Value) -> Vec<String> {
+    let strings = |value: Option<&Value>| -> Vec<String> {
+        value.and_then(Value::as_array).into_iter().flatten()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect()
+    };
+
+    let mut matched = strings(finding.get("matched_strings"));
+    for reason in finding.get("reasons").and_then(Value::as_array).into_iter().flatten() {
+        matched.extend(strings(reason.get("matched")));
+    }
+    if let Some(object) = finding.as_object() {
+        for (key, value) in object {
+            let numbered = key.to_ascii_lowercase().strip_prefix("matched_")
+                .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+            if let (true, Some(value)) = (numbered, value.as_str()) {
+                matched.push(value.to_string());
+            }
+        }
+    }
+    matched
+}
+
+/// RFC 3339, or Thor's `2024-01-31 12:00:00` taken as UTC.
+fn parse_timestamp(time: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(time.trim())
+        .map(|time| time.with_timezone(&chrono::Utc))
+        .or_else(|_| chrono::NaiveDateTime::parse_from_str(time.trim(), "%Y-%m-%d %H:%M:%S").map(|time| time.and_utc()))
+        .ok()
+}
+
+/// Thor's total score for a finding: its `score` field (a number or a
+/// numeric string), else the sum of the `score`s in its `reasons`.
+pub fn finding_score(finding: &Value) -> Option<f64> {
+    let number = |value: &Value| match value {
+        Value::String(score) => score.trim().parse().ok(),
+        score => score.as_f64(),
+    };
+
+    if let Some(score) = finding.get("score").and_then(number) {
+        return Some(score);
+    }
+    let reason_scores: Vec<f64> = finding.get("reasons").and_then(Value::as_array).into_iter().flatten()
+        .filter_map(|reason| reason.get("score").and_then(number))
+        .collect();
+    (!reason_scores.is_empty()).then(|| reason_scores.iter().sum())
+}
+
+/// Path of the file a finding refers to, if Thor reported one.
+pub fn finding_path(finding: &Value) -> Option<&str> {
+    ["file", "path", "filepath", "FILE"]
+        .iter()
+        .find_map(|key| finding.get(*key).and_then(Value::as_str))
+}
+
+/// Names of the YARA rules that matched in a finding.
+///
+/// Understands explicit rule fields (`rule`, `rule_name`, `rulename`), a
+/// `reasons` array with `rule_name`/`signature` entries, and Thor's classic
+/// `reason_N` strings of the form `YARA Rule <name> / <description>`.
+pub fn finding_rules(finding: &Value) -> Vec<String> {
+    let mut rules: Vec<String> = Vec::new();
+    let mut add = |rule: &str| {
+        let rule = rule.trim();
+        if !rule.is_empty() && !rules.iter().any(|r| r == rule) {
+            rules.push(rule.to_string());
+        }
+    };
+
+    for key in ["rule", "rule_name", "rulename"] {
+        if let Some(rule) = finding.get(key).and_then(Value::as_str) {
+            add(rule);
+        }
+    }
+
+    if let Some(reasons) = finding.get("reasons").and_then(Value::as_array) {
+        for reason in reasons {
+            let rule = reason.get("rule_name")
+                .or_else(|| reason.get("signature").and_then(|s| s.get("rule_name")))
+                .and_then(Value::as_str);
+            if let Some(rule) = rule {
+                add(rule);
+            }
+        }
+    }
+
+    if let Some(object) = finding.as_object() {
+        for (key, value) in object {
+            let key = key.to_ascii_lowercase();
+            if !key.starts_with("reason_") {
+                continue;
+            }
+            if let Some(reason) = value.as_str() {
+                let lower = reason.to_ascii_lowercase();
+                if let Some(rest) = lower.strip_prefix("yara rule ") {
+                    let name_len = rest.find(" /").unwrap_or(rest.len());
+                    let start = "yara rule ".len();
+                    add(&reason[start..start + name_len]);
+                }
+            }
+        }
+    }
+
+    rules
+}
@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::io::BufRead;
+use std::time::Duration;
+
+use super::yara_rules_redb::{ExportFormat, ExportRecord, YaraRule, YaraRulesRedbHook};
+
+const FEED_TIMEOUT_SECONDS: u64 = 120;
+
+/// How a rule compares between an upstream feed and the local database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedStatus {
+    /// Upstream only, under a name the database doesn't have.
+    New,
+    /// Same name on both sides, different content.
+    Modified,
+    /// In the database but not in the feed.
+    LocalOnly,
+    /// Content stored locally already.
+    Unchanged,
+}
+
+impl FeedStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeedStatus::New => "new",
+            FeedStatus::Modified => "modified",
+            FeedStatus::LocalOnly => "local-only",
+            FeedStatus::Unchanged => "unchanged",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedDiffEntry {
+    pub name: String,
+    pub status: FeedStatus,
+    pub upstream_hash: Option<String>,
+    pub local_hash: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct FeedDiff {
+    pub url: String,
+    pub entries: Vec<FeedDiffEntry>,
+}
+
+impl FeedDiff {
+    pub fn count(&self, status: FeedStatus) -> usize {
+        self.entries.iter().filter(|entry| entry.status == status).count()
+    }
+}
+
+/// Fetches a rule bundle in `export_database` format from `url` and
+/// compares its rules with the database. The database is only read.
+///
+/// Rules are matched by content hash first, so a rule stored under another
+/// name still counts as unchanged, and by name second to find modified
+/// rules. Metadata and intel records in the bundle are ignored.
+pub async fn diff_feed(hook: &YaraRulesRedbHook, url: &str, format: ExportFormat) -> Result<FeedDiff> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(FEED_TIMEOUT_SECONDS))
+        .build()
+        .context("Failed to create HTTP client")?;
+
+    log::info!("Fetching rule feed: {}", url);
+    let response = client.get(url).send().await
+        .with_context(|| format!("Failed to fetch rule feed: {}", url))?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("Failed to fetch rule feed {}: HTTP {}", url, response.status()));
+    }
+    let body = response.bytes().await
+        .context("Failed to read rule feed")?;
+
+    let upstream = bundle_rules(body.as_ref(), format)?;
+    let local = hook.list_yara_rules().await?;
+
+    Ok(FeedDiff {
+        url: url.to_string(),
+        entries: compare_rules(&upstream, &local),
+    })
+}
+
+fn bundle_rules<R: BufRead>(reader: R, format: ExportFormat) -> Result<Vec<YaraRule>> {
+    let records: Vec<ExportRecord> = match format {
+        ExportFormat::Json => serde_json::from_reader(reader)
+            .context("Failed to parse rule feed as a JSON export")?,
+        ExportFormat::Ndjson => reader.lines()
+            .enumerate()
+            .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+            .map(|(line_number, line)| {
+                let line = line.context("Failed to read rule feed")?;
+                serde_json::from_str(&line)
+                    .with_context(|| format!("Invalid record on line {} of the rule feed", line_number + 1))
+            })
+            .collect::<Result<_>>()?,
+    };
+
+    Ok(records.into_iter()
+        .filter_map(|record| match record {
+            ExportRecord::Rule(rule) => Some(rule),
+            _ => None,
+        })
+        .collect())
+}
+
+fn compare_rules(upstream: &[YaraRule], local: &[YaraRule]) -> Vec<FeedDiffEntry> {
+    let local_hashes: HashSet<&str> = local.iter().map(|rule| rule.hash.as_str()).collect();
+    let mut local_by_name: BTreeMap<&str, &YaraRule> = BTreeMap::new();
+    for rule in local {
+        local_by_name.entry(rule.name.as_str()).or_insert(rule);
+    }
+
+    let mut entries: Vec<FeedDiffEntry> = upstream.iter()
+        .map(|rule| {
+            let (status, local_hash) = if local_hashes.contains(rule.hash.as_str()) {
+                (FeedStatus::Unchanged, Some(rule.hash.clone()))
+            } else if let Some(local_rule) = local_by_name.get(rule.name.as_str()) {
+                (FeedStatus::Modified, Some(local_rule.hash.clone()))
+            } else {
+                (FeedStatus::New, None)
+            };
+            FeedDiffEntry {
+                name: rule.name.clone(),
+                status,
+                upstream_hash: Some(rule.hash.clone()),
+                local_hash,
+            }
+        })
+        .collect();
+
+    let upstream_hashes: HashSet<&str> = upstream.iter().map(|rule| rule.hash.as_str()).collect();
+    let upstream_names: HashSet<&str> = upstream.iter().map(|rule| rule.name.as_str()).collect();
+    entries.extend(local.iter()
+        .filter(|rule| !upstream_hashes.contains(rule.hash.as_str()) && !upstream_names.contains(rule.name.as_str()))
+        .map(|rule| FeedDiffEntry {
+            name: rule.name.clone(),
+            status: FeedStatus::LocalOnly,
+            upstream_hash: None,
+            local_hash: Some(rule.hash.clone()),
+        }));
+
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    entries
+}
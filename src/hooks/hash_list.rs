@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::path::Path;
+use tokio::fs;
+
+use super::yara_rules_redb::{content_hash, intel_key, ScanTier, ThreatIntelIndicator, YaraRule, YaraRulesRedbHook};
+
+/// Upper bound on hashes per generated rule; larger lists are split.
+pub const MAX_HASHES_PER_RULE: usize = 1000;
+
+#[derive(Debug, Default)]
+pub struct HashListReport {
+    pub hashes_read: u64,
+    pub duplicates: u64,
+    pub invalid_lines: u64,
+    pub indicators_added: u64,
+    pub indicators_merged: u64,
+    pub rules_generated: Vec<String>,
+}
+
+/// Imports a flat list of SHA-256 hashes as `sha256` indicators.
+///
+/// Lines may be bare hashes or `sha256sum` output; blank lines and `#`
+/// comments are ignored. With `generate_rule`, the deduplicated hashes are
+/// also stored as `hash`-module YARA rules of at most `MAX_HASHES_PER_RULE`
+/// hashes each, with ids derived from the file name so re-imports replace them.
+pub async fn import_hash_list(
+    hook: &YaraRulesRedbHook,
+    path: &Path,
+    generate_rule: bool,
+) -> Result<HashListReport> {
+    let content = fs::read_to_string(path).await
+        .with_context(|| format!("Failed to read hash list: {}", path.display()))?;
+
+    let mut report = HashListReport::default();
+    let mut hashes = BTreeSet::new();
+
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let candidate = line.split_whitespace().next().unwrap_or_default().to_ascii_lowercase();
+        if candidate.len() != 64 || !candidate.chars().all(|c| c.is_ascii_hexdigit()) {
+            log::warn!("Skipping invalid SHA-256 on line {} of {}: {}", line_number + 1, path.display(), line);
+            report.invalid_lines += 1;
+            continue;
+        }
+
+        report.hashes_read += 1;
+        if !hashes.insert(candidate) {
+            report.duplicates += 1;
+        }
+    }
+
+    let source = format!("hash-list:{}", path.display());
+    let now = hook.now();
+
+    for hash in &hashes {
+        let indicator = ThreatIntelIndicator {
+            id: intel_key("sha256", hash),
+            indicator_type: "sha256".to_string(),
+            value: hash.clone(),
+            confidence: 0.8,
+            threat_score: 0.8,
+            first_seen: now,
+            last_seen: now,
+            source_feeds: vec![source.clone()],
+            associated_campaigns: vec![],
+            mitre_mapping: vec![],
+            quantum_resistant: false,
+        };
+
+        if hook.merge_threat_intel(&indicator).await? {
+            report.indicators_added += 1;
+        } else {
+            report.indicators_merged += 1;
+        }
+    }
+
+    if generate_rule && !hashes.is_empty() {
+        let list_name = rule_identifier(
+            path.file_stem().and_then(|s| s.to_str()).unwrap_or("hash_list")
+        );
+        let hashes: Vec<&String> = hashes.iter().collect();
+
+        for (part, chunk) in hashes.chunks(MAX_HASHES_PER_RULE).enumerate() {
+            let name = format!("hashlist_{}_{}", list_name, part + 1);
+            let content = generate_hash_rule(&name, &source, chunk);
+
+            let rule = YaraRule {
+                id: format!("hash-list-{}-{}", list_name, part + 1),
+                name: name.clone(),
+                hash: content_hash(&content),
+                content,
+                author: "Hash list import".to_string(),
+                description: format!("Matches {} SHA-256 hashes from {}", chunk.len(), path.display()),
+                tags: vec!["hash-list".to_string()],
+                severity: "high".to_string(),
+                created_at: now,
+                updated_at: now,
+                version: "1.0".to_string(),
+                source: path.to_string_lossy().to_string(),
+                mitre_tactics: vec![],
+                mitre_techniques: vec![],
+                threat_actors: vec![],
+                malware_families: vec![],
+                scan_tier: ScanTier::default(),
+            };
+
+            hook.store_yara_rule(&rule).await?;
+            report.rules_generated.push(name);
+        }
+    }
+
+    log::info!("Imported {} unique hashes from {} ({} duplicates, {} invalid lines, {} rules generated)",
+              hashes.len(), path.display(), report.duplicates, report.invalid_lines,
+              report.rules_generated.len());
+    Ok(report)
+}
+
+fn generate_hash_rule(name: &str, source: &str, hashes: &[&String]) -> String {
+    let conditions: Vec<String> = hashes
+        .iter()
+        .map(|hash| format!("        hash.sha256(0, filesize) == \"{}\"", hash))
+        .collect();
+
+    format!(
+        "import \"hash\"\n\nrule {} {{\n    meta:\n        description = \"SHA-256 hash list match\"\n        source = \"{}\"\n        hash_count = {}\n    condition:\n{}\n}}\n",
+        name,
+        source.replace('\\', "\\\\").replace('"', "\\\""),
+        hashes.len(),
+        conditions.join(" or\n")
+    )
+}
+
+/// Turns a file name into a valid YARA identifier fragment.
+fn rule_identifier(stem: &str) -> String {
+    stem.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
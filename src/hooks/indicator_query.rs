@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use super::yara_rules_redb::ThreatIntelIndicator;
+
+/// An IP network such as `10.0.0.0/8`; a bare address is a single-host
+/// network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    address: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    /// Whether `other` lies entirely inside this network. Addresses of the
+    /// other IP version never match.
+    pub fn contains(&self, other: &IpNetwork) -> bool {
+        if other.prefix < self.prefix {
+            return false;
+        }
+        match (self.address, other.address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(self.prefix)).unwrap_or(0);
+                u32::from(network) & mask == u32::from(address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(self.prefix)).unwrap_or(0);
+                u128::from(network) & mask == u128::from(address) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (address, prefix) = match s.trim().split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (s.trim(), None),
+        };
+        let address: IpAddr = address.parse()
+            .with_context(|| format!("Invalid IP address: {}", address))?;
+        let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().ok()
+                .filter(|prefix| *prefix <= max_prefix)
+                .with_context(|| format!("Invalid prefix length in {}: expected 0-{}", s, max_prefix))?,
+            None => max_prefix,
+        };
+        Ok(Self { address, prefix })
+    }
+}
+
+/// A domain to look up: `evil.com` matches the domain and its subdomains,
+/// and `*` matches any run of characters (`*.evil.com` only subdomains).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomainPattern(String);
+
+impl DomainPattern {
+    pub fn new(pattern: &str) -> Self {
+        Self(normalize_domain(pattern))
+    }
+
+    pub fn matches(&self, domain: &str) -> bool {
+        let domain = normalize_domain(domain);
+        if self.0.contains('*') {
+            return wildcard_match(self.0.as_bytes(), domain.as_bytes());
+        }
+        domain == self.0 || domain.strip_suffix(&self.0).is_some_and(|rest| rest.ends_with('.'))
+    }
+}
+
+/// How an indicator search matches values, picked from what the query
+/// looks like. Each strategy only applies to indicator types it makes sense
+/// for; see `matches`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndicatorQuery {
+    /// IP indicators inside the network.
+    Network(IpNetwork),
+    /// Domain indicators, and the host part of URL indicators.
+    Domain(DomainPattern),
+    /// Substring of any indicator value.
+    Text(String),
+}
+
+impl IndicatorQuery {
+    pub fn parse(query: &str) -> Self {
+        let query = query.trim();
+        if let Ok(network) = query.parse() {
+            return IndicatorQuery::Network(network);
+        }
+        let domain_like = query.contains('*') || (query.contains('.')
+            && query.chars().any(|c| c.is_ascii_alphabetic())
+            && query.chars().all(|c| c.is_ascii_alphanumeric() || "-_.*".contains(c)));
+        if domain_like {
+            IndicatorQuery::Domain(DomainPattern::new(query))
+        } else {
+            IndicatorQuery::Text(query.to_string())
+        }
+    }
+
+    pub fn matches(&self, indicator: &ThreatIntelIndicator) -> bool {
+        match self {
+            IndicatorQuery::Network(network) => matches!(indicator.indicator_type.as_str(), "ipv4" | "ipv6")
+                && indicator.value.parse().is_ok_and(|value| network.contains(&value)),
+            IndicatorQuery::Domain(pattern) => match indicator.indicator_type.as_str() {
+                "domain" => pattern.matches(&indicator.value),
+                "url" => url_host(&indicator.value).is_some_and(|host| pattern.matches(host)),
+                _ => false,
+            },
+            IndicatorQuery::Text(text) => indicator.value.contains(text.as_str()),
+        }
+    }
+}
+
+fn normalize_domain(domain: &str) -> String {
+    domain.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Host of a URL such as `http://user@host:8080/path`.
+fn url_host(url: &str) -> Option<&str> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let host = host.split(':').next()?;
+    (!host.is_empty()).then_some(host)
+}
+
+/// Glob match where `*` matches any run of bytes, including none.
+pub(crate) fn wildcard_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&b| b == b'*')
+}
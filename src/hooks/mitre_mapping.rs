@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// ATT&CK ids to set on every rule with a given name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MitreMapping {
+    pub techniques: Vec<String>,
+    /// Left out of the mapping file means the rule's tactics are kept.
+    pub tactics: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct MitreMappingReport {
+    pub entries: u64,
+    pub rules_updated: u64,
+    /// Matching rules that already had the mapped ids.
+    pub rules_unchanged: u64,
+    /// Mapping entries whose rule name matched no stored rule.
+    pub unmatched_entries: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonEntry {
+    Techniques(Vec<String>),
+    Full {
+        #[serde(default)]
+        techniques: Vec<String>,
+        #[serde(default)]
+        tactics: Vec<String>,
+    },
+}
+
+/// Reads a rule-name → ATT&CK mapping.
+///
+/// `.csv` files have `rule_name,technique[,tactic]` rows, where a rule may
+/// appear on several rows and a cell may hold several `;`-separated ids; a
+/// leading `rule_name` header row and `#` comments are skipped. Anything
+/// else is read as a JSON object mapping each rule name to either a list of
+/// technique ids or `{"techniques": [...], "tactics": [...]}`.
+pub fn load_mitre_mapping(path: &Path) -> Result<BTreeMap<String, MitreMapping>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read MITRE mapping: {}", path.display()))?;
+
+    let is_csv = path.extension().and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+
+    if is_csv {
+        parse_csv(&content).with_context(|| format!("Invalid MITRE mapping CSV: {}", path.display()))
+    } else {
+        parse_json(&content).with_context(|| format!("Invalid MITRE mapping JSON: {}", path.display()))
+    }
+}
+
+fn parse_json(content: &str) -> Result<BTreeMap<String, MitreMapping>> {
+    let entries: BTreeMap<String, JsonEntry> = serde_json::from_str(content)?;
+
+    Ok(entries.into_iter()
+        .map(|(rule, entry)| {
+            let mapping = match entry {
+                JsonEntry::Techniques(techniques) => MitreMapping { techniques, tactics: Vec::new() },
+                JsonEntry::Full { techniques, tactics } => MitreMapping { techniques, tactics },
+            };
+            (rule, normalized(mapping))
+        })
+        .collect())
+}
+
+fn parse_csv(content: &str) -> Result<BTreeMap<String, MitreMapping>> {
+    let mut mappings: BTreeMap<String, MitreMapping> = BTreeMap::new();
+
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let columns: Vec<&str> = line.split(',').map(|column| column.trim().trim_matches('"')).collect();
+        if line_number == 0 && columns[0].eq_ignore_ascii_case("rule_name") {
+            continue;
+        }
+        if columns.len() < 2 || columns.len() > 3 || columns[0].is_empty() {
+            anyhow::bail!("line {}: expected rule_name,technique[,tactic], got {:?}", line_number + 1, line);
+        }
+
+        let mapping = mappings.entry(columns[0].to_string()).or_default();
+        mapping.techniques.extend(split_ids(columns[1]));
+        if let Some(tactics) = columns.get(2) {
+            mapping.tactics.extend(split_ids(tactics));
+        }
+    }
+
+    Ok(mappings.into_iter().map(|(rule, mapping)| (rule, normalized(mapping))).collect())
+}
+
+fn split_ids(cell: &str) -> impl Iterator<Item = String> + '_ {
+    cell.split(';').map(str::trim).filter(|id| !id.is_empty()).map(str::to_string)
+}
+
+/// Sorted, deduplicated ids so re-applying a mapping is a no-op.
+fn normalized(mut mapping: MitreMapping) -> MitreMapping {
+    for ids in [&mut mapping.techniques, &mut mapping.tactics] {
+        ids.sort();
+        ids.dedup();
+    }
+    mapping
+}
@@ -1,6 +1,18 @@
+pub mod feed;
+pub mod hash_list;
+pub mod indicator_query;
+pub mod mitre_mapping;
+pub mod taxii;
 pub mod yara_rules_redb;
+pub mod yara_source;
 
+pub use feed::{diff_feed, FeedStatus};
+pub use hash_list::{import_hash_list, HashListReport};
+pub use mitre_mapping::{load_mitre_mapping, MitreMappingReport};
+pub use taxii::{fetch_taxii, TaxiiPullReport};
 pub use yara_rules_redb::{
-    YaraRulesRedbHook, YaraRule, RuleMetadata, ThreatIntelIndicator,
-    initialize_yara_rules_hook, sync_yara_rules_from_directory
-};
\ No newline at end of file
+    YaraRulesRedbHook, YaraRule, ScanTier, RuleMetadata, ThreatIntelIndicator, IntelKeyMigration, MergeStrategy, MergeReport,
+    IntegrityPolicy, DuplicateNamePolicy, NameConflict, RenamedRule, ExportFormat, MetadataFormat,
+    DeletedRule, MultiSyncReport, SyncOptions, SyncReport, SyncStatus,
+    initialize_yara_rules_hook, sync_yara_rules_from_directory, sync_directories, prune_missing_rules, export_yara_rules_to_directory
+};
@@ -0,0 +1,405 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Duration;
+
+use super::yara_rules_redb::{intel_key, ThreatIntelIndicator, YaraRulesRedbHook};
+
+const TAXII_MEDIA_TYPE: &str = "application/taxii+json;version=2.1";
+const TAXII_MAX_ATTEMPTS: u32 = 3;
+const TAXII_RETRY_DELAY_MS: u64 = 1000;
+const TAXII_TIMEOUT_SECONDS: u64 = 60;
+
+#[derive(Debug, Deserialize)]
+struct TaxiiEnvelope {
+    #[serde(default)]
+    more: bool,
+    next: Option<String>,
+    #[serde(default)]
+    objects: Vec<Value>,
+}
+
+#[derive(Debug, Default)]
+pub struct TaxiiPullReport {
+    pub collection: String,
+    pub objects_fetched: u64,
+    pub indicators_added: u64,
+    pub indicators_merged: u64,
+    pub objects_skipped: u64,
+    pub cursor: Option<String>,
+}
+
+/// Polls a TAXII 2.1 collection and stores its STIX indicators in the hook.
+///
+/// Only objects added after the last successful poll of this server/collection
+/// pair are requested. Every page is requested with the same `added_after`,
+/// since a `next` token belongs to the query that returned it. The newest
+/// `X-TAXII-Date-Added-Last` the server sent is saved as the cursor once the
+/// last page is stored; an interrupted pull starts over from the old cursor,
+/// and the indicators it already stored are merged again.
+pub async fn fetch_taxii(
+    hook: &YaraRulesRedbHook,
+    server_url: &str,
+    collection: &str,
+    api_key: Option<&str>,
+) -> Result<TaxiiPullReport> {
+    let server_url = server_url.trim_end_matches('/');
+    let cursor_key = format!("{}|{}", server_url, collection);
+    let objects_url = format!("{}/collections/{}/objects/", server_url, collection);
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(TAXII_TIMEOUT_SECONDS))
+        .build()
+        .context("Failed to create HTTP client")?;
+
+    let added_after = hook.get_taxii_cursor(&cursor_key).await?;
+    let mut report = TaxiiPullReport {
+        collection: collection.to_string(),
+        cursor: added_after.clone(),
+        ..Default::default()
+    };
+
+    log::info!("Polling TAXII collection {} at {} (added after: {})",
+              collection, server_url, added_after.as_deref().unwrap_or("beginning"));
+
+    // Newest date_added the server reported; objects' own timestamps say
+    // nothing about when they were added, so they aren't used
+    let mut date_added_latest: Option<String> = None;
+    let mut next: Option<String> = None;
+    loop {
+        let mut query = vec![("match[type]", "indicator".to_string())];
+        if let Some(added_after) = &added_after {
+            query.push(("added_after", added_after.clone()));
+        }
+        if let Some(next) = &next {
+            query.push(("next", next.clone()));
+        }
+
+        let (envelope, date_added_last) = fetch_page(&client, &objects_url, &query, api_key).await?;
+        if let Some(date_added_last) = date_added_last {
+            if date_added_latest.as_deref().is_none_or(|latest| date_added_last.as_str() > latest) {
+                date_added_latest = Some(date_added_last);
+            }
+        }
+
+        for object in &envelope.objects {
+            report.objects_fetched += 1;
+
+            let indicators = stix_to_indicators(object, collection, hook.now());
+            if indicators.is_empty() {
+                report.objects_skipped += 1;
+                continue;
+            }
+
+            for indicator in indicators {
+                if hook.merge_threat_intel(&indicator).await? {
+                    report.indicators_added += 1;
+                } else {
+                    report.indicators_merged += 1;
+                }
+            }
+        }
+
+        match envelope.next {
+            Some(token) if envelope.more => next = Some(token),
+            _ => break,
+        }
+    }
+
+    match date_added_latest {
+        Some(latest) if report.cursor.as_deref().is_none_or(|cursor| latest.as_str() > cursor) => {
+            hook.set_taxii_cursor(&cursor_key, &latest).await?;
+            report.cursor = Some(latest);
+        }
+        None if report.objects_fetched > 0 => {
+            log::warn!("TAXII server sent no X-TAXII-Date-Added-Last; the cursor for {} is unchanged", collection);
+        }
+        _ => {}
+    }
+
+    log::info!("TAXII pull of {} complete: {} objects, {} new indicators, {} merged, {} skipped",
+              collection, report.objects_fetched, report.indicators_added,
+              report.indicators_merged, report.objects_skipped);
+    Ok(report)
+}
+
+/// Fetches and parses a single envelope page, retrying transient failures.
+///
+/// Connection errors, 5xx/429 responses and unparseable bodies are retried;
+/// other HTTP errors fail immediately. Nothing is written to the store here.
+async fn fetch_page(
+    client: &reqwest::Client,
+    url: &str,
+    query: &[(&str, String)],
+    api_key: Option<&str>,
+) -> Result<(TaxiiEnvelope, Option<String>)> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+
+        let mut request = client.get(url)
+            .query(query)
+            .header("Accept", TAXII_MEDIA_TYPE);
+        if let Some(api_key) = api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        let error = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                let date_added_last = response.headers()
+                    .get("X-TAXII-Date-Added-Last")
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string);
+
+                match response.json::<TaxiiEnvelope>().await {
+                    Ok(envelope) => return Ok((envelope, date_added_last)),
+                    Err(e) => anyhow::anyhow!("Failed to parse TAXII envelope: {}", e),
+                }
+            }
+            Ok(response) => {
+                let status = response.status();
+                if !(status.is_server_error() || status.as_u16() == 429) {
+                    return Err(anyhow::anyhow!("TAXII server returned HTTP {}", status));
+                }
+                anyhow::anyhow!("TAXII server returned HTTP {}", status)
+            }
+            Err(e) => anyhow::anyhow!("TAXII request failed: {}", e),
+        };
+
+        if attempt >= TAXII_MAX_ATTEMPTS {
+            return Err(error.context(format!("Giving up on {} after {} attempts", url, attempt)));
+        }
+
+        let delay = TAXII_RETRY_DELAY_MS * 2u64.pow(attempt - 1);
+        log::warn!("{} (attempt {}/{}), retrying in {}ms", error, attempt, TAXII_MAX_ATTEMPTS, delay);
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+    }
+}
+
+/// Maps a STIX 2.1 indicator object to one indicator per comparison in its pattern.
+fn stix_to_indicators(object: &Value, collection: &str, now: chrono::DateTime<chrono::Utc>) -> Vec<ThreatIntelIndicator> {
+    if object.get("type").and_then(Value::as_str) != Some("indicator") {
+        return Vec::new();
+    }
+
+    let (Some(stix_id), Some(pattern)) = (
+        object.get("id").and_then(Value::as_str),
+        object.get("pattern").and_then(Value::as_str),
+    ) else {
+        log::warn!("Skipping malformed STIX indicator: missing id or pattern");
+        return Vec::new();
+    };
+
+    let comparisons = parse_stix_pattern(pattern);
+    if comparisons.is_empty() {
+        log::warn!("Skipping STIX indicator {} with unsupported pattern: {}", stix_id, pattern);
+        return Vec::new();
+    }
+
+    let timestamp = |field: &str| {
+        object.get(field)
+            .and_then(Value::as_str)
+            .and_then(|ts| chrono::DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&chrono::Utc))
+    };
+    let first_seen = timestamp("valid_from").or_else(|| timestamp("created")).unwrap_or(now);
+    let last_seen = timestamp("modified").unwrap_or(first_seen);
+
+    let confidence = object.get("confidence")
+        .and_then(Value::as_f64)
+        .map(|c| (c / 100.0).clamp(0.0, 1.0))
+        .unwrap_or(0.5);
+
+    let mut mitre_mapping = Vec::new();
+    if let Some(phases) = object.get("kill_chain_phases").and_then(Value::as_array) {
+        for phase in phases {
+            if phase.get("kill_chain_name").and_then(Value::as_str) == Some("mitre-attack") {
+                if let Some(name) = phase.get("phase_name").and_then(Value::as_str) {
+                    mitre_mapping.push(name.to_string());
+                }
+            }
+        }
+    }
+    if let Some(references) = object.get("external_references").and_then(Value::as_array) {
+        for reference in references {
+            if reference.get("source_name").and_then(Value::as_str) == Some("mitre-attack") {
+                if let Some(external_id) = reference.get("external_id").and_then(Value::as_str) {
+                    mitre_mapping.push(external_id.to_string());
+                }
+            }
+        }
+    }
+
+    comparisons
+        .into_iter()
+        .map(|(indicator_type, value)| ThreatIntelIndicator {
+            id: intel_key(&indicator_type, &value),
+            indicator_type,
+            value,
+            confidence,
+            threat_score: confidence,
+            first_seen,
+            last_seen,
+            source_feeds: vec![format!("taxii:{}", collection)],
+            associated_campaigns: Vec::new(),
+            mitre_mapping: mitre_mapping.clone(),
+            quantum_resistant: false,
+        })
+        .collect()
+}
+
+/// Extracts `(indicator_type, value)` pairs from the equality comparisons of a
+/// STIX pattern such as `[file:hashes.'SHA-256' = 'abc...' OR ipv4-addr:value = '1.2.3.4']`.
+fn parse_stix_pattern(pattern: &str) -> Vec<(String, String)> {
+    let mut comparisons = Vec::new();
+    let mut rest = pattern;
+
+    while let Some(eq) = rest.find(" = '") {
+        let lhs_start = rest[..eq]
+            .rfind(|c: char| c == '[' || c == '(' || c.is_whitespace())
+            .map_or(0, |i| i + 1);
+        let object_path = rest[lhs_start..eq].trim();
+
+        let value_start = eq + 4;
+        let Some(value_len) = find_stix_string_end(&rest[value_start..]) else {
+            break;
+        };
+        let value = rest[value_start..value_start + value_len].replace("\\'", "'");
+
+        if let Some(indicator_type) = stix_indicator_type(object_path) {
+            comparisons.push((indicator_type.to_string(), value));
+        }
+
+        rest = &rest[value_start + value_len + 1..];
+    }
+
+    comparisons
+}
+
+fn find_stix_string_end(s: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '\'' if !escaped => return Some(i),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn stix_indicator_type(object_path: &str) -> Option<&'static str> {
+    let path = object_path.to_ascii_lowercase();
+    let indicator_type = match path.as_str() {
+        "file:hashes.'sha-256'" | "file:hashes.sha256" => "sha256",
+        "file:hashes.'sha-1'" | "file:hashes.sha1" => "sha1",
+        "file:hashes.md5" | "file:hashes.'md5'" => "md5",
+        "file:name" => "filename",
+        "ipv4-addr:value" => "ipv4",
+        "ipv6-addr:value" => "ipv6",
+        "domain-name:value" => "domain",
+        "url:value" => "url",
+        "email-addr:value" => "email",
+        _ => return None,
+    };
+    Some(indicator_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{recording_http_server, temp_hook};
+
+    fn stix_indicator(id: &str, pattern: &str) -> Value {
+        serde_json::json!({
+            "type": "indicator",
+            "id": id,
+            "pattern": pattern,
+            "confidence": 80,
+            "valid_from": "2024-01-01T00:00:00Z",
+            "modified": "2024-06-01T00:00:00Z",
+            "external_references": [{"source_name": "mitre-attack", "external_id": "T1105"}],
+        })
+    }
+
+    fn envelope(objects: Vec<Value>, next: Option<&str>) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({"more": next.is_some(), "next": next, "objects": objects})).unwrap()
+    }
+
+    #[test]
+    fn pattern_comparisons_become_typed_values() {
+        let pattern = "[file:hashes.'SHA-256' = 'abc123' OR ipv4-addr:value = '10.0.0.1'] \
+                       AND [domain-name:value = 'it\\'s.example' AND process:pid = '4']";
+        assert_eq!(parse_stix_pattern(pattern), [
+            ("sha256".to_string(), "abc123".to_string()),
+            ("ipv4".to_string(), "10.0.0.1".to_string()),
+            ("domain".to_string(), "it's.example".to_string()),
+        ]);
+        assert!(parse_stix_pattern("[process:pid = '4']").is_empty());
+    }
+
+    #[test]
+    fn indicator_objects_map_to_one_indicator_per_comparison() {
+        let object = stix_indicator("indicator--1", "[url:value = 'http://evil.example/' OR email-addr:value = 'a@evil.example']");
+        let indicators = stix_to_indicators(&object, "feed", chrono::Utc::now());
+
+        assert_eq!(indicators.len(), 2);
+        assert_eq!(indicators[0].id, intel_key("url", "http://evil.example/"));
+        assert_eq!(indicators[1].indicator_type, "email");
+        for indicator in &indicators {
+            assert_eq!(indicator.confidence, 0.8);
+            assert_eq!(indicator.source_feeds, ["taxii:feed"]);
+            assert_eq!(indicator.mitre_mapping, ["T1105"]);
+            assert_eq!(indicator.first_seen.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+            assert_eq!(indicator.last_seen.to_rfc3339(), "2024-06-01T00:00:00+00:00");
+        }
+
+        let malware = serde_json::json!({"type": "malware", "id": "malware--1"});
+        assert!(stix_to_indicators(&malware, "feed", chrono::Utc::now()).is_empty());
+        let unsupported = stix_indicator("indicator--2", "[process:pid = '4']");
+        assert!(stix_to_indicators(&unsupported, "feed", chrono::Utc::now()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_two_page_pull_keeps_its_filter_and_saves_the_newest_date_added() {
+        let (_dir, hook) = temp_hook().await;
+        let (endpoint, requests) = recording_http_server(vec![
+            (200, vec![("X-TAXII-Date-Added-Last", "2024-02-01T00:00:00Z".to_string())],
+             envelope(vec![stix_indicator("indicator--1", "[domain-name:value = 'one.example']")], Some("page-2"))),
+            (200, vec![("X-TAXII-Date-Added-Last", "2024-03-01T00:00:00Z".to_string())],
+             envelope(vec![stix_indicator("indicator--2", "[domain-name:value = 'two.example']")], None)),
+        ]).await;
+        let cursor_key = format!("{}|feed", endpoint);
+        hook.set_taxii_cursor(&cursor_key, "2024-01-01T00:00:00Z").await.unwrap();
+
+        let report = fetch_taxii(&hook, &endpoint, "feed", None).await.unwrap();
+
+        assert_eq!(report.objects_fetched, 2);
+        assert_eq!(report.indicators_added, 2);
+        assert_eq!(report.cursor.as_deref(), Some("2024-03-01T00:00:00Z"));
+        assert_eq!(hook.get_taxii_cursor(&cursor_key).await.unwrap().as_deref(), Some("2024-03-01T00:00:00Z"));
+        assert!(hook.get_threat_intel("domain", "two.example").await.unwrap().is_some());
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        for request in requests.iter() {
+            assert!(request.contains("added_after=2024-01-01T00%3A00%3A00Z"), "{}", request);
+        }
+        assert!(!requests[0].contains("next="), "{}", requests[0]);
+        assert!(requests[1].contains("next=page-2"), "{}", requests[1]);
+    }
+
+    #[tokio::test]
+    async fn objects_without_a_date_added_header_leave_the_cursor_alone() {
+        let (_dir, hook) = temp_hook().await;
+        let (endpoint, _) = recording_http_server(vec![
+            (200, Vec::new(), envelope(vec![stix_indicator("indicator--1", "[domain-name:value = 'one.example']")], None)),
+        ]).await;
+
+        let report = fetch_taxii(&hook, &endpoint, "feed", None).await.unwrap();
+
+        assert_eq!(report.indicators_added, 1);
+        assert!(report.cursor.is_none());
+        assert!(hook.get_taxii_cursor(&format!("{}|feed", endpoint)).await.unwrap().is_none());
+    }
+}
@@ -1,13 +1,67 @@
 use anyhow::{Context, Result};
-use redb::{Database, ReadableTable, TableDefinition};
+use redb::{Database, MultimapTableDefinition, MultimapTableHandle, ReadableMultimapTable, ReadableTable, ReadableTableMetadata, TableDefinition, TableHandle};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use std::io::{BufRead, Write};
 use std::path::Path;
 use tokio::fs;
 
+use super::indicator_query::{IndicatorQuery, IpNetwork};
+use super::mitre_mapping::{load_mitre_mapping, MitreMappingReport};
+use super::yara_source::{declared_rule_names, rename_rule, split_rules, validate_rule_content, RuleBlock};
+use crate::clock::SharedClock;
+use crate::error::{ErrorCategory, PyroError};
+use crate::results_file::csv_field;
+use crate::severity::Severity;
+
 // Table definitions for YARA rules database
 const YARA_RULES_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("yara_rules");
 const RULE_METADATA_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("rule_metadata");
 const THREAT_INTEL_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("threat_intel");
+const TAXII_STATE_TABLE: TableDefinition<&str, &str> = TableDefinition::new("taxii_state");
+/// Rule bodies keyed by content hash, shared by every rule with that content.
+const RULE_BLOBS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("rule_blobs");
+/// Analyst true/false positive counts keyed by rule id. Kept apart from
+/// `RuleMetadata` so existing metadata records still deserialize.
+const RULE_FEEDBACK_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("rule_feedback");
+/// Append-only record of mutations keyed by sequence number. Entries are
+/// written in the mutation's own transaction and nothing updates or removes them.
+const AUDIT_LOG_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("audit_log");
+/// Raw bytes of records `quarantine_corrupt_records` moved out of their
+/// table, keyed by `<table>/<key>`.
+const CORRUPT_RECORDS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("corrupt_records");
+/// YARA rule name declared in a rule's content -> id of that rule. Entries
+/// whose rule is gone or no longer declares the name are stale and ignored.
+const RULE_NAMES_TABLE: TableDefinition<&str, &str> = TableDefinition::new("rule_names");
+/// Threat intel value -> ids of the records for it, one per indicator type.
+/// Entries whose record is gone are stale and ignored.
+const INTEL_VALUES_TABLE: MultimapTableDefinition<&str, &str> = MultimapTableDefinition::new("intel_values");
+/// Lowercased rule tag -> ids of the rules tagged with it. Entries whose
+/// rule is gone or no longer has the tag are stale and ignored.
+const RULE_TAGS_TABLE: MultimapTableDefinition<&str, &str> = MultimapTableDefinition::new("rule_tags");
+/// Rule content hash -> ids of the rules with that content. Entries whose
+/// rule is gone or whose content changed are stale and ignored.
+const RULE_HASHES_TABLE: MultimapTableDefinition<&str, &str> = MultimapTableDefinition::new("rule_hashes");
+/// Lowercased MITRE technique id -> ids of the rules mapped to it. Entries
+/// whose rule is gone or no longer has the technique are stale and ignored.
+const RULE_TECHNIQUES_TABLE: MultimapTableDefinition<&str, &str> = MultimapTableDefinition::new("rule_techniques");
+/// Lowercased MITRE tactic id -> ids of the rules mapped to it, kept like
+/// `RULE_TECHNIQUES_TABLE`.
+const RULE_TACTICS_TABLE: MultimapTableDefinition<&str, &str> = MultimapTableDefinition::new("rule_tactics");
+
+/// Tables whose records `find_corrupt_records` checks and
+/// `quarantine_corrupt_records` may move.
+const QUARANTINABLE_TABLES: [TableDefinition<&str, &[u8]>; 4] =
+    [YARA_RULES_TABLE, RULE_METADATA_TABLE, THREAT_INTEL_TABLE, RULE_FEEDBACK_TABLE];
+
+/// Layout of the bincode rule records in the rules table. Version 1 is
+/// `YaraRuleV1`, from before `scan_tier`; both are read.
+pub const RULE_RECORD_VERSION: u32 = 2;
+
+/// Records written per transaction by `import_database`.
+const IMPORT_BATCH_SIZE: usize = 1000;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct YaraRule {
@@ -27,6 +81,97 @@ pub struct YaraRule {
     pub mitre_techniques: Vec<String>,
     pub threat_actors: Vec<String>,
     pub malware_families: Vec<String>,
+    /// Cheapest scan tier the rule runs in. Kept last so records written
+    /// before it existed still decode (see `deserialize_rule`).
+    #[serde(default)]
+    pub scan_tier: ScanTier,
+}
+
+/// How thorough a scan must be for a rule to run, from cheapest to most
+/// expensive. A scan at one tier runs the rules of that tier and below.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanTier {
+    Quick,
+    #[default]
+    Standard,
+    Deep,
+}
+
+impl ScanTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScanTier::Quick => "quick",
+            ScanTier::Standard => "standard",
+            ScanTier::Deep => "deep",
+        }
+    }
+}
+
+impl std::fmt::Display for ScanTier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ScanTier {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "quick" => Ok(ScanTier::Quick),
+            "standard" => Ok(ScanTier::Standard),
+            "deep" => Ok(ScanTier::Deep),
+            other => Err(anyhow::anyhow!(
+                "Invalid scan tier '{}': expected quick, standard or deep", other
+            )),
+        }
+    }
+}
+
+/// `YaraRule` as stored before `scan_tier` was added.
+#[derive(Deserialize)]
+struct YaraRuleV1 {
+    id: String,
+    name: String,
+    content: String,
+    author: String,
+    description: String,
+    tags: Vec<String>,
+    severity: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+    version: String,
+    hash: String,
+    source: String,
+    mitre_tactics: Vec<String>,
+    mitre_techniques: Vec<String>,
+    threat_actors: Vec<String>,
+    malware_families: Vec<String>,
+}
+
+impl From<YaraRuleV1> for YaraRule {
+    fn from(rule: YaraRuleV1) -> Self {
+        Self {
+            id: rule.id,
+            name: rule.name,
+            content: rule.content,
+            author: rule.author,
+            description: rule.description,
+            tags: rule.tags,
+            severity: rule.severity,
+            created_at: rule.created_at,
+            updated_at: rule.updated_at,
+            version: rule.version,
+            hash: rule.hash,
+            source: rule.source,
+            mitre_tactics: rule.mitre_tactics,
+            mitre_techniques: rule.mitre_techniques,
+            threat_actors: rule.threat_actors,
+            malware_families: rule.malware_families,
+            scan_tier: ScanTier::default(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +186,56 @@ pub struct RuleMetadata {
     pub quantum_threat_relevance: f64,
 }
 
+impl RuleMetadata {
+    /// Fresh metadata for a rule that has no recorded history yet.
+    pub fn new(rule_id: &str) -> Self {
+        Self {
+            rule_id: rule_id.to_string(),
+            performance_score: 0.0,
+            false_positive_rate: 0.0,
+            detection_count: 0,
+            last_detection: None,
+            effectiveness_rating: "unknown".to_string(),
+            ai_confidence_score: 0.0,
+            quantum_threat_relevance: 0.0,
+        }
+    }
+}
+
+/// Running analyst verdicts on a rule's findings.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct RuleFeedback {
+    pub true_positives: u64,
+    pub false_positives: u64,
+}
+
+impl RuleFeedback {
+    pub fn false_positive_rate(&self) -> f64 {
+        let total = self.true_positives + self.false_positives;
+        if total == 0 {
+            0.0
+        } else {
+            self.false_positives as f64 / total as f64
+        }
+    }
+}
+
+/// One mutation recorded in the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub sequence: u64,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// e.g. `store_rule`, `import`, `prune_intel`.
+    pub operation: String,
+    /// Id of the changed record, a file for bulk operations, or `*`.
+    pub target: String,
+    pub actor: String,
+    pub detail: String,
+}
+
+/// A threat intel record. Records are unique per indicator type and value:
+/// the same string can be stored once as, say, a `sha256` and once as a
+/// `filename`, and `id` is always `intel_key(indicator_type, value)`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThreatIntelIndicator {
     pub id: String,
@@ -56,9 +251,130 @@ pub struct ThreatIntelIndicator {
     pub quantum_resistant: bool,
 }
 
+impl ThreatIntelIndicator {
+    /// `intel_key` of this indicator's type and value.
+    pub fn key(&self) -> String {
+        intel_key(&self.indicator_type, &self.value)
+    }
+
+    /// This indicator as stored: type lowercased and `id` set to its key.
+    fn keyed(&self) -> Self {
+        let mut indicator = self.clone();
+        indicator.indicator_type = indicator.indicator_type.trim().to_ascii_lowercase();
+        indicator.id = indicator.key();
+        indicator
+    }
+}
+
+/// Key of the threat intel record for `value` as an indicator of
+/// `indicator_type`: `<type>:<value>`, the type lowercased.
+pub fn intel_key(indicator_type: &str, value: &str) -> String {
+    format!("{}:{}", indicator_type.trim().to_ascii_lowercase(), value)
+}
+
+/// Threat intel records `migrate_intel_keys` moved to their type-and-value
+/// key.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct IntelKeyMigration {
+    /// Records moved to a key no other record had.
+    pub rekeyed: u64,
+    /// Records merged into another record of the same type and value.
+    pub merged: u64,
+}
+
+/// What to do when a rule's content no longer matches its stored hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntegrityPolicy {
+    /// Don't recompute hashes on read.
+    #[default]
+    Off,
+    /// Log mismatches and return the rule anyway.
+    Warn,
+    /// Fail the read on a mismatch.
+    Error,
+}
+
+/// What storing a rule does when its content declares a YARA rule name
+/// another stored rule already declares. Thor won't compile a rule set
+/// with duplicate names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateNamePolicy {
+    /// Fail the store.
+    #[default]
+    Reject,
+    /// Rename the declaration in the rule being stored to the first free
+    /// `<name>_2`, `<name>_3`, ...
+    Rename,
+}
+
+/// A YARA rule name declared by more than one stored rule.
+#[derive(Debug, Clone, Serialize)]
+pub struct NameConflict {
+    pub name: String,
+    /// Ids of the declaring rules, oldest first.
+    pub rule_ids: Vec<String>,
+}
+
+/// A rule declaration renamed by `resolve_name_conflicts`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RenamedRule {
+    pub id: String,
+    pub name: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// A record that failed to deserialize.
+#[derive(Debug, Clone, Serialize)]
+pub struct CorruptRecord {
+    pub table: String,
+    pub key: String,
+    pub error: String,
+}
+
+/// A rule removed by `delete_rules_where`, or that would be on a dry run.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeletedRule {
+    pub id: String,
+    pub name: String,
+    pub source: String,
+    pub severity: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HashMismatch {
+    pub rule_id: String,
+    pub name: String,
+    pub stored_hash: String,
+    pub actual_hash: String,
+}
+
+/// Handle on a ReDB rules database.
+///
+/// Cloning is cheap and every clone shares the same open `Database` (and
+/// the list of skipped records), so one handle can be given to the scanner
+/// and to reporting tasks without reopening the file. Transactions behave
+/// as with a single handle: reads see the last committed state and writes
+/// from all clones are serialized by redb. Settings applied with the
+/// `with_*` builders belong to the clone they were called on.
+#[derive(Clone)]
 pub struct YaraRulesRedbHook {
-    db: Database,
+    db: Arc<Database>,
     db_path: String,
+    integrity_policy: IntegrityPolicy,
+    duplicate_names: DuplicateNamePolicy,
+    dedup_bodies: bool,
+    compress_bodies: bool,
+    severity_from_tags: BTreeMap<String, Severity>,
+    /// Recorded as the actor of every audit log entry.
+    actor: String,
+    skip_corrupt_records: bool,
+    /// Records skipped by lenient reads so far.
+    skipped_records: Arc<Mutex<Vec<CorruptRecord>>>,
+    /// Timestamps every stored record and audit entry.
+    clock: SharedClock,
 }
 
 impl YaraRulesRedbHook {
@@ -85,6 +401,51 @@ impl YaraRulesRedbHook {
                 .context("Failed to open rule metadata table")?;
             let _intel_table = write_txn.open_table(THREAT_INTEL_TABLE)
                 .context("Failed to open threat intel table")?;
+            let _taxii_table = write_txn.open_table(TAXII_STATE_TABLE)
+                .context("Failed to open TAXII state table")?;
+            let _blobs_table = write_txn.open_table(RULE_BLOBS_TABLE)
+                .context("Failed to open rule blobs table")?;
+            let _feedback_table = write_txn.open_table(RULE_FEEDBACK_TABLE)
+                .context("Failed to open rule feedback table")?;
+            let _audit_table = write_txn.open_table(AUDIT_LOG_TABLE)
+                .context("Failed to open audit log table")?;
+            let _corrupt_table = write_txn.open_table(CORRUPT_RECORDS_TABLE)
+                .context("Failed to open corrupt records table")?;
+        }
+
+        // Databases from before the name index get it built on first open
+        let unindexed = {
+            let rules_table = write_txn.open_table(YARA_RULES_TABLE)
+                .context("Failed to open YARA rules table")?;
+            let names_table = write_txn.open_table(RULE_NAMES_TABLE)
+                .context("Failed to open rule names table")?;
+            names_table.iter()?.next().is_none() && rules_table.iter()?.next().is_some()
+        };
+        if unindexed {
+            rebuild_name_index(&write_txn)?;
+        }
+        let intel_unindexed = {
+            let intel_table = write_txn.open_table(THREAT_INTEL_TABLE)
+                .context("Failed to open threat intel table")?;
+            let values_table = write_txn.open_multimap_table(INTEL_VALUES_TABLE)
+                .context("Failed to open threat intel value index")?;
+            values_table.iter()?.next().is_none() && intel_table.iter()?.next().is_some()
+        };
+        if intel_unindexed {
+            rebuild_intel_value_index(&write_txn)?;
+        }
+        // Checked by table rather than content since rules may have no tags
+        let multimap_tables: Vec<String> = write_txn.list_multimap_tables()?
+            .map(|table| table.name().to_string())
+            .collect();
+        if !multimap_tables.iter().any(|name| name == RULE_TAGS_TABLE.name()) {
+            rebuild_tag_index(&write_txn)?;
+        }
+        if !multimap_tables.iter().any(|name| name == RULE_HASHES_TABLE.name()) {
+            rebuild_hash_index(&write_txn)?;
+        }
+        if [RULE_TECHNIQUES_TABLE, RULE_TACTICS_TABLE].iter().any(|index| !multimap_tables.iter().any(|name| name == index.name())) {
+            rebuild_mitre_index(&write_txn)?;
         }
         
         write_txn.commit()
@@ -93,26 +454,222 @@ impl YaraRulesRedbHook {
         log::info!("Initialized YARA rules ReDB database at: {}", db_path_str);
 
         Ok(Self {
-            db,
+            db: Arc::new(db),
             db_path: db_path_str,
+            integrity_policy: IntegrityPolicy::Off,
+            duplicate_names: DuplicateNamePolicy::Reject,
+            dedup_bodies: false,
+            compress_bodies: false,
+            severity_from_tags: BTreeMap::new(),
+            actor: audit_actor(),
+            skip_corrupt_records: false,
+            skipped_records: Arc::new(Mutex::new(Vec::new())),
+            clock: crate::clock::system(),
         })
     }
 
-    pub async fn store_yara_rule(&self, rule: &YaraRule) -> Result<()> {
-        let rule_data = bincode::serialize(rule)
-            .context("Failed to serialize YARA rule")?;
+    /// Replaces the wall clock, e.g. with a `MockClock` to make timestamps
+    /// and age-based cleanup deterministic.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
 
-        let write_txn = self.db.begin_write()
-            .context("Failed to begin write transaction")?;
+    /// The current time according to the hook's clock.
+    pub fn now(&self) -> chrono::DateTime<chrono::Utc> {
+        self.clock.now()
+    }
+
+    /// Stores rule bodies once per distinct content in the `rule_blobs` table.
+    ///
+    /// Affects writes only; blob-backed and inline rules are read the same
+    /// way, so the setting can be changed on an existing database.
+    pub fn with_content_dedup(mut self, enabled: bool) -> Self {
+        self.dedup_bodies = enabled;
+        self
+    }
+
+    /// Stores rule bodies zstd-compressed in the `rule_blobs` table, which
+    /// also deduplicates them. Bodies that don't get smaller stay plain.
+    ///
+    /// Affects writes only; compressed and plain blobs are told apart when
+    /// read, so the setting can be changed on an existing database and
+    /// `db migrate-blobs` compresses what is already stored.
+    pub fn with_rule_compression(mut self, enabled: bool) -> Self {
+        self.compress_bodies = enabled;
+        self
+    }
+
+    /// Where `encode_rule` writes rule bodies: the blob table when dedup or
+    /// compression is on, otherwise inline.
+    fn body_storage<'a, 't>(&self, blobs: &'a mut redb::Table<'t, &'static str, &'static [u8]>) -> Option<BlobWriter<'a, 't>> {
+        (self.dedup_bodies || self.compress_bodies).then_some(BlobWriter { table: blobs, compress: self.compress_bodies })
+    }
+
+    /// Severity given to synced rules by YARA tag when their `meta:` section
+    /// has no `severity`. Tags match case-insensitively and the most severe
+    /// match wins.
+    pub fn with_severity_from_tags(mut self, severity_from_tags: BTreeMap<String, Severity>) -> Self {
+        self.severity_from_tags = severity_from_tags;
+        self
+    }
+
+    /// Makes iterating reads skip records that fail to deserialize instead
+    /// of failing outright. Skipped records are logged and collected in
+    /// `skipped_records`; lookups by key still return the error.
+    pub fn with_skip_corrupt_records(mut self, enabled: bool) -> Self {
+        self.skip_corrupt_records = enabled;
+        self
+    }
+
+    /// Records skipped by lenient reads since the hook was opened.
+    pub fn skipped_records(&self) -> Vec<CorruptRecord> {
+        self.skipped_records.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Passes a decoded record through, or in lenient mode logs and collects
+    /// a failed one and returns `None` so the caller can skip it.
+    fn lenient<T>(&self, table: &impl TableHandle, key: &str, decoded: Result<T>) -> Result<Option<T>> {
+        match decoded {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if self.skip_corrupt_records => {
+                log::warn!("Skipping unreadable record {} in {}: {:#}", key, table.name(), e);
+                self.skipped_records.lock().unwrap_or_else(|e| e.into_inner()).push(CorruptRecord {
+                    table: table.name().to_string(),
+                    key: key.to_string(),
+                    error: format!("{:#}", e),
+                });
+                Ok(None)
+            }
+            Err(e) => Err(e.context(format!("Unreadable record {} in {}", key, table.name()))),
+        }
+    }
+
+    /// Verifies rule content against its stored hash on every read.
+    pub fn with_integrity_policy(mut self, policy: IntegrityPolicy) -> Self {
+        self.integrity_policy = policy;
+        self
+    }
+
+    /// How `store_yara_rule` treats a rule declaring a YARA rule name that
+    /// another stored rule already declares.
+    pub fn with_duplicate_name_policy(mut self, policy: DuplicateNamePolicy) -> Self {
+        self.duplicate_names = policy;
+        self
+    }
+
+    fn check_integrity(&self, rule: &YaraRule) -> Result<()> {
+        if self.integrity_policy == IntegrityPolicy::Off {
+            return Ok(());
+        }
+
+        let actual_hash = content_hash(&rule.content);
+        if actual_hash == rule.hash {
+            return Ok(());
+        }
+
+        match self.integrity_policy {
+            IntegrityPolicy::Error => Err(anyhow::anyhow!(
+                "Integrity check failed for YARA rule {} ({}): stored hash {}, content hash {}",
+                rule.name, rule.id, rule.hash, actual_hash
+            )),
+            _ => {
+                log::warn!("Integrity check failed for YARA rule {} ({}): stored hash {}, content hash {}",
+                          rule.name, rule.id, rule.hash, actual_hash);
+                Ok(())
+            }
+        }
+    }
+
+    /// Recomputes every rule's content hash and returns the mismatches,
+    /// regardless of the configured integrity policy.
+    pub async fn verify_rule_hashes(&self) -> Result<Vec<HashMismatch>> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
         
-        {
-            let mut table = write_txn.open_table(YARA_RULES_TABLE)
-                .context("Failed to open YARA rules table")?;
+        let table = read_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let blobs = read_txn.open_table(RULE_BLOBS_TABLE)
+            .context("Failed to open rule blobs table")?;
+        
+        let mut mismatches = Vec::new();
+        
+        for result in table.iter()? {
+            let (key, value) = result?;
+            let Some(rule) = self.lenient(&YARA_RULES_TABLE, key.value(), decode_rule(value.value(), &blobs))? else { continue };
             
-            table.insert(&rule.id, rule_data.as_slice())
-                .context("Failed to insert YARA rule")?;
+            let actual_hash = content_hash(&rule.content);
+            if actual_hash != rule.hash {
+                mismatches.push(HashMismatch {
+                    rule_id: rule.id,
+                    name: rule.name,
+                    stored_hash: rule.hash,
+                    actual_hash,
+                });
+            }
+        }
+        
+        Ok(mismatches)
+    }
+
+    /// Appends an audit entry inside `write_txn`, so it commits or rolls
+    /// back together with the mutation it describes.
+    fn audit(&self, write_txn: &redb::WriteTransaction, operation: &str, target: &str, detail: String) -> Result<()> {
+        let mut table = write_txn.open_table(AUDIT_LOG_TABLE)
+            .context("Failed to open audit log table")?;
+        let sequence = table.last()?.map_or(0, |(key, _)| key.value() + 1);
+
+        let entry = AuditEntry {
+            sequence,
+            timestamp: self.now(),
+            operation: operation.to_string(),
+            target: target.to_string(),
+            actor: self.actor.clone(),
+            detail,
+        };
+        let entry_data = bincode::serialize(&entry)
+            .context("Failed to serialize audit entry")?;
+        table.insert(sequence, entry_data.as_slice())
+            .context("Failed to append audit entry")?;
+        Ok(())
+    }
+
+    /// The most recent `limit` audit entries, oldest first.
+    pub async fn audit_tail(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
+        let table = read_txn.open_table(AUDIT_LOG_TABLE)
+            .context("Failed to open audit log table")?;
+
+        let mut entries = Vec::new();
+        for result in table.iter()?.rev().take(limit) {
+            let (_key, value) = result?;
+            entries.push(bincode::deserialize::<AuditEntry>(value.value())
+                .context("Failed to deserialize audit entry")?);
         }
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Stores `rule`, replacing any rule with the same id and keeping the YARA
+    /// rule names declared in its content unique across the database
+    /// according to the duplicate name policy. Content that fails the
+    /// structural check of `validate_rule_content` is rejected; that check is
+    /// not a YARA compile.
+    pub async fn store_yara_rule(&self, rule: &YaraRule) -> Result<()> {
+        check_rule_content(rule)?;
+        self.store_yara_rule_unchecked(rule).await
+    }
+
+    /// Stores a rule without checking its content, for drafts that are
+    /// deliberately not well-formed YARA yet. Thor fails to compile an exported
+    /// draft, so keep drafts out of the rules used for scanning.
+    pub async fn store_yara_rule_unchecked(&self, rule: &YaraRule) -> Result<()> {
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
         
+        self.insert_rule(&write_txn, rule)?;
+
         write_txn.commit()
             .context("Failed to commit YARA rule storage")?;
 
@@ -120,18 +677,154 @@ impl YaraRulesRedbHook {
         Ok(())
     }
 
+    /// Stores `rules` in a single write transaction, so storing thousands of
+    /// rules commits (and fsyncs) once instead of once per rule. If any rule
+    /// fails, including on content `store_yara_rule` rejects, none is
+    /// stored. Returns the number of rules stored.
+    pub async fn store_yara_rules(&self, rules: &[YaraRule]) -> Result<u64> {
+        if rules.is_empty() {
+            return Ok(0);
+        }
+        for rule in rules {
+            check_rule_content(rule)?;
+        }
+
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+
+        for rule in rules {
+            self.insert_rule(&write_txn, rule)?;
+        }
+
+        write_txn.commit()
+            .context("Failed to commit YARA rule storage")?;
+
+        log::info!("Stored {} YARA rules", rules.len());
+        Ok(rules.len() as u64)
+    }
+
+    /// Inserts or replaces `rule` within `write_txn`, indexing its names,
+    /// tags, hash and MITRE ids and recording it in the audit log.
+    fn insert_rule(&self, write_txn: &redb::WriteTransaction, rule: &YaraRule) -> Result<()> {
+        let rule = {
+            let mut table = write_txn.open_table(YARA_RULES_TABLE)
+                .context("Failed to open YARA rules table")?;
+            let mut blobs = write_txn.open_table(RULE_BLOBS_TABLE)
+                .context("Failed to open rule blobs table")?;
+            let mut names = write_txn.open_table(RULE_NAMES_TABLE)
+                .context("Failed to open rule names table")?;
+            let mut tags = write_txn.open_multimap_table(RULE_TAGS_TABLE)
+                .context("Failed to open rule tag index")?;
+            let mut hashes = write_txn.open_multimap_table(RULE_HASHES_TABLE)
+                .context("Failed to open rule hash index")?;
+            let mut techniques = write_txn.open_multimap_table(RULE_TECHNIQUES_TABLE)
+                .context("Failed to open rule technique index")?;
+            let mut tactics = write_txn.open_multimap_table(RULE_TACTICS_TABLE)
+                .context("Failed to open rule tactic index")?;
+
+            if let Some(previous) = table.get(rule.id.as_str())? {
+                if let Ok(previous) = deserialize_rule(previous.value()) {
+                    unindex_tags(&mut tags, &previous.id, &previous.tags)?;
+                    hashes.remove(previous.hash.as_str(), previous.id.as_str())
+                        .context("Failed to remove rule hash from index")?;
+                    unindex_mitre_ids(&mut techniques, &previous.id, &previous.mitre_techniques)?;
+                    unindex_mitre_ids(&mut tactics, &previous.id, &previous.mitre_tactics)?;
+                }
+            }
+            let rule = self.claim_rule_names(rule, &table, &blobs, &mut names)?;
+            index_tags(&mut tags, &rule.id, &rule.tags)?;
+            index_mitre_ids(&mut techniques, &rule.id, &rule.mitre_techniques)?;
+            index_mitre_ids(&mut tactics, &rule.id, &rule.mitre_tactics)?;
+            hashes.insert(rule.hash.as_str(), rule.id.as_str())
+                .context("Failed to index rule hash")?;
+            let rule_data = encode_rule(&rule, self.body_storage(&mut blobs))?;
+            table.insert(rule.id.as_str(), rule_data.as_slice())
+                .context("Failed to insert YARA rule")?;
+            rule
+        };
+        
+        self.audit(write_txn, "store_rule", &rule.id, format!("name={} hash={} source={}", rule.name, rule.hash, rule.source))
+    }
+
+    /// Points the name index at `rule` for every YARA rule name its content
+    /// declares, after dropping names a previous version of it declared.
+    /// Returns the rule to store: `rule` itself, or under
+    /// `DuplicateNamePolicy::Rename` a copy with taken names suffixed.
+    fn claim_rule_names<'r>(
+        &self,
+        rule: &'r YaraRule,
+        rules: &impl ReadableTable<&'static str, &'static [u8]>,
+        blobs: &impl ReadableTable<&'static str, &'static [u8]>,
+        names: &mut redb::Table<&'static str, &'static str>,
+    ) -> Result<Cow<'r, YaraRule>> {
+        if let Some(previous) = rules.get(rule.id.as_str())? {
+            if let Ok(previous) = decode_rule(previous.value(), blobs) {
+                for name in declared_rule_names(&previous.content) {
+                    if names.get(name.as_str())?.is_some_and(|id| id.value() == rule.id) {
+                        names.remove(name.as_str())?;
+                    }
+                }
+            }
+        }
+
+        let declared = declared_rule_names(&rule.content);
+        let mut rule = Cow::Borrowed(rule);
+        let mut claimed = Vec::new();
+        for name in &declared {
+            let Some(holder) = name_holder(name, &rule.id, names, rules, blobs)? else {
+                claimed.push(name.clone());
+                continue;
+            };
+            if self.duplicate_names == DuplicateNamePolicy::Reject {
+                return Err(anyhow::anyhow!(
+                    "YARA rule name '{}' in {} ({}) is already declared by {} ({}, source {}); \
+                     rename one of them or set database.duplicate_rule_names to rename",
+                    name, rule.name, rule.id, holder.name, holder.id, holder.source
+                ));
+            }
+
+            let mut suffix = 2;
+            let renamed = loop {
+                let candidate = format!("{}_{}", name, suffix);
+                if !declared.contains(&candidate)
+                    && !claimed.contains(&candidate)
+                    && name_holder(&candidate, &rule.id, names, rules, blobs)?.is_none()
+                {
+                    break candidate;
+                }
+                suffix += 1;
+            };
+            log::warn!("YARA rule name '{}' in {} ({}) is already declared by {} ({}); storing it as '{}'",
+                      name, rule.name, rule.id, holder.name, holder.id, renamed);
+
+            let owned = rule.to_mut();
+            owned.content = rename_rule(&owned.content, name, &renamed)
+                .with_context(|| format!("Failed to rename YARA rule '{}' in {}", name, owned.id))?;
+            owned.hash = content_hash(&owned.content);
+            claimed.push(renamed);
+        }
+
+        for name in &claimed {
+            names.insert(name.as_str(), rule.id.as_str())
+                .context("Failed to index YARA rule name")?;
+        }
+        Ok(rule)
+    }
+
     pub async fn get_yara_rule(&self, rule_id: &str) -> Result<Option<YaraRule>> {
         let read_txn = self.db.begin_read()
             .context("Failed to begin read transaction")?;
         
         let table = read_txn.open_table(YARA_RULES_TABLE)
             .context("Failed to open YARA rules table")?;
+        let blobs = read_txn.open_table(RULE_BLOBS_TABLE)
+            .context("Failed to open rule blobs table")?;
         
         if let Some(rule_data) = table.get(rule_id)
             .context("Failed to get YARA rule")? {
             
-            let rule: YaraRule = bincode::deserialize(rule_data.value())
-                .context("Failed to deserialize YARA rule")?;
+            let rule = decode_rule(rule_data.value(), &blobs)?;
+            self.check_integrity(&rule)?;
             
             Ok(Some(rule))
         } else {
@@ -145,133 +838,513 @@ impl YaraRulesRedbHook {
         
         let table = read_txn.open_table(YARA_RULES_TABLE)
             .context("Failed to open YARA rules table")?;
+        let blobs = read_txn.open_table(RULE_BLOBS_TABLE)
+            .context("Failed to open rule blobs table")?;
         
         let mut rules = Vec::new();
         
         for result in table.iter()? {
-            let (_key, value) = result?;
-            let rule: YaraRule = bincode::deserialize(value.value())
-                .context("Failed to deserialize YARA rule")?;
+            let (key, value) = result?;
+            let Some(rule) = self.lenient(&YARA_RULES_TABLE, key.value(), decode_rule(value.value(), &blobs))? else { continue };
+            self.check_integrity(&rule)?;
             rules.push(rule);
         }
         
         Ok(rules)
     }
 
-    pub async fn update_rule_metadata(&self, metadata: &RuleMetadata) -> Result<()> {
-        let metadata_data = bincode::serialize(metadata)
-            .context("Failed to serialize rule metadata")?;
+    /// At most `limit` rules, starting at the `offset`-th record in key
+    /// (rule id) order, so consecutive pages neither overlap nor miss rules
+    /// while the table is unchanged. Skipped records aren't decoded. A record
+    /// skipped as unreadable still counts towards `offset`, so such a page can
+    /// hold fewer than `limit` rules.
+    pub async fn list_yara_rules_paged(&self, offset: usize, limit: usize) -> Result<Vec<YaraRule>> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
 
-        let write_txn = self.db.begin_write()
-            .context("Failed to begin write transaction")?;
-        
-        {
-            let mut table = write_txn.open_table(RULE_METADATA_TABLE)
-                .context("Failed to open rule metadata table")?;
-            
-            table.insert(&metadata.rule_id, metadata_data.as_slice())
-                .context("Failed to insert rule metadata")?;
+        let table = read_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let blobs = read_txn.open_table(RULE_BLOBS_TABLE)
+            .context("Failed to open rule blobs table")?;
+
+        let mut rules = Vec::new();
+
+        for result in table.iter()?.skip(offset).take(limit) {
+            let (key, value) = result?;
+            let Some(rule) = self.lenient(&YARA_RULES_TABLE, key.value(), decode_rule(value.value(), &blobs))? else { continue };
+            self.check_integrity(&rule)?;
+            rules.push(rule);
         }
-        
-        write_txn.commit()
-            .context("Failed to commit rule metadata update")?;
 
-        log::debug!("Updated metadata for rule: {}", metadata.rule_id);
-        Ok(())
+        Ok(rules)
     }
 
-    pub async fn get_rule_metadata(&self, rule_id: &str) -> Result<Option<RuleMetadata>> {
+    /// Rules with `tag` among their tags, compared case-insensitively, found
+    /// through the tag index without reading any other rule.
+    pub async fn search_rules_by_tag(&self, tag: &str) -> Result<Vec<YaraRule>> {
         let read_txn = self.db.begin_read()
             .context("Failed to begin read transaction")?;
-        
-        let table = read_txn.open_table(RULE_METADATA_TABLE)
-            .context("Failed to open rule metadata table")?;
-        
-        if let Some(metadata_data) = table.get(rule_id)
-            .context("Failed to get rule metadata")? {
-            
-            let metadata: RuleMetadata = bincode::deserialize(metadata_data.value())
-                .context("Failed to deserialize rule metadata")?;
-            
-            Ok(Some(metadata))
-        } else {
-            Ok(None)
+        let table = read_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let blobs = read_txn.open_table(RULE_BLOBS_TABLE)
+            .context("Failed to open rule blobs table")?;
+        let tags = read_txn.open_multimap_table(RULE_TAGS_TABLE)
+            .context("Failed to open rule tag index")?;
+
+        let tag = tag.to_lowercase();
+        let mut rules = Vec::new();
+        for id in tags.get(tag.as_str())? {
+            let id = id?;
+            let Some(value) = table.get(id.value())? else { continue };
+            let Some(rule) = self.lenient(&YARA_RULES_TABLE, id.value(), decode_rule(value.value(), &blobs))? else { continue };
+            if rule.tags.iter().any(|rule_tag| rule_tag.to_lowercase() == tag) {
+                self.check_integrity(&rule)?;
+                rules.push(rule);
+            }
         }
+        Ok(rules)
     }
 
-    pub async fn store_threat_intel(&self, indicator: &ThreatIntelIndicator) -> Result<()> {
-        let intel_data = bincode::serialize(indicator)
-            .context("Failed to serialize threat intel indicator")?;
+    /// Rules whose content hashes to `hash`, found through the hash index.
+    pub async fn find_rules_by_hash(&self, hash: &str) -> Result<Vec<YaraRule>> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
+        let table = read_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let blobs = read_txn.open_table(RULE_BLOBS_TABLE)
+            .context("Failed to open rule blobs table")?;
+        let hashes = read_txn.open_multimap_table(RULE_HASHES_TABLE)
+            .context("Failed to open rule hash index")?;
 
-        let write_txn = self.db.begin_write()
-            .context("Failed to begin write transaction")?;
-        
-        {
-            let mut table = write_txn.open_table(THREAT_INTEL_TABLE)
-                .context("Failed to open threat intel table")?;
-            
-            table.insert(&indicator.id, intel_data.as_slice())
-                .context("Failed to insert threat intel indicator")?;
+        let mut rules = Vec::new();
+        for id in hashes.get(hash)? {
+            let id = id?;
+            let Some(value) = table.get(id.value())? else { continue };
+            let Some(rule) = self.lenient(&YARA_RULES_TABLE, id.value(), decode_rule(value.value(), &blobs))? else { continue };
+            if rule.hash == hash {
+                self.check_integrity(&rule)?;
+                rules.push(rule);
+            }
         }
-        
-        write_txn.commit()
-            .context("Failed to commit threat intel storage")?;
+        Ok(rules)
+    }
 
-        log::info!("Stored threat intel indicator: {} (confidence: {:.2})", 
-                  indicator.value, indicator.confidence);
-        Ok(())
+    /// Rules whose `mitre_techniques` include `technique_id` (e.g.
+    /// `T1059.001`), compared case-insensitively and found through the
+    /// technique index. A technique doesn't match its sub-techniques or the
+    /// other way round.
+    pub async fn get_rules_by_mitre_technique(&self, technique_id: &str) -> Result<Vec<YaraRule>> {
+        self.rules_by_mitre_id(RULE_TECHNIQUES_TABLE, technique_id, |rule| &rule.mitre_techniques)
+    }
+
+    /// Rules whose `mitre_tactics` include `tactic_id` (e.g. `TA0002`),
+    /// compared case-insensitively and found through the tactic index.
+    pub async fn get_rules_by_mitre_tactic(&self, tactic_id: &str) -> Result<Vec<YaraRule>> {
+        self.rules_by_mitre_id(RULE_TACTICS_TABLE, tactic_id, |rule| &rule.mitre_tactics)
     }
 
-    pub async fn get_threat_intel_by_value(&self, value: &str) -> Result<Vec<ThreatIntelIndicator>> {
+    /// The rules `index` lists under `mitre_id` whose `ids` field still
+    /// contains it, so stale index entries are skipped.
+    fn rules_by_mitre_id(
+        &self,
+        index: MultimapTableDefinition<&str, &str>,
+        mitre_id: &str,
+        ids: impl Fn(&YaraRule) -> &Vec<String>,
+    ) -> Result<Vec<YaraRule>> {
         let read_txn = self.db.begin_read()
             .context("Failed to begin read transaction")?;
+        let table = read_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let blobs = read_txn.open_table(RULE_BLOBS_TABLE)
+            .context("Failed to open rule blobs table")?;
+        let index = read_txn.open_multimap_table(index)
+            .context("Failed to open rule MITRE index")?;
+
+        let mitre_id = mitre_index_key(mitre_id);
+        let mut rules = Vec::new();
+        for id in index.get(mitre_id.as_str())? {
+            let id = id?;
+            let Some(value) = table.get(id.value())? else { continue };
+            let Some(rule) = self.lenient(&YARA_RULES_TABLE, id.value(), decode_rule(value.value(), &blobs))? else { continue };
+            if ids(&rule).iter().any(|rule_id| mitre_index_key(rule_id) == mitre_id) {
+                self.check_integrity(&rule)?;
+                rules.push(rule);
+            }
+        }
+        Ok(rules)
+    }
+
+    /// Deletes every rule `predicate` accepts, together with its metadata
+    /// and feedback, in one transaction, and returns what was deleted. Rule
+    /// blobs no other rule uses are removed too. With `dry_run` nothing is
+    /// written and the rules that would be deleted are returned.
+    ///
+    /// Unreadable rules can't be matched and are left alone (or, without
+    /// lenient reads, fail the call).
+    pub async fn delete_rules_where(&self, predicate: impl Fn(&YaraRule) -> bool, dry_run: bool) -> Result<Vec<DeletedRule>> {
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+        let mut deleted = Vec::new();
+
+        {
+            let mut rules_table = write_txn.open_table(YARA_RULES_TABLE)
+                .context("Failed to open YARA rules table")?;
+            let mut blobs = write_txn.open_table(RULE_BLOBS_TABLE)
+                .context("Failed to open rule blobs table")?;
+
+            let mut deleted_hashes = BTreeSet::new();
+            let mut deleted_tags = Vec::new();
+            let mut kept_hashes = BTreeSet::new();
+            let mut unknown_kept = false;
+            for result in rules_table.iter()? {
+                let (key, value) = result?;
+                let Some(rule) = self.lenient(&YARA_RULES_TABLE, key.value(), decode_rule(value.value(), &blobs))? else {
+                    unknown_kept = true;
+                    continue;
+                };
+                if predicate(&rule) {
+                    deleted_hashes.insert(rule.hash.clone());
+                    deleted_tags.push((rule.id.clone(), rule.hash.clone(), rule.tags, rule.mitre_techniques, rule.mitre_tactics));
+                    deleted.push(DeletedRule { id: rule.id, name: rule.name, source: rule.source, severity: rule.severity });
+                } else {
+                    kept_hashes.insert(rule.hash);
+                }
+            }
+
+            if dry_run || deleted.is_empty() {
+                return Ok(deleted);
+            }
+
+            let mut metadata_table = write_txn.open_table(RULE_METADATA_TABLE)
+                .context("Failed to open rule metadata table")?;
+            let mut feedback_table = write_txn.open_table(RULE_FEEDBACK_TABLE)
+                .context("Failed to open rule feedback table")?;
+            for rule in &deleted {
+                rules_table.remove(rule.id.as_str())
+                    .context("Failed to delete YARA rule")?;
+                metadata_table.remove(rule.id.as_str())
+                    .context("Failed to delete rule metadata")?;
+                feedback_table.remove(rule.id.as_str())
+                    .context("Failed to delete rule feedback")?;
+            }
+            let mut tags = write_txn.open_multimap_table(RULE_TAGS_TABLE)
+                .context("Failed to open rule tag index")?;
+            let mut hashes = write_txn.open_multimap_table(RULE_HASHES_TABLE)
+                .context("Failed to open rule hash index")?;
+            let mut techniques = write_txn.open_multimap_table(RULE_TECHNIQUES_TABLE)
+                .context("Failed to open rule technique index")?;
+            let mut tactics = write_txn.open_multimap_table(RULE_TACTICS_TABLE)
+                .context("Failed to open rule tactic index")?;
+            for (id, hash, rule_tags, rule_techniques, rule_tactics) in &deleted_tags {
+                unindex_tags(&mut tags, id, rule_tags)?;
+                hashes.remove(hash.as_str(), id.as_str())
+                    .context("Failed to remove rule hash from index")?;
+                unindex_mitre_ids(&mut techniques, id, rule_techniques)?;
+                unindex_mitre_ids(&mut tactics, id, rule_tactics)?;
+            }
+
+            // A skipped record may still point at any blob
+            if !unknown_kept {
+                for hash in deleted_hashes.difference(&kept_hashes) {
+                    blobs.remove(hash.as_str())
+                        .context("Failed to delete rule blob")?;
+                }
+            }
+        }
+
+        for rule in &deleted {
+            self.audit(&write_txn, "delete_rule", &rule.id, format!("name={} source={}", rule.name, rule.source))?;
+        }
+
+        write_txn.commit()
+            .context("Failed to commit rule deletion")?;
+
+        log::info!("Deleted {} YARA rules", deleted.len());
+        Ok(deleted)
+    }
+
+    /// YARA rule names declared by more than one stored rule, which Thor
+    /// refuses to compile together. Rules stored before the name index, or
+    /// brought in by `import_database` and `merge_database`, can conflict.
+    pub async fn find_name_conflicts(&self) -> Result<Vec<NameConflict>> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
+        let rules_table = read_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let blobs = read_txn.open_table(RULE_BLOBS_TABLE)
+            .context("Failed to open rule blobs table")?;
+
+        Ok(rule_declarations(&rules_table, &blobs)?
+            .into_iter()
+            .filter(|(_, holders)| holders.len() > 1)
+            .map(|(name, holders)| NameConflict {
+                name,
+                rule_ids: holders.into_iter().map(|(_, id)| id).collect(),
+            })
+            .collect())
+    }
+
+    /// Resolves every name conflict in one transaction: the oldest rule
+    /// declaring a name keeps it and the others' declarations are renamed
+    /// to the first free `<name>_2`, `<name>_3`, ... Returns the renames.
+    pub async fn resolve_name_conflicts(&self) -> Result<Vec<RenamedRule>> {
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+        let mut renamed = Vec::new();
+
+        {
+            let mut rules_table = write_txn.open_table(YARA_RULES_TABLE)
+                .context("Failed to open YARA rules table")?;
+            let mut blobs = write_txn.open_table(RULE_BLOBS_TABLE)
+                .context("Failed to open rule blobs table")?;
+
+            let declarations = rule_declarations(&rules_table, &blobs)?;
+            let mut taken: BTreeSet<String> = declarations.keys().cloned().collect();
+            for (name, holders) in &declarations {
+                for (_, id) in holders.iter().skip(1) {
+                    // Re-read, as an earlier rename may have rewritten the rule
+                    let mut rule = {
+                        let data = rules_table.get(id.as_str())?
+                            .with_context(|| format!("YARA rule {} disappeared", id))?;
+                        decode_rule(data.value(), &blobs)?
+                    };
+                    let to = (2..)
+                        .map(|suffix| format!("{}_{}", name, suffix))
+                        .find(|candidate| !taken.contains(candidate))
+                        .expect("unbounded suffixes");
+
+                    rule.content = rename_rule(&rule.content, name, &to)
+                        .with_context(|| format!("Failed to rename YARA rule '{}' in {}", name, id))?;
+                    rule.hash = content_hash(&rule.content);
+                    rule.updated_at = self.now();
+                    let rule_data = encode_rule(&rule, self.body_storage(&mut blobs))?;
+                    rules_table.insert(id.as_str(), rule_data.as_slice())
+                        .context("Failed to update YARA rule")?;
+
+                    taken.insert(to.clone());
+                    renamed.push(RenamedRule { id: id.clone(), name: rule.name, from: name.clone(), to });
+                }
+            }
+        }
+
+        for rename in &renamed {
+            self.audit(&write_txn, "rename_rule", &rename.id, format!("{} -> {}", rename.from, rename.to))?;
+        }
+        rebuild_name_index(&write_txn)?;
+        rebuild_hash_index(&write_txn)?;
+
+        write_txn.commit()
+            .context("Failed to commit rule renames")?;
+
+        log::info!("Renamed {} conflicting YARA rule declarations", renamed.len());
+        Ok(renamed)
+    }
+
+    /// Deletes the rule with id `rule_id`, with its metadata and feedback.
+    /// Returns whether it existed.
+    pub async fn delete_yara_rule(&self, rule_id: &str) -> Result<bool> {
+        Ok(!self.delete_rules_where(|rule| rule.id == rule_id, false).await?.is_empty())
+    }
+
+    /// Deletes the rules carrying `tag`, compared case-insensitively.
+    pub async fn delete_rules_by_tag(&self, tag: &str, dry_run: bool) -> Result<Vec<DeletedRule>> {
+        self.delete_rules_where(|rule| rule.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)), dry_run).await
+    }
+
+    /// Deletes the rules whose `source` is exactly `source`, as listed by
+    /// `stats_by_source`.
+    pub async fn delete_rules_by_source(&self, source: &str, dry_run: bool) -> Result<Vec<DeletedRule>> {
+        self.delete_rules_where(|rule| rule.source == source, dry_run).await
+    }
+
+    pub async fn delete_rules_by_severity(&self, severity: Severity, dry_run: bool) -> Result<Vec<DeletedRule>> {
+        self.delete_rules_where(|rule| rule.severity.parse::<Severity>().ok() == Some(severity), dry_run).await
+    }
+
+    pub async fn update_rule_metadata(&self, metadata: &RuleMetadata) -> Result<()> {
+        let metadata_data = bincode::serialize(metadata)
+            .context("Failed to serialize rule metadata")?;
+
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
         
-        let table = read_txn.open_table(THREAT_INTEL_TABLE)
-            .context("Failed to open threat intel table")?;
-        
-        let mut indicators = Vec::new();
-        
-        for result in table.iter()? {
-            let (_key, intel_data) = result?;
-            let indicator: ThreatIntelIndicator = bincode::deserialize(intel_data.value())
-                .context("Failed to deserialize threat intel indicator")?;
+        {
+            let mut table = write_txn.open_table(RULE_METADATA_TABLE)
+                .context("Failed to open rule metadata table")?;
             
-            if indicator.value.contains(value) {
-                indicators.push(indicator);
-            }
+            table.insert(metadata.rule_id.as_str(), metadata_data.as_slice())
+                .context("Failed to insert rule metadata")?;
         }
         
-        Ok(indicators)
+        self.audit(&write_txn, "update_rule_metadata", &metadata.rule_id, format!("detections={} false_positive_rate={:.3}", metadata.detection_count, metadata.false_positive_rate))?;
+
+        write_txn.commit()
+            .context("Failed to commit rule metadata update")?;
+
+        log::debug!("Updated metadata for rule: {}", metadata.rule_id);
+        Ok(())
     }
 
-    pub async fn get_high_confidence_indicators(&self, min_confidence: f64) -> Result<Vec<ThreatIntelIndicator>> {
+    pub async fn get_rule_metadata(&self, rule_id: &str) -> Result<Option<RuleMetadata>> {
         let read_txn = self.db.begin_read()
             .context("Failed to begin read transaction")?;
         
-        let table = read_txn.open_table(THREAT_INTEL_TABLE)
-            .context("Failed to open threat intel table")?;
+        let table = read_txn.open_table(RULE_METADATA_TABLE)
+            .context("Failed to open rule metadata table")?;
         
-        let mut indicators = Vec::new();
+        if let Some(metadata_data) = table.get(rule_id)
+            .context("Failed to get rule metadata")? {
+            
+            let metadata: RuleMetadata = bincode::deserialize(metadata_data.value())
+                .context("Failed to deserialize rule metadata")?;
+            
+            Ok(Some(metadata))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Adds per-rule match counts from a scan to the rules' metadata.
+    ///
+    /// `counts` is keyed by rule name; names with no stored rule are ignored.
+    /// Metadata is created for rules that don't have any yet. Returns the
+    /// number of rules updated.
+    pub async fn record_detections(&self, counts: &BTreeMap<String, u64>, detected_at: chrono::DateTime<chrono::Utc>) -> Result<u64> {
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
         
-        for result in table.iter()? {
-            let (_key, intel_data) = result?;
-            let indicator: ThreatIntelIndicator = bincode::deserialize(intel_data.value())
-                .context("Failed to deserialize threat intel indicator")?;
+        let mut updated = 0u64;
+        {
+            let rules_table = write_txn.open_table(YARA_RULES_TABLE)
+                .context("Failed to open YARA rules table")?;
+            let mut metadata_table = write_txn.open_table(RULE_METADATA_TABLE)
+                .context("Failed to open rule metadata table")?;
             
-            if indicator.confidence >= min_confidence {
-                indicators.push(indicator);
+            let mut rule_ids = Vec::new();
+            for result in rules_table.iter()? {
+                let (key, value) = result?;
+                let decoded = deserialize_rule(value.value());
+                let Some(rule) = self.lenient(&YARA_RULES_TABLE, key.value(), decoded)? else { continue };
+                if let Some(count) = counts.get(&rule.name) {
+                    rule_ids.push((key.value().to_string(), *count));
+                }
+            }
+            
+            for (rule_id, count) in rule_ids {
+                let existing = metadata_table.get(rule_id.as_str())?
+                    .map(|value| bincode::deserialize::<RuleMetadata>(value.value()))
+                    .transpose()
+                    .context("Failed to deserialize rule metadata")?;
+                let mut metadata = existing.unwrap_or_else(|| RuleMetadata::new(&rule_id));
+                
+                metadata.detection_count += count;
+                metadata.last_detection = Some(detected_at);
+                
+                let metadata_data = bincode::serialize(&metadata)
+                    .context("Failed to serialize rule metadata")?;
+                metadata_table.insert(rule_id.as_str(), metadata_data.as_slice())
+                    .context("Failed to insert rule metadata")?;
+                updated += 1;
             }
         }
         
-        // Sort by confidence descending
-        indicators.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap());
-        
-        Ok(indicators)
+        self.audit(&write_txn, "record_detections", "*", format!("{} rules updated", updated))?;
+
+        write_txn.commit()
+            .context("Failed to commit detection counts")?;
+
+        log::debug!("Recorded detections for {} rules", updated);
+        Ok(updated)
+    }
+
+    /// Records an analyst verdict that a finding from `rule_id` was a false
+    /// positive, and recomputes the rule's `false_positive_rate`.
+    pub async fn record_false_positive(&self, rule_id: &str) -> Result<RuleMetadata> {
+        self.record_feedback(rule_id, true)
+    }
+
+    /// Records an analyst verdict that a finding from `rule_id` was genuine,
+    /// and recomputes the rule's `false_positive_rate`.
+    pub async fn record_true_positive(&self, rule_id: &str) -> Result<RuleMetadata> {
+        self.record_feedback(rule_id, false)
+    }
+
+    pub async fn get_rule_feedback(&self, rule_id: &str) -> Result<RuleFeedback> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
+        let table = read_txn.open_table(RULE_FEEDBACK_TABLE)
+            .context("Failed to open rule feedback table")?;
+
+        let feedback = table.get(rule_id)?
+            .map(|value| bincode::deserialize(value.value()))
+            .transpose()
+            .context("Failed to deserialize rule feedback")?;
+        Ok(feedback.unwrap_or_default())
+    }
+
+    fn record_feedback(&self, rule_id: &str, false_positive: bool) -> Result<RuleMetadata> {
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+
+        let metadata = {
+            let rules_table = write_txn.open_table(YARA_RULES_TABLE)
+                .context("Failed to open YARA rules table")?;
+            if rules_table.get(rule_id)?.is_none() {
+                return Err(anyhow::anyhow!("No YARA rule with id: {}", rule_id));
+            }
+
+            let mut feedback_table = write_txn.open_table(RULE_FEEDBACK_TABLE)
+                .context("Failed to open rule feedback table")?;
+            let mut feedback: RuleFeedback = feedback_table.get(rule_id)?
+                .map(|value| bincode::deserialize(value.value()))
+                .transpose()
+                .context("Failed to deserialize rule feedback")?
+                .unwrap_or_default();
+
+            if false_positive {
+                feedback.false_positives += 1;
+            } else {
+                feedback.true_positives += 1;
+            }
+
+            let feedback_data = bincode::serialize(&feedback)
+                .context("Failed to serialize rule feedback")?;
+            feedback_table.insert(rule_id, feedback_data.as_slice())
+                .context("Failed to insert rule feedback")?;
+
+            let mut metadata_table = write_txn.open_table(RULE_METADATA_TABLE)
+                .context("Failed to open rule metadata table")?;
+            let existing = metadata_table.get(rule_id)?
+                .map(|value| bincode::deserialize::<RuleMetadata>(value.value()))
+                .transpose()
+                .context("Failed to deserialize rule metadata")?;
+            let mut metadata = existing.unwrap_or_else(|| RuleMetadata::new(rule_id));
+            metadata.false_positive_rate = feedback.false_positive_rate();
+
+            let metadata_data = bincode::serialize(&metadata)
+                .context("Failed to serialize rule metadata")?;
+            metadata_table.insert(rule_id, metadata_data.as_slice())
+                .context("Failed to insert rule metadata")?;
+            metadata
+        };
+
+        let operation = if false_positive { "record_false_positive" } else { "record_true_positive" };
+        self.audit(&write_txn, operation, rule_id, format!(
+            "false_positive_rate={:.3}", metadata.false_positive_rate))?;
+        write_txn.commit()
+            .context("Failed to commit rule feedback")?;
+
+        log::debug!("Recorded {} feedback for rule {}",
+                    if false_positive { "false positive" } else { "true positive" }, rule_id);
+        Ok(metadata)
     }
 
-    pub async fn cleanup_old_indicators(&self, days_old: i64) -> Result<u64> {
-        let cutoff_date = chrono::Utc::now() - chrono::Duration::days(days_old);
-        let mut removed_count = 0u64;
+    /// Stores an indicator under its type and value, replacing any record
+    /// for the same pair.
+    pub async fn store_threat_intel(&self, indicator: &ThreatIntelIndicator) -> Result<()> {
+        let indicator = indicator.keyed();
+        let intel_data = bincode::serialize(&indicator)
+            .context("Failed to serialize threat intel indicator")?;
 
         let write_txn = self.db.begin_write()
             .context("Failed to begin write transaction")?;
@@ -280,114 +1353,2455 @@ impl YaraRulesRedbHook {
             let mut table = write_txn.open_table(THREAT_INTEL_TABLE)
                 .context("Failed to open threat intel table")?;
             
-            let mut keys_to_remove = Vec::new();
-            
-            for result in table.iter()? {
-                let (key, intel_data) = result?;
-                let indicator: ThreatIntelIndicator = bincode::deserialize(intel_data.value())
-                    .context("Failed to deserialize threat intel indicator")?;
-                
-                if indicator.last_seen < cutoff_date {
-                    keys_to_remove.push(key.value().to_string());
-                }
-            }
-            
-            for key in keys_to_remove {
-                table.remove(&key)?;
-                removed_count += 1;
-            }
+            table.insert(indicator.id.as_str(), intel_data.as_slice())
+                .context("Failed to insert threat intel indicator")?;
         }
+        index_intel_value(&write_txn, &indicator)?;
         
+        self.audit(&write_txn, "store_intel", &indicator.id, format!("{}={}", indicator.indicator_type, indicator.value))?;
+
         write_txn.commit()
-            .context("Failed to commit cleanup transaction")?;
+            .context("Failed to commit threat intel storage")?;
 
-        log::info!("Cleaned up {} old threat intel indicators", removed_count);
-        Ok(removed_count)
+        log::info!("Stored threat intel indicator: {} (confidence: {:.2})", 
+                  indicator.value, indicator.confidence);
+        Ok(())
     }
 
-    pub async fn get_database_stats(&self) -> Result<DatabaseStats> {
+    /// The record for `value` as an indicator of `indicator_type`.
+    pub async fn get_threat_intel(&self, indicator_type: &str, value: &str) -> Result<Option<ThreatIntelIndicator>> {
         let read_txn = self.db.begin_read()
             .context("Failed to begin read transaction")?;
         
-        let rules_table = read_txn.open_table(YARA_RULES_TABLE)
-            .context("Failed to open YARA rules table")?;
-        let metadata_table = read_txn.open_table(RULE_METADATA_TABLE)
-            .context("Failed to open rule metadata table")?;
-        let intel_table = read_txn.open_table(THREAT_INTEL_TABLE)
+        let table = read_txn.open_table(THREAT_INTEL_TABLE)
             .context("Failed to open threat intel table")?;
         
-        let rules_count = rules_table.len()? as u64;
-        let metadata_count = metadata_table.len()? as u64;
-        let intel_count = intel_table.len()? as u64;
-        
-        Ok(DatabaseStats {
-            yara_rules_count: rules_count,
-            metadata_entries_count: metadata_count,
-            threat_intel_count: intel_count,
-            database_path: self.db_path.clone(),
-            last_updated: chrono::Utc::now(),
-        })
+        if let Some(intel_data) = table.get(intel_key(indicator_type, value).as_str())
+            .context("Failed to get threat intel indicator")? {
+            
+            let indicator: ThreatIntelIndicator = bincode::deserialize(intel_data.value())
+                .context("Failed to deserialize threat intel indicator")?;
+            
+            Ok(Some(indicator))
+        } else {
+            Ok(None)
+        }
     }
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DatabaseStats {
-    pub yara_rules_count: u64,
-    pub metadata_entries_count: u64,
-    pub threat_intel_count: u64,
-    pub database_path: String,
-    pub last_updated: chrono::DateTime<chrono::Utc>,
-}
-
-// Hook integration functions
-pub async fn initialize_yara_rules_hook(db_path: &str) -> Result<YaraRulesRedbHook> {
-    YaraRulesRedbHook::new(db_path).await
-}
-
-pub async fn sync_yara_rules_from_directory(
-    hook: &YaraRulesRedbHook,
-    rules_directory: &str,
-) -> Result<u64> {
-    let mut synced_count = 0u64;
-    let mut entries = fs::read_dir(rules_directory).await
-        .context("Failed to read rules directory")?;
 
-    while let Some(entry) = entries.next_entry().await? {
-        let path = entry.path();
+    /// Stores an indicator, merging it into an existing record with the same
+    /// type and value.
+    ///
+    /// Merging keeps the earliest `first_seen`, the latest `last_seen`, the highest
+    /// confidence/threat score and the union of feeds, campaigns and MITRE mappings.
+    /// Returns `true` when the indicator was new.
+    pub async fn merge_threat_intel(&self, indicator: &ThreatIntelIndicator) -> Result<bool> {
+        let indicator = &indicator.keyed();
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
         
-        if path.extension().and_then(|s| s.to_str()) == Some("yar") ||
-           path.extension().and_then(|s| s.to_str()) == Some("yara") {
+        let is_new;
+        {
+            let mut table = write_txn.open_table(THREAT_INTEL_TABLE)
+                .context("Failed to open threat intel table")?;
             
-            let content = fs::read_to_string(&path).await
-                .context("Failed to read YARA rule file")?;
+            let existing = match table.get(indicator.id.as_str())
+                .context("Failed to get threat intel indicator")? {
+                Some(intel_data) => Some(bincode::deserialize::<ThreatIntelIndicator>(intel_data.value())
+                    .context("Failed to deserialize threat intel indicator")?),
+                None => None,
+            };
             
-            let rule = YaraRule {
-                id: uuid::Uuid::new_v4().to_string(),
-                name: path.file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("unknown")
-                    .to_string(),
-                content,
-                author: "Auto-imported".to_string(),
-                description: format!("Imported from {}", path.display()),
-                tags: vec!["auto-imported".to_string()],
-                severity: "medium".to_string(),
-                created_at: chrono::Utc::now(),
-                updated_at: chrono::Utc::now(),
-                version: "1.0".to_string(),
-                hash: format!("{:x}", md5::compute(&content)),
-                source: path.to_string_lossy().to_string(),
-                mitre_tactics: vec![],
-                mitre_techniques: vec![],
-                threat_actors: vec![],
-                malware_families: vec![],
+            is_new = existing.is_none();
+            let merged = match existing {
+                Some(existing) => merge_indicators(existing, indicator),
+                None => indicator.clone(),
             };
             
-            hook.store_yara_rule(&rule).await?;
-            synced_count += 1;
+            let intel_data = bincode::serialize(&merged)
+                .context("Failed to serialize threat intel indicator")?;
+            table.insert(merged.id.as_str(), intel_data.as_slice())
+                .context("Failed to insert threat intel indicator")?;
         }
+        index_intel_value(&write_txn, indicator)?;
+        
+        self.audit(&write_txn, "merge_intel", &indicator.id, format!("{}={} new={}", indicator.indicator_type, indicator.value, is_new))?;
+
+        write_txn.commit()
+            .context("Failed to commit threat intel merge")?;
+
+        log::debug!("Merged threat intel indicator: {} (new: {})", indicator.id, is_new);
+        Ok(is_new)
     }
-    
-    log::info!("Synced {} YARA rules from directory: {}", synced_count, rules_directory);
-    Ok(synced_count)
-}
\ No newline at end of file
+
+    /// Moves threat intel records stored under any other key, such as the
+    /// feed ids used before records were keyed by type and value, to their
+    /// `intel_key`, merging records that share a type and value. Records
+    /// for one value under different types stay apart.
+    pub async fn migrate_intel_keys(&self) -> Result<IntelKeyMigration> {
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+        let migration = self.rekey_threat_intel(&write_txn)?;
+        rebuild_intel_value_index(&write_txn)?;
+        self.audit(&write_txn, "migrate_intel_keys", "*", format!("{} re-keyed, {} merged", migration.rekeyed, migration.merged))?;
+        write_txn.commit()
+            .context("Failed to commit threat intel key migration")?;
+
+        log::info!("Re-keyed {} threat intel records and merged {} into records of the same type and value",
+                  migration.rekeyed, migration.merged);
+        Ok(migration)
+    }
+
+    fn rekey_threat_intel(&self, write_txn: &redb::WriteTransaction) -> Result<IntelKeyMigration> {
+        let mut table = write_txn.open_table(THREAT_INTEL_TABLE)
+            .context("Failed to open threat intel table")?;
+
+        let mut misplaced = Vec::new();
+        for result in table.iter()? {
+            let (key, value) = result?;
+            let decoded = bincode::deserialize::<ThreatIntelIndicator>(value.value())
+                .context("Failed to deserialize threat intel indicator");
+            let Some(indicator) = self.lenient(&THREAT_INTEL_TABLE, key.value(), decoded)? else { continue };
+            let keyed = indicator.keyed();
+            if key.value() != keyed.id || indicator.id != keyed.id || indicator.indicator_type != keyed.indicator_type {
+                misplaced.push((key.value().to_string(), keyed));
+            }
+        }
+
+        let mut migration = IntelKeyMigration::default();
+        for (old_key, indicator) in misplaced {
+            table.remove(old_key.as_str())
+                .context("Failed to remove threat intel indicator")?;
+            let existing = match table.get(indicator.id.as_str())? {
+                Some(data) => Some(bincode::deserialize::<ThreatIntelIndicator>(data.value())
+                    .context("Failed to deserialize threat intel indicator")?),
+                None => None,
+            };
+            let indicator = match existing {
+                Some(existing) => {
+                    migration.merged += 1;
+                    merge_indicators(existing, &indicator)
+                }
+                None => {
+                    migration.rekeyed += 1;
+                    indicator
+                }
+            };
+            let intel_data = bincode::serialize(&indicator)
+                .context("Failed to serialize threat intel indicator")?;
+            table.insert(indicator.id.as_str(), intel_data.as_slice())
+                .context("Failed to insert threat intel indicator")?;
+        }
+        Ok(migration)
+    }
+
+    /// Returns the last-polled TAXII timestamp stored under `collection_key`.
+    pub async fn get_taxii_cursor(&self, collection_key: &str) -> Result<Option<String>> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
+        
+        let table = read_txn.open_table(TAXII_STATE_TABLE)
+            .context("Failed to open TAXII state table")?;
+        
+        let cursor = table.get(collection_key)
+            .context("Failed to get TAXII cursor")?
+            .map(|value| value.value().to_string());
+        
+        Ok(cursor)
+    }
+
+    pub async fn set_taxii_cursor(&self, collection_key: &str, cursor: &str) -> Result<()> {
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+        
+        {
+            let mut table = write_txn.open_table(TAXII_STATE_TABLE)
+                .context("Failed to open TAXII state table")?;
+            
+            table.insert(collection_key, cursor)
+                .context("Failed to store TAXII cursor")?;
+        }
+        
+        self.audit(&write_txn, "set_taxii_cursor", collection_key, cursor.to_string())?;
+
+        write_txn.commit()
+            .context("Failed to commit TAXII cursor")?;
+
+        log::debug!("Updated TAXII cursor for {}: {}", collection_key, cursor);
+        Ok(())
+    }
+
+    /// The records whose value is exactly `value`, one per indicator type it
+    /// is stored as, found through the value index without reading any
+    /// other record.
+    pub async fn get_threat_intel_exact(&self, value: &str) -> Result<Vec<ThreatIntelIndicator>> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
+        let table = read_txn.open_table(THREAT_INTEL_TABLE)
+            .context("Failed to open threat intel table")?;
+        let values = read_txn.open_multimap_table(INTEL_VALUES_TABLE)
+            .context("Failed to open threat intel value index")?;
+
+        let mut indicators = Vec::new();
+        for id in values.get(value)? {
+            let id = id?;
+            let Some(intel_data) = table.get(id.value())? else { continue };
+            let decoded = bincode::deserialize::<ThreatIntelIndicator>(intel_data.value())
+                .context("Failed to deserialize threat intel indicator");
+            let Some(indicator) = self.lenient(&THREAT_INTEL_TABLE, id.value(), decoded)? else { continue };
+            if indicator.value == value {
+                indicators.push(indicator);
+            }
+        }
+        Ok(indicators)
+    }
+
+    /// Indicators matching `value`, compared in the way that suits it: an IP
+    /// or CIDR range matches the IP indicators it contains, a domain (with
+    /// optional `*` wildcards) matches domain and URL indicators by name or
+    /// subdomain, and anything else is a substring of the indicator value.
+    ///
+    /// Without `indicator_type` a value stored as several types returns one
+    /// record per type; with it only records of that type match.
+    ///
+    /// This reads every stored indicator; `get_threat_intel_exact` and
+    /// `get_threat_intel` are the fast paths for exact values.
+    pub async fn get_threat_intel_by_value(&self, value: &str, indicator_type: Option<&str>) -> Result<Vec<ThreatIntelIndicator>> {
+        self.find_indicators(&IndicatorQuery::parse(value), indicator_type)
+    }
+
+    /// IPv4 and IPv6 indicators inside `cidr`, e.g. `10.0.0.0/8`, for
+    /// enriching findings that carry IP addresses. Indicators that are
+    /// networks themselves match when wholly inside `cidr`.
+    pub async fn get_indicators_in_cidr(&self, cidr: &str) -> Result<Vec<ThreatIntelIndicator>> {
+        let network: IpNetwork = cidr.parse()?;
+        self.find_indicators(&IndicatorQuery::Network(network), None)
+    }
+
+    fn find_indicators(&self, query: &IndicatorQuery, indicator_type: Option<&str>) -> Result<Vec<ThreatIntelIndicator>> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
+        
+        let table = read_txn.open_table(THREAT_INTEL_TABLE)
+            .context("Failed to open threat intel table")?;
+        
+        let mut indicators = Vec::new();
+        
+        for result in table.iter()? {
+            let (key, intel_data) = result?;
+            let decoded = bincode::deserialize::<ThreatIntelIndicator>(intel_data.value())
+                .context("Failed to deserialize threat intel indicator");
+            let Some(indicator) = self.lenient(&THREAT_INTEL_TABLE, key.value(), decoded)? else { continue };
+            
+            if indicator_type.is_none_or(|wanted| indicator.indicator_type.eq_ignore_ascii_case(wanted.trim()))
+                && query.matches(&indicator)
+            {
+                indicators.push(indicator);
+            }
+        }
+        
+        Ok(indicators)
+    }
+
+    pub async fn get_high_confidence_indicators(&self, min_confidence: f64) -> Result<Vec<ThreatIntelIndicator>> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
+        
+        let table = read_txn.open_table(THREAT_INTEL_TABLE)
+            .context("Failed to open threat intel table")?;
+        
+        let mut indicators = Vec::new();
+        
+        for result in table.iter()? {
+            let (key, intel_data) = result?;
+            let decoded = bincode::deserialize::<ThreatIntelIndicator>(intel_data.value())
+                .context("Failed to deserialize threat intel indicator");
+            let Some(indicator) = self.lenient(&THREAT_INTEL_TABLE, key.value(), decoded)? else { continue };
+            
+            if indicator.confidence >= min_confidence {
+                indicators.push(indicator);
+            }
+        }
+        
+        // Sort by confidence descending
+        indicators.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap());
+        
+        Ok(indicators)
+    }
+
+    /// Removes indicators last seen more than `days_old` days ago and returns
+    /// them, oldest first. With `dry_run` nothing is written and the
+    /// indicators that would be removed are returned.
+    pub async fn cleanup_old_indicators(&self, days_old: i64, dry_run: bool) -> Result<Vec<ThreatIntelIndicator>> {
+        let cutoff_date = self.now() - chrono::Duration::days(days_old);
+
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+        let mut removed = Vec::new();
+        
+        {
+            let mut table = write_txn.open_table(THREAT_INTEL_TABLE)
+                .context("Failed to open threat intel table")?;
+            
+            for result in table.iter()? {
+                let (key, intel_data) = result?;
+                let decoded = bincode::deserialize::<ThreatIntelIndicator>(intel_data.value())
+                    .context("Failed to deserialize threat intel indicator");
+                let Some(indicator) = self.lenient(&THREAT_INTEL_TABLE, key.value(), decoded)? else { continue };
+                
+                if indicator.last_seen < cutoff_date {
+                    removed.push((key.value().to_string(), indicator));
+                }
+            }
+
+            if dry_run || removed.is_empty() {
+                return Ok(oldest_first(removed));
+            }
+            
+            let mut values = write_txn.open_multimap_table(INTEL_VALUES_TABLE)
+                .context("Failed to open threat intel value index")?;
+            for (key, indicator) in &removed {
+                table.remove(key.as_str())?;
+                values.remove(indicator.value.as_str(), key.as_str())?;
+            }
+        }
+        
+        self.audit(&write_txn, "prune_intel", "*", format!("{} indicators last seen over {} days ago removed", removed.len(), days_old))?;
+
+        write_txn.commit()
+            .context("Failed to commit cleanup transaction")?;
+
+        log::info!("Cleaned up {} old threat intel indicators", removed.len());
+        Ok(oldest_first(removed))
+    }
+
+    /// Deletes the indicator with id `id` (`<type>:<value>`, see
+    /// `intel_key`). Returns whether it existed.
+    pub async fn delete_threat_intel(&self, id: &str) -> Result<bool> {
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+
+        let existed = {
+            let mut table = write_txn.open_table(THREAT_INTEL_TABLE)
+                .context("Failed to open threat intel table")?;
+            let removed = table.remove(id)
+                .context("Failed to delete threat intel indicator")?;
+            // An unreadable record leaves a stale index entry, which lookups ignore
+            let value = removed.as_ref()
+                .and_then(|data| bincode::deserialize::<ThreatIntelIndicator>(data.value()).ok())
+                .map(|indicator| indicator.value);
+            if let Some(value) = value {
+                write_txn.open_multimap_table(INTEL_VALUES_TABLE)
+                    .context("Failed to open threat intel value index")?
+                    .remove(value.as_str(), id)?;
+            }
+            removed.is_some()
+        };
+        if !existed {
+            return Ok(false);
+        }
+
+        self.audit(&write_txn, "delete_intel", id, String::new())?;
+
+        write_txn.commit()
+            .context("Failed to commit threat intel deletion")?;
+
+        log::info!("Deleted threat intel indicator: {}", id);
+        Ok(true)
+    }
+
+    /// Imports rules, metadata and threat intel from another ReDB file.
+    ///
+    /// Records missing locally are added; conflicts on the same id are resolved
+    /// by `strategy`. "Newer" compares `updated_at` for rules, `last_seen` for
+    /// intel and `last_detection` for metadata, keeping the existing record on
+    /// ties. Everything is applied in a single transaction.
+    pub async fn merge_database(&self, other: &Path, strategy: MergeStrategy) -> Result<MergeReport> {
+        let other_db = Database::open(other)
+            .with_context(|| format!("Failed to open database to merge: {}", other.display()))?;
+        let read_txn = other_db.begin_read()
+            .context("Failed to begin read transaction")?;
+
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+
+        // Blobs are keyed by content, so existing ones never need replacing
+        merge_table(&read_txn, &write_txn, RULE_BLOBS_TABLE, MergeStrategy::KeepExisting, |_, _| Ok(false))
+            .context("Failed to merge rule blobs")?;
+
+        let report = MergeReport {
+            rules: merge_table(&read_txn, &write_txn, YARA_RULES_TABLE, strategy, |existing, incoming| {
+                let existing = deserialize_rule(existing)?;
+                let incoming = deserialize_rule(incoming)?;
+                Ok(incoming.updated_at > existing.updated_at)
+            }).context("Failed to merge YARA rules")?,
+            metadata: merge_table(&read_txn, &write_txn, RULE_METADATA_TABLE, strategy, |existing, incoming| {
+                let existing: RuleMetadata = bincode::deserialize(existing)?;
+                let incoming: RuleMetadata = bincode::deserialize(incoming)?;
+                Ok(incoming.last_detection > existing.last_detection)
+            }).context("Failed to merge rule metadata")?,
+            threat_intel: merge_table(&read_txn, &write_txn, THREAT_INTEL_TABLE, strategy, |existing, incoming| {
+                let existing: ThreatIntelIndicator = bincode::deserialize(existing)?;
+                let incoming: ThreatIntelIndicator = bincode::deserialize(incoming)?;
+                Ok(incoming.last_seen > existing.last_seen)
+            }).context("Failed to merge threat intel")?,
+        };
+        // The other database may predate type-and-value keys
+        let intel_migration = self.rekey_threat_intel(&write_txn)?;
+        if intel_migration.rekeyed + intel_migration.merged > 0 {
+            log::info!("Re-keyed merged threat intel by type and value: {:?}", intel_migration);
+        }
+
+        rebuild_name_index(&write_txn)?;
+        rebuild_intel_value_index(&write_txn)?;
+        rebuild_tag_index(&write_txn)?;
+        rebuild_hash_index(&write_txn)?;
+        rebuild_mitre_index(&write_txn)?;
+        self.audit(&write_txn, "merge_database", &other.to_string_lossy(), serde_json::to_string(&report)?)?;
+
+        write_txn.commit()
+            .context("Failed to commit database merge")?;
+
+        log::info!("Merged {} into {}: rules {:?}, metadata {:?}, intel {:?}",
+                  other.display(), self.db_path, report.rules, report.metadata, report.threat_intel);
+        Ok(report)
+    }
+
+    /// Tries to deserialize every rule, metadata, intel and feedback record
+    /// and returns the ones that fail, whatever the lenient-read setting.
+    pub async fn find_corrupt_records(&self) -> Result<Vec<CorruptRecord>> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
+        let blobs = read_txn.open_table(RULE_BLOBS_TABLE)
+            .context("Failed to open rule blobs table")?;
+
+        type Check<'a> = &'a dyn Fn(&[u8]) -> Result<()>;
+        let checks: [(TableDefinition<&str, &[u8]>, Check); 4] = [
+            (YARA_RULES_TABLE, &|data| decode_rule(data, &blobs).map(drop)),
+            (RULE_METADATA_TABLE, &|data| Ok(bincode::deserialize::<RuleMetadata>(data).map(drop)?)),
+            (THREAT_INTEL_TABLE, &|data| Ok(bincode::deserialize::<ThreatIntelIndicator>(data).map(drop)?)),
+            (RULE_FEEDBACK_TABLE, &|data| Ok(bincode::deserialize::<RuleFeedback>(data).map(drop)?)),
+        ];
+
+        let mut corrupt = Vec::new();
+        for (definition, check) in checks {
+            let table = read_txn.open_table(definition)
+                .with_context(|| format!("Failed to open {} table", definition.name()))?;
+
+            for result in table.iter()? {
+                let (key, value) = result?;
+                if let Err(e) = check(value.value()) {
+                    corrupt.push(CorruptRecord {
+                        table: definition.name().to_string(),
+                        key: key.value().to_string(),
+                        error: format!("{:#}", e),
+                    });
+                }
+            }
+        }
+        Ok(corrupt)
+    }
+
+    /// Moves `records` out of their tables into `corrupt_records`, keeping the
+    /// raw bytes for later inspection. Returns the number moved.
+    pub async fn quarantine_corrupt_records(&self, records: &[CorruptRecord]) -> Result<u64> {
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+
+        let mut moved = 0u64;
+        for record in records {
+            let definition = QUARANTINABLE_TABLES.into_iter()
+                .find(|definition| definition.name() == record.table)
+                .ok_or_else(|| anyhow::anyhow!("Cannot quarantine records from table {}", record.table))?;
+
+            let raw = {
+                let mut table = write_txn.open_table(definition)
+                    .with_context(|| format!("Failed to open {} table", record.table))?;
+                let removed = table.remove(record.key.as_str())?;
+                removed.map(|value| value.value().to_vec())
+            };
+            let Some(raw) = raw else { continue };
+
+            {
+                let mut corrupt_table = write_txn.open_table(CORRUPT_RECORDS_TABLE)
+                    .context("Failed to open corrupt records table")?;
+                let quarantine_key = format!("{}/{}", record.table, record.key);
+                corrupt_table.insert(quarantine_key.as_str(), raw.as_slice())
+                    .context("Failed to quarantine corrupt record")?;
+            }
+            self.audit(&write_txn, "quarantine_record", &format!("{}/{}", record.table, record.key), record.error.clone())?;
+            moved += 1;
+        }
+
+        write_txn.commit()
+            .context("Failed to commit corrupt record quarantine")?;
+
+        log::info!("Quarantined {} corrupt records", moved);
+        Ok(moved)
+    }
+
+    /// Moves inline rule bodies into the `rule_blobs` table, for databases
+    /// created before content deduplication was enabled. Rules whose content
+    /// doesn't match their stored hash are left inline. With compression on,
+    /// plain blobs already stored are compressed as well. Returns the number
+    /// of rules converted.
+    pub async fn migrate_rule_blobs(&self) -> Result<u64> {
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+
+        let mut migrated = 0u64;
+        let mut compressed_blobs = 0u64;
+        {
+            let mut table = write_txn.open_table(YARA_RULES_TABLE)
+                .context("Failed to open YARA rules table")?;
+            let mut blobs = write_txn.open_table(RULE_BLOBS_TABLE)
+                .context("Failed to open rule blobs table")?;
+
+            let mut inline_rules = Vec::new();
+            for result in table.iter()? {
+                let (key, value) = result?;
+                let decoded = deserialize_rule(value.value());
+                let Some(rule) = self.lenient(&YARA_RULES_TABLE, key.value(), decoded)? else { continue };
+                if !rule.content.is_empty() && content_hash(&rule.content) == rule.hash {
+                    inline_rules.push(rule);
+                }
+            }
+
+            for rule in inline_rules {
+                let writer = BlobWriter { table: &mut blobs, compress: self.compress_bodies };
+                let rule_data = encode_rule(&rule, Some(writer))?;
+                table.insert(rule.id.as_str(), rule_data.as_slice())
+                    .context("Failed to rewrite YARA rule")?;
+                migrated += 1;
+            }
+
+            if self.compress_bodies {
+                let mut plain = Vec::new();
+                for result in blobs.iter()? {
+                    let (hash, blob) = result?;
+                    if !is_compressed(blob.value()) {
+                        plain.push((hash.value().to_string(), blob.value().to_vec()));
+                    }
+                }
+                for (hash, blob) in plain {
+                    let compressed = compress_blob(&blob)?;
+                    if compressed.len() < blob.len() {
+                        blobs.insert(hash.as_str(), compressed.as_slice())
+                            .context("Failed to rewrite rule blob")?;
+                        compressed_blobs += 1;
+                    }
+                }
+            }
+        }
+
+        self.audit(&write_txn, "migrate_blobs", "*", format!("{} rules moved to blobs, {} blobs compressed", migrated, compressed_blobs))?;
+
+        write_txn.commit()
+            .context("Failed to commit rule blob migration")?;
+
+        log::info!("Moved {} rule bodies into the rule blobs table", migrated);
+        if compressed_blobs > 0 {
+            log::info!("Compressed {} existing rule blobs", compressed_blobs);
+        }
+        Ok(migrated)
+    }
+
+    /// Sets the MITRE techniques (and tactics, where given) of every rule
+    /// named in `mapping_file`, in one transaction. See `load_mitre_mapping`
+    /// for the accepted formats.
+    pub async fn apply_mitre_mapping(&self, mapping_file: &Path) -> Result<MitreMappingReport> {
+        let mapping = load_mitre_mapping(mapping_file)?;
+        let mut report = MitreMappingReport {
+            entries: mapping.len() as u64,
+            ..Default::default()
+        };
+
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+        {
+            let mut table = write_txn.open_table(YARA_RULES_TABLE)
+                .context("Failed to open YARA rules table")?;
+            let mut technique_index = write_txn.open_multimap_table(RULE_TECHNIQUES_TABLE)
+                .context("Failed to open rule technique index")?;
+            let mut tactic_index = write_txn.open_multimap_table(RULE_TACTICS_TABLE)
+                .context("Failed to open rule tactic index")?;
+
+            let mut matched = std::collections::BTreeSet::new();
+            let mut changed_rules = Vec::new();
+            for result in table.iter()? {
+                let (key, value) = result?;
+                // Only the MITRE fields change, so blob-backed records are
+                // rewritten as stored without resolving their content
+                let decoded = deserialize_rule(value.value());
+                let Some(mut rule) = self.lenient(&YARA_RULES_TABLE, key.value(), decoded)? else { continue };
+                let Some(entry) = mapping.get(&rule.name) else { continue };
+                matched.insert(rule.name.clone());
+
+                let tactics = if entry.tactics.is_empty() { &rule.mitre_tactics } else { &entry.tactics };
+                if rule.mitre_techniques == entry.techniques && *tactics == rule.mitre_tactics {
+                    report.rules_unchanged += 1;
+                    continue;
+                }
+
+                unindex_mitre_ids(&mut technique_index, &rule.id, &rule.mitre_techniques)?;
+                unindex_mitre_ids(&mut tactic_index, &rule.id, &rule.mitre_tactics)?;
+                rule.mitre_tactics = tactics.clone();
+                rule.mitre_techniques = entry.techniques.clone();
+                rule.updated_at = self.now();
+                changed_rules.push(rule);
+            }
+
+            for rule in changed_rules {
+                index_mitre_ids(&mut technique_index, &rule.id, &rule.mitre_techniques)?;
+                index_mitre_ids(&mut tactic_index, &rule.id, &rule.mitre_tactics)?;
+                let rule_data = bincode::serialize(&rule)
+                    .context("Failed to serialize YARA rule")?;
+                table.insert(rule.id.as_str(), rule_data.as_slice())
+                    .context("Failed to update YARA rule")?;
+                report.rules_updated += 1;
+            }
+
+            report.unmatched_entries = mapping.keys()
+                .filter(|name| !matched.contains(*name))
+                .cloned()
+                .collect();
+        }
+        self.audit(&write_txn, "apply_mitre_mapping", &mapping_file.to_string_lossy(), format!("{} rules updated", report.rules_updated))?;
+
+        write_txn.commit()
+            .context("Failed to commit MITRE mapping")?;
+
+        log::info!("Applied MITRE mapping from {}: {} rules updated, {} unchanged, {} entries without a rule",
+                  mapping_file.display(), report.rules_updated, report.rules_unchanged,
+                  report.unmatched_entries.len());
+        Ok(report)
+    }
+
+    /// Writes every rule, metadata entry and indicator to `writer`.
+    ///
+    /// Records are streamed from a single read transaction, so memory use
+    /// doesn't grow with the database. NDJSON puts one `ExportRecord` per
+    /// line; JSON writes the same records as one array.
+    pub async fn export_database<W: Write>(&self, writer: W, format: ExportFormat) -> Result<TransferCounts> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
+        
+        let rules_table = read_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let blobs = read_txn.open_table(RULE_BLOBS_TABLE)
+            .context("Failed to open rule blobs table")?;
+        let metadata_table = read_txn.open_table(RULE_METADATA_TABLE)
+            .context("Failed to open rule metadata table")?;
+        let intel_table = read_txn.open_table(THREAT_INTEL_TABLE)
+            .context("Failed to open threat intel table")?;
+
+        let mut out = RecordWriter::new(writer, format)?;
+        let mut counts = TransferCounts::default();
+
+        for result in rules_table.iter()? {
+            let (key, value) = result?;
+            let Some(rule) = self.lenient(&YARA_RULES_TABLE, key.value(), decode_rule(value.value(), &blobs))? else { continue };
+            out.write(&ExportRecord::Rule(rule))?;
+            counts.rules += 1;
+        }
+        for result in metadata_table.iter()? {
+            let (key, value) = result?;
+            let decoded = bincode::deserialize::<RuleMetadata>(value.value())
+                .context("Failed to deserialize rule metadata");
+            let Some(metadata) = self.lenient(&RULE_METADATA_TABLE, key.value(), decoded)? else { continue };
+            out.write(&ExportRecord::RuleMetadata(metadata))?;
+            counts.metadata += 1;
+        }
+        for result in intel_table.iter()? {
+            let (key, value) = result?;
+            let decoded = bincode::deserialize::<ThreatIntelIndicator>(value.value())
+                .context("Failed to deserialize threat intel");
+            let Some(indicator) = self.lenient(&THREAT_INTEL_TABLE, key.value(), decoded)? else { continue };
+            out.write(&ExportRecord::ThreatIntel(indicator))?;
+            counts.threat_intel += 1;
+        }
+        out.finish()?;
+
+        log::info!("Exported {}: {:?}", self.db_path, counts);
+        Ok(counts)
+    }
+
+    /// Writes the operational metrics of every stored rule for dashboards:
+    /// its metadata and analyst feedback joined with the rule's name and
+    /// severity, plus derived rates. Rules without metadata get zero counts;
+    /// metadata left behind by deleted rules is not exported. Returns the
+    /// number of rows written.
+    pub async fn export_metadata<W: Write>(&self, writer: W, format: MetadataFormat) -> Result<u64> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
+
+        let rules_table = read_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let metadata_table = read_txn.open_table(RULE_METADATA_TABLE)
+            .context("Failed to open rule metadata table")?;
+        let feedback_table = read_txn.open_table(RULE_FEEDBACK_TABLE)
+            .context("Failed to open rule feedback table")?;
+
+        let now = self.now();
+        let mut out = MetricsWriter::new(writer, format)?;
+        let mut rows = 0u64;
+
+        for result in rules_table.iter()? {
+            let (key, value) = result?;
+            let Some(rule) = self.lenient(&YARA_RULES_TABLE, key.value(), deserialize_rule(value.value()))? else { continue };
+
+            let decoded = metadata_table.get(key.value())?
+                .map(|value| bincode::deserialize::<RuleMetadata>(value.value()))
+                .transpose()
+                .context("Failed to deserialize rule metadata");
+            let metadata = self.lenient(&RULE_METADATA_TABLE, key.value(), decoded)?
+                .flatten()
+                .unwrap_or_else(|| RuleMetadata::new(&rule.id));
+            let decoded = feedback_table.get(key.value())?
+                .map(|value| bincode::deserialize::<RuleFeedback>(value.value()))
+                .transpose()
+                .context("Failed to deserialize rule feedback");
+            let feedback = self.lenient(&RULE_FEEDBACK_TABLE, key.value(), decoded)?
+                .flatten()
+                .unwrap_or_default();
+
+            out.write(&RuleMetrics::new(&rule, metadata, feedback, now))?;
+            rows += 1;
+        }
+        out.finish()?;
+
+        log::info!("Exported metadata of {} rules from {}", rows, self.db_path);
+        Ok(rows)
+    }
+
+    /// Loads records produced by `export_database`, replacing records with
+    /// the same key. NDJSON is read line by line and committed in batches of
+    /// `IMPORT_BATCH_SIZE`; a JSON document is read whole.
+    pub async fn import_database<R: BufRead>(&self, reader: R, format: ExportFormat) -> Result<TransferCounts> {
+        let mut counts = TransferCounts::default();
+
+        match format {
+            ExportFormat::Ndjson => {
+                let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+                for (line_number, line) in reader.lines().enumerate() {
+                    let line = line.context("Failed to read import file")?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let record: ExportRecord = serde_json::from_str(&line)
+                        .with_context(|| format!("Invalid record on line {}", line_number + 1))?;
+                    batch.push(record);
+
+                    if batch.len() >= IMPORT_BATCH_SIZE {
+                        self.write_records(&batch, &mut counts)?;
+                        batch.clear();
+                    }
+                }
+                self.write_records(&batch, &mut counts)?;
+            }
+            ExportFormat::Json => {
+                let records: Vec<ExportRecord> = serde_json::from_reader(reader)
+                    .context("Failed to parse JSON export")?;
+                for batch in records.chunks(IMPORT_BATCH_SIZE) {
+                    self.write_records(batch, &mut counts)?;
+                }
+            }
+        }
+
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+        rebuild_name_index(&write_txn)?;
+        rebuild_intel_value_index(&write_txn)?;
+        rebuild_tag_index(&write_txn)?;
+        rebuild_hash_index(&write_txn)?;
+        rebuild_mitre_index(&write_txn)?;
+        write_txn.commit()
+            .context("Failed to commit rule name, intel value, tag, hash and MITRE indexes")?;
+
+        log::info!("Imported into {}: {:?}", self.db_path, counts);
+        Ok(counts)
+    }
+
+    fn write_records(&self, records: &[ExportRecord], counts: &mut TransferCounts) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let before = *counts;
+        let write_txn = self.db.begin_write()
+            .context("Failed to begin write transaction")?;
+        
+        {
+            let mut rules_table = write_txn.open_table(YARA_RULES_TABLE)
+                .context("Failed to open YARA rules table")?;
+            let mut blobs = write_txn.open_table(RULE_BLOBS_TABLE)
+                .context("Failed to open rule blobs table")?;
+            let mut metadata_table = write_txn.open_table(RULE_METADATA_TABLE)
+                .context("Failed to open rule metadata table")?;
+            let mut intel_table = write_txn.open_table(THREAT_INTEL_TABLE)
+                .context("Failed to open threat intel table")?;
+
+            for record in records {
+                match record {
+                    ExportRecord::Rule(rule) => {
+                        let data = encode_rule(rule, self.body_storage(&mut blobs))?;
+                        rules_table.insert(rule.id.as_str(), data.as_slice())?;
+                        counts.rules += 1;
+                    }
+                    ExportRecord::RuleMetadata(metadata) => {
+                        let data = bincode::serialize(metadata)
+                            .context("Failed to serialize rule metadata")?;
+                        metadata_table.insert(metadata.rule_id.as_str(), data.as_slice())?;
+                        counts.metadata += 1;
+                    }
+                    ExportRecord::ThreatIntel(indicator) => {
+                        let indicator = indicator.keyed();
+                        let data = bincode::serialize(&indicator)
+                            .context("Failed to serialize threat intel")?;
+                        intel_table.insert(indicator.id.as_str(), data.as_slice())?;
+                        counts.threat_intel += 1;
+                    }
+                }
+            }
+        }
+        
+        self.audit(&write_txn, "import", "*", format!("{} rules, {} metadata, {} indicators",
+            counts.rules - before.rules, counts.metadata - before.metadata,
+            counts.threat_intel - before.threat_intel))?;
+
+        write_txn.commit()
+            .context("Failed to commit imported records")?;
+        Ok(())
+    }
+
+    /// Rule and indicator counts per source: `YaraRule::source` for rules and
+    /// each of `source_feeds` for indicators (an indicator seen in two feeds
+    /// counts towards both). `last_updated` is the newest rule `updated_at` or
+    /// indicator `last_seen`, to spot feeds that have gone stale.
+    pub async fn stats_by_source(&self) -> Result<BTreeMap<String, SourceStats>> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
+        
+        let rules_table = read_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let intel_table = read_txn.open_table(THREAT_INTEL_TABLE)
+            .context("Failed to open threat intel table")?;
+
+        let mut sources: BTreeMap<String, SourceStats> = BTreeMap::new();
+
+        for result in rules_table.iter()? {
+            let (key, value) = result?;
+            let decoded = deserialize_rule(value.value());
+            let Some(rule) = self.lenient(&YARA_RULES_TABLE, key.value(), decoded)? else { continue };
+            let stats = sources.entry(rule.source).or_default();
+            stats.rules += 1;
+            stats.last_updated = stats.last_updated.max(Some(rule.updated_at));
+        }
+
+        for result in intel_table.iter()? {
+            let (key, value) = result?;
+            let decoded = bincode::deserialize::<ThreatIntelIndicator>(value.value())
+                .context("Failed to deserialize threat intel");
+            let Some(indicator) = self.lenient(&THREAT_INTEL_TABLE, key.value(), decoded)? else { continue };
+            for feed in indicator.source_feeds {
+                let stats = sources.entry(feed).or_default();
+                stats.threat_intel += 1;
+                stats.last_updated = stats.last_updated.max(Some(indicator.last_seen));
+            }
+        }
+
+        Ok(sources)
+    }
+
+    pub async fn get_database_stats(&self) -> Result<DatabaseStats> {
+        let read_txn = self.db.begin_read()
+            .context("Failed to begin read transaction")?;
+        
+        let rules_table = read_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let metadata_table = read_txn.open_table(RULE_METADATA_TABLE)
+            .context("Failed to open rule metadata table")?;
+        let intel_table = read_txn.open_table(THREAT_INTEL_TABLE)
+            .context("Failed to open threat intel table")?;
+        let blobs_table = read_txn.open_table(RULE_BLOBS_TABLE)
+            .context("Failed to open rule blobs table")?;
+        
+        let rules_count = rules_table.len()?;
+        let metadata_count = metadata_table.len()?;
+        let intel_count = intel_table.len()?;
+        let blob_count = blobs_table.len()?;
+
+        let mut blob_bytes = BlobBytes::default();
+        for result in blobs_table.iter()? {
+            let (_, blob) = result?;
+            let blob = blob.value();
+            blob_bytes.stored += blob.len() as u64;
+            if is_compressed(blob) {
+                blob_bytes.compressed_blobs += 1;
+                blob_bytes.uncompressed += zstd::decode_all(blob).map_or(0, |plain| plain.len() as u64);
+            } else {
+                blob_bytes.uncompressed += blob.len() as u64;
+            }
+        }
+
+        let mut blob_backed_rules = 0u64;
+        if blob_count > 0 {
+            for result in rules_table.iter()? {
+                let (key, value) = result?;
+                let decoded = deserialize_rule(value.value());
+                let Some(rule) = self.lenient(&YARA_RULES_TABLE, key.value(), decoded)? else { continue };
+                if rule.content.is_empty() {
+                    blob_backed_rules += 1;
+                }
+            }
+        }
+        
+        Ok(DatabaseStats {
+            yara_rules_count: rules_count,
+            metadata_entries_count: metadata_count,
+            threat_intel_count: intel_count,
+            rule_blob_count: blob_count,
+            blob_backed_rules,
+            blob_bytes,
+            database_path: self.db_path.clone(),
+            last_updated: self.now(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DatabaseStats {
+    pub yara_rules_count: u64,
+    pub metadata_entries_count: u64,
+    pub threat_intel_count: u64,
+    /// Distinct rule bodies in the content-addressed `rule_blobs` table.
+    #[serde(default)]
+    pub rule_blob_count: u64,
+    /// Rules whose body is stored as a blob rather than inline.
+    #[serde(default)]
+    pub blob_backed_rules: u64,
+    #[serde(default)]
+    pub blob_bytes: BlobBytes,
+    pub database_path: String,
+    pub last_updated: chrono::DateTime<chrono::Utc>,
+}
+
+/// Size of the `rule_blobs` table's values.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct BlobBytes {
+    /// Bytes as stored, compressed or not.
+    pub stored: u64,
+    /// Bytes of rule text the blobs hold.
+    pub uncompressed: u64,
+    pub compressed_blobs: u64,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SourceStats {
+    pub rules: u64,
+    pub threat_intel: u64,
+    pub last_updated: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl DatabaseStats {
+    /// Blob-backed rules per stored blob; 1.0 means no sharing, and 0.0 that
+    /// content deduplication isn't in use.
+    pub fn dedup_ratio(&self) -> f64 {
+        if self.rule_blob_count == 0 {
+            return 0.0;
+        }
+        self.blob_backed_rules as f64 / self.rule_blob_count as f64
+    }
+}
+
+/// File layout for `export_database` and `import_database`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One record per line; streams in both directions.
+    Ndjson,
+    /// A single array of records; import holds it in memory.
+    Json,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ndjson" => Ok(ExportFormat::Ndjson),
+            "json" => Ok(ExportFormat::Json),
+            other => Err(anyhow::anyhow!(
+                "Invalid export format '{}': expected ndjson or json", other
+            )),
+        }
+    }
+}
+
+/// File layout for `export_metadata`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataFormat {
+    /// A single array of `RuleMetrics` objects.
+    Json,
+    /// A header row and one row per rule; list fields are `;`-separated.
+    Csv,
+}
+
+impl std::str::FromStr for MetadataFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(MetadataFormat::Json),
+            "csv" => Ok(MetadataFormat::Csv),
+            other => Err(anyhow::anyhow!(
+                "Invalid metadata format '{}': expected json or csv", other
+            )),
+        }
+    }
+}
+
+/// Operational metrics of one rule, as `export_metadata` writes them.
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleMetrics {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub severity: String,
+    pub source: String,
+    pub tags: Vec<String>,
+    pub detection_count: u64,
+    pub last_detection: Option<chrono::DateTime<chrono::Utc>>,
+    /// Whole days since `last_detection`.
+    pub days_since_last_detection: Option<i64>,
+    /// Detections averaged over the days since the rule was created,
+    /// counting at least one day.
+    pub detections_per_day: f64,
+    pub true_positives: u64,
+    pub false_positives: u64,
+    pub false_positive_rate: f64,
+    pub performance_score: f64,
+    pub effectiveness_rating: String,
+}
+
+impl RuleMetrics {
+    const CSV_HEADER: &'static str = "rule_id,rule_name,severity,source,tags,detection_count,last_detection,\
+        days_since_last_detection,detections_per_day,true_positives,false_positives,false_positive_rate,\
+        performance_score,effectiveness_rating";
+
+    fn new(rule: &YaraRule, metadata: RuleMetadata, feedback: RuleFeedback, now: chrono::DateTime<chrono::Utc>) -> Self {
+        let days_tracked = ((now - rule.created_at).num_seconds() as f64 / 86_400.0).max(1.0);
+        Self {
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            severity: rule.severity.clone(),
+            source: rule.source.clone(),
+            tags: rule.tags.clone(),
+            detection_count: metadata.detection_count,
+            last_detection: metadata.last_detection,
+            days_since_last_detection: metadata.last_detection.map(|last| (now - last).num_days()),
+            detections_per_day: metadata.detection_count as f64 / days_tracked,
+            true_positives: feedback.true_positives,
+            false_positives: feedback.false_positives,
+            false_positive_rate: metadata.false_positive_rate,
+            performance_score: metadata.performance_score,
+            effectiveness_rating: metadata.effectiveness_rating,
+        }
+    }
+
+    fn csv_row(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        [
+            self.rule_id.clone(),
+            self.rule_name.clone(),
+            self.severity.clone(),
+            self.source.clone(),
+            self.tags.join(";"),
+            self.detection_count.to_string(),
+            optional(self.last_detection.map(|time| time.to_rfc3339())),
+            optional(self.days_since_last_detection.map(|days| days.to_string())),
+            format!("{:.4}", self.detections_per_day),
+            self.true_positives.to_string(),
+            self.false_positives.to_string(),
+            format!("{:.4}", self.false_positive_rate),
+            self.performance_score.to_string(),
+            self.effectiveness_rating.clone(),
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+/// Rejects a rule whose content fails the YARA structure check, naming the rule.
+fn check_rule_content(rule: &YaraRule) -> Result<()> {
+    validate_rule_content(&rule.content)
+        .with_context(|| format!("YARA rule {} ({}) failed the YARA structure check", rule.name, rule.id))
+}
+
+/// Writes `RuleMetrics` rows as JSON or CSV as they are read.
+struct MetricsWriter<W: Write> {
+    writer: std::io::BufWriter<W>,
+    format: MetadataFormat,
+    first: bool,
+}
+
+impl<W: Write> MetricsWriter<W> {
+    fn new(writer: W, format: MetadataFormat) -> Result<Self> {
+        let mut writer = std::io::BufWriter::new(writer);
+        match format {
+            MetadataFormat::Json => writer.write_all(b"[")?,
+            MetadataFormat::Csv => writeln!(writer, "{}", RuleMetrics::CSV_HEADER)?,
+        }
+        Ok(Self { writer, format, first: true })
+    }
+
+    fn write(&mut self, metrics: &RuleMetrics) -> Result<()> {
+        match self.format {
+            MetadataFormat::Json => {
+                self.writer.write_all(if self.first { b"\n" } else { b",\n" })?;
+                serde_json::to_writer(&mut self.writer, metrics)?;
+            }
+            MetadataFormat::Csv => writeln!(self.writer, "{}", metrics.csv_row())?,
+        }
+        self.first = false;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        if self.format == MetadataFormat::Json {
+            self.writer.write_all(if self.first { b"]\n" } else { b"\n]\n" })?;
+        }
+        self.writer.flush().context("Failed to write metadata export")?;
+        Ok(())
+    }
+}
+
+/// One exported database record, tagged with its kind in a `type` field.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExportRecord {
+    Rule(YaraRule),
+    RuleMetadata(RuleMetadata),
+    ThreatIntel(ThreatIntelIndicator),
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct TransferCounts {
+    pub rules: u64,
+    pub metadata: u64,
+    pub threat_intel: u64,
+}
+
+/// Writes export records one at a time in either format.
+struct RecordWriter<W: Write> {
+    writer: std::io::BufWriter<W>,
+    format: ExportFormat,
+    first: bool,
+}
+
+impl<W: Write> RecordWriter<W> {
+    fn new(writer: W, format: ExportFormat) -> Result<Self> {
+        let mut writer = std::io::BufWriter::new(writer);
+        if format == ExportFormat::Json {
+            writer.write_all(b"[")?;
+        }
+        Ok(Self { writer, format, first: true })
+    }
+
+    fn write(&mut self, record: &ExportRecord) -> Result<()> {
+        match self.format {
+            ExportFormat::Ndjson => {
+                serde_json::to_writer(&mut self.writer, record)?;
+                self.writer.write_all(b"\n")?;
+            }
+            ExportFormat::Json => {
+                self.writer.write_all(if self.first { b"\n" } else { b",\n" })?;
+                serde_json::to_writer(&mut self.writer, record)?;
+            }
+        }
+        self.first = false;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        if self.format == ExportFormat::Json {
+            self.writer.write_all(b"\n]\n")?;
+        }
+        self.writer.flush().context("Failed to write export")?;
+        Ok(())
+    }
+}
+
+/// Conflict resolution used by [`YaraRulesRedbHook::merge_database`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum MergeStrategy {
+    KeepNewer,
+    KeepExisting,
+    KeepIncoming,
+}
+
+impl std::str::FromStr for MergeStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "newer" => Ok(MergeStrategy::KeepNewer),
+            "existing" => Ok(MergeStrategy::KeepExisting),
+            "incoming" => Ok(MergeStrategy::KeepIncoming),
+            other => Err(anyhow::anyhow!(
+                "Invalid merge strategy '{}': expected newer, existing or incoming", other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct MergeCounts {
+    pub added: u64,
+    pub replaced: u64,
+    pub kept_existing: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MergeReport {
+    pub rules: MergeCounts,
+    pub metadata: MergeCounts,
+    pub threat_intel: MergeCounts,
+}
+
+fn merge_table(
+    source: &redb::ReadTransaction,
+    target: &redb::WriteTransaction,
+    definition: TableDefinition<&str, &[u8]>,
+    strategy: MergeStrategy,
+    incoming_is_newer: impl Fn(&[u8], &[u8]) -> Result<bool>,
+) -> Result<MergeCounts> {
+    let mut counts = MergeCounts::default();
+
+    let source_table = match source.open_table(definition) {
+        Ok(table) => table,
+        Err(redb::TableError::TableDoesNotExist(_)) => return Ok(counts),
+        Err(e) => return Err(e.into()),
+    };
+    let mut target_table = target.open_table(definition)?;
+
+    for result in source_table.iter()? {
+        let (key, incoming) = result?;
+        let existing = target_table.get(key.value())?.map(|value| value.value().to_vec());
+
+        let replace = match (&existing, strategy) {
+            (None, _) => {
+                counts.added += 1;
+                true
+            }
+            (Some(_), MergeStrategy::KeepIncoming) => true,
+            (Some(_), MergeStrategy::KeepExisting) => false,
+            (Some(existing), MergeStrategy::KeepNewer) => incoming_is_newer(existing, incoming.value())?,
+        };
+
+        if replace {
+            if existing.is_some() {
+                counts.replaced += 1;
+            }
+            target_table.insert(key.value(), incoming.value())?;
+        } else {
+            counts.kept_existing += 1;
+        }
+    }
+
+    Ok(counts)
+}
+
+/// Merges two records of the same indicator type and value.
+fn merge_indicators(mut existing: ThreatIntelIndicator, incoming: &ThreatIntelIndicator) -> ThreatIntelIndicator {
+    existing.confidence = existing.confidence.max(incoming.confidence);
+    existing.threat_score = existing.threat_score.max(incoming.threat_score);
+    existing.first_seen = existing.first_seen.min(incoming.first_seen);
+    existing.last_seen = existing.last_seen.max(incoming.last_seen);
+    existing.quantum_resistant = existing.quantum_resistant || incoming.quantum_resistant;
+
+    for (target, source) in [
+        (&mut existing.source_feeds, &incoming.source_feeds),
+        (&mut existing.associated_campaigns, &incoming.associated_campaigns),
+        (&mut existing.mitre_mapping, &incoming.mitre_mapping),
+    ] {
+        for item in source {
+            if !target.contains(item) {
+                target.push(item.clone());
+            }
+        }
+    }
+
+    existing
+}
+
+/// Frame magic number every zstd-compressed blob starts with. Plain blobs
+/// are UTF-8, which can't start with these bytes, so each blob records on
+/// its own whether it is compressed.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// The blob table a rule body is written to, and whether to compress it.
+struct BlobWriter<'a, 't> {
+    table: &'a mut redb::Table<'t, &'static str, &'static [u8]>,
+    compress: bool,
+}
+
+/// Serializes a rule for the rules table. With `blobs`, a body matching its
+/// hash is written to the blob table (compressed when that is smaller and
+/// compression is on) and the record keeps an empty `content` that
+/// `decode_rule` resolves through `hash`.
+fn encode_rule(rule: &YaraRule, blobs: Option<BlobWriter>) -> Result<Vec<u8>> {
+    let Some(blobs) = blobs.filter(|_| !rule.content.is_empty() && content_hash(&rule.content) == rule.hash) else {
+        return bincode::serialize(rule).context("Failed to serialize YARA rule");
+    };
+
+    if blobs.table.get(rule.hash.as_str())?.is_none() {
+        let plain = rule.content.as_bytes();
+        let compressed = blobs.compress.then(|| compress_blob(plain)).transpose()?
+            .filter(|compressed| compressed.len() < plain.len());
+        blobs.table.insert(rule.hash.as_str(), compressed.as_deref().unwrap_or(plain))
+            .context("Failed to insert rule blob")?;
+    }
+
+    let record = YaraRule { content: String::new(), ..rule.clone() };
+    bincode::serialize(&record).context("Failed to serialize YARA rule")
+}
+
+fn compress_blob(plain: &[u8]) -> Result<Vec<u8>> {
+    zstd::bulk::compress(plain, zstd::DEFAULT_COMPRESSION_LEVEL)
+        .context("Failed to compress rule blob")
+}
+
+fn is_compressed(blob: &[u8]) -> bool {
+    blob.starts_with(&ZSTD_MAGIC)
+}
+
+/// Rule text stored in a blob, decompressing it if needed.
+fn blob_content(blob: &[u8]) -> Result<String> {
+    let plain = if is_compressed(blob) {
+        zstd::decode_all(blob).context("Failed to decompress rule blob")?
+    } else {
+        blob.to_vec()
+    };
+    String::from_utf8(plain).context("Rule blob is not valid UTF-8")
+}
+
+/// Deserializes a rules-table record as stored, falling back to the layout
+/// from before `scan_tier` (which then defaults to standard). An old record
+/// runs out of bytes where the new field would start, so it never decodes
+/// as the current layout by accident.
+fn deserialize_rule(data: &[u8]) -> Result<YaraRule> {
+    bincode::deserialize::<YaraRule>(data)
+        .or_else(|e| bincode::deserialize::<YaraRuleV1>(data).map(YaraRule::from).map_err(|_| e))
+        .context("Failed to deserialize YARA rule")
+}
+
+/// Deserializes a rules-table record, resolving a blob-backed body.
+fn decode_rule(data: &[u8], blobs: &impl ReadableTable<&'static str, &'static [u8]>) -> Result<YaraRule> {
+    let mut rule = deserialize_rule(data)?;
+
+    if rule.content.is_empty() {
+        if let Some(blob) = blobs.get(rule.hash.as_str())? {
+            rule.content = blob_content(blob.value())?;
+        }
+    }
+    Ok(rule)
+}
+
+/// Who audit entries are attributed to: `PYRO_ACTOR` when set, otherwise the
+/// login name.
+/// The stored rule other than `own_id` whose content declares `name`
+/// according to the name index, skipping stale entries.
+fn name_holder(
+    name: &str,
+    own_id: &str,
+    names: &impl ReadableTable<&'static str, &'static str>,
+    rules: &impl ReadableTable<&'static str, &'static [u8]>,
+    blobs: &impl ReadableTable<&'static str, &'static [u8]>,
+) -> Result<Option<YaraRule>> {
+    let Some(holder_id) = names.get(name)?.map(|id| id.value().to_string()) else { return Ok(None) };
+    if holder_id == own_id {
+        return Ok(None);
+    }
+    let Some(data) = rules.get(holder_id.as_str())? else { return Ok(None) };
+    let Ok(holder) = decode_rule(data.value(), blobs) else { return Ok(None) };
+    Ok(declared_rule_names(&holder.content).iter().any(|declared| declared == name).then_some(holder))
+}
+
+/// Every YARA rule name declared in stored rule content, with the declaring
+/// rules as (created_at, id), oldest first. Unreadable rules are left out;
+/// `find_corrupt_records` reports them.
+#[allow(clippy::type_complexity)]
+fn rule_declarations(
+    rules: &impl ReadableTable<&'static str, &'static [u8]>,
+    blobs: &impl ReadableTable<&'static str, &'static [u8]>,
+) -> Result<BTreeMap<String, Vec<(chrono::DateTime<chrono::Utc>, String)>>> {
+    let mut declarations: BTreeMap<String, Vec<_>> = BTreeMap::new();
+    for result in rules.iter()? {
+        let (_key, value) = result?;
+        let Ok(rule) = decode_rule(value.value(), blobs) else { continue };
+        for name in declared_rule_names(&rule.content) {
+            declarations.entry(name).or_default().push((rule.created_at, rule.id.clone()));
+        }
+    }
+    for holders in declarations.values_mut() {
+        holders.sort();
+        holders.dedup();
+    }
+    Ok(declarations)
+}
+
+/// Rebuilds the rule name index from the stored rules inside `write_txn`.
+/// Where several rules declare a name the oldest gets the entry.
+fn rebuild_name_index(write_txn: &redb::WriteTransaction) -> Result<()> {
+    let declarations = {
+        let rules_table = write_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let blobs = write_txn.open_table(RULE_BLOBS_TABLE)
+            .context("Failed to open rule blobs table")?;
+        rule_declarations(&rules_table, &blobs)?
+    };
+
+    let mut names = write_txn.open_table(RULE_NAMES_TABLE)
+        .context("Failed to open rule names table")?;
+    names.retain(|_, _| false)
+        .context("Failed to clear rule names table")?;
+    for (name, holders) in &declarations {
+        names.insert(name.as_str(), holders[0].1.as_str())
+            .context("Failed to index YARA rule name")?;
+    }
+
+    let conflicts = declarations.values().filter(|holders| holders.len() > 1).count();
+    if conflicts > 0 {
+        log::warn!("{} YARA rule names are declared by more than one rule; see `db name-conflicts`", conflicts);
+    }
+    Ok(())
+}
+
+/// Points the tag index at rule `id` for each of `tags`.
+fn index_tags(index: &mut redb::MultimapTable<&'static str, &'static str>, id: &str, tags: &[String]) -> Result<()> {
+    for tag in tags {
+        index.insert(tag.to_lowercase().as_str(), id)
+            .context("Failed to index rule tag")?;
+    }
+    Ok(())
+}
+
+/// Drops rule `id` from the tag index entries of `tags`.
+fn unindex_tags(index: &mut redb::MultimapTable<&'static str, &'static str>, id: &str, tags: &[String]) -> Result<()> {
+    for tag in tags {
+        index.remove(tag.to_lowercase().as_str(), id)
+            .context("Failed to remove rule tag from index")?;
+    }
+    Ok(())
+}
+
+/// Rebuilds the rule tag index from the stored rules inside `write_txn`.
+/// Unreadable records are left out.
+fn rebuild_tag_index(write_txn: &redb::WriteTransaction) -> Result<()> {
+    let rules: Vec<(String, Vec<String>)> = {
+        let rules_table = write_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let mut rules = Vec::new();
+        for result in rules_table.iter()? {
+            let (key, value) = result?;
+            let Ok(rule) = deserialize_rule(value.value()) else { continue };
+            rules.push((key.value().to_string(), rule.tags));
+        }
+        rules
+    };
+
+    write_txn.delete_multimap_table(RULE_TAGS_TABLE)
+        .context("Failed to clear rule tag index")?;
+    let mut tags = write_txn.open_multimap_table(RULE_TAGS_TABLE)
+        .context("Failed to open rule tag index")?;
+    for (id, rule_tags) in &rules {
+        index_tags(&mut tags, id, rule_tags)?;
+    }
+    Ok(())
+}
+
+/// The MITRE index key for `mitre_id`: trimmed and lowercased.
+fn mitre_index_key(mitre_id: &str) -> String {
+    mitre_id.trim().to_lowercase()
+}
+
+/// Points a MITRE index at rule `id` for each of `mitre_ids`.
+fn index_mitre_ids(index: &mut redb::MultimapTable<&'static str, &'static str>, id: &str, mitre_ids: &[String]) -> Result<()> {
+    for mitre_id in mitre_ids {
+        index.insert(mitre_index_key(mitre_id).as_str(), id)
+            .context("Failed to index rule MITRE id")?;
+    }
+    Ok(())
+}
+
+/// Drops rule `id` from the MITRE index entries of `mitre_ids`.
+fn unindex_mitre_ids(index: &mut redb::MultimapTable<&'static str, &'static str>, id: &str, mitre_ids: &[String]) -> Result<()> {
+    for mitre_id in mitre_ids {
+        index.remove(mitre_index_key(mitre_id).as_str(), id)
+            .context("Failed to remove rule MITRE id from index")?;
+    }
+    Ok(())
+}
+
+/// Rebuilds the rule technique and tactic indexes from the stored rules
+/// inside `write_txn`. Unreadable records are left out.
+fn rebuild_mitre_index(write_txn: &redb::WriteTransaction) -> Result<()> {
+    let rules: Vec<(String, Vec<String>, Vec<String>)> = {
+        let rules_table = write_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let mut rules = Vec::new();
+        for result in rules_table.iter()? {
+            let (key, value) = result?;
+            let Ok(rule) = deserialize_rule(value.value()) else { continue };
+            rules.push((key.value().to_string(), rule.mitre_techniques, rule.mitre_tactics));
+        }
+        rules
+    };
+
+    write_txn.delete_multimap_table(RULE_TECHNIQUES_TABLE)
+        .context("Failed to clear rule technique index")?;
+    write_txn.delete_multimap_table(RULE_TACTICS_TABLE)
+        .context("Failed to clear rule tactic index")?;
+    let mut techniques = write_txn.open_multimap_table(RULE_TECHNIQUES_TABLE)
+        .context("Failed to open rule technique index")?;
+    let mut tactics = write_txn.open_multimap_table(RULE_TACTICS_TABLE)
+        .context("Failed to open rule tactic index")?;
+    for (id, rule_techniques, rule_tactics) in &rules {
+        index_mitre_ids(&mut techniques, id, rule_techniques)?;
+        index_mitre_ids(&mut tactics, id, rule_tactics)?;
+    }
+    Ok(())
+}
+
+/// Rebuilds the rule hash index from the stored rules inside `write_txn`.
+/// Unreadable records are left out.
+fn rebuild_hash_index(write_txn: &redb::WriteTransaction) -> Result<()> {
+    let rules: Vec<(String, String)> = {
+        let rules_table = write_txn.open_table(YARA_RULES_TABLE)
+            .context("Failed to open YARA rules table")?;
+        let mut rules = Vec::new();
+        for result in rules_table.iter()? {
+            let (key, value) = result?;
+            let Ok(rule) = deserialize_rule(value.value()) else { continue };
+            rules.push((key.value().to_string(), rule.hash));
+        }
+        rules
+    };
+
+    write_txn.delete_multimap_table(RULE_HASHES_TABLE)
+        .context("Failed to clear rule hash index")?;
+    let mut hashes = write_txn.open_multimap_table(RULE_HASHES_TABLE)
+        .context("Failed to open rule hash index")?;
+    for (id, hash) in &rules {
+        hashes.insert(hash.as_str(), id.as_str())
+            .context("Failed to index rule hash")?;
+    }
+    Ok(())
+}
+
+/// Points the value index at `indicator`'s record.
+fn index_intel_value(write_txn: &redb::WriteTransaction, indicator: &ThreatIntelIndicator) -> Result<()> {
+    write_txn.open_multimap_table(INTEL_VALUES_TABLE)
+        .context("Failed to open threat intel value index")?
+        .insert(indicator.value.as_str(), indicator.id.as_str())
+        .context("Failed to index threat intel value")?;
+    Ok(())
+}
+
+/// Rebuilds the threat intel value index from the stored indicators inside
+/// `write_txn`. Unreadable records are left out.
+fn rebuild_intel_value_index(write_txn: &redb::WriteTransaction) -> Result<()> {
+    let entries: Vec<(String, String)> = {
+        let intel_table = write_txn.open_table(THREAT_INTEL_TABLE)
+            .context("Failed to open threat intel table")?;
+        let mut entries = Vec::new();
+        for result in intel_table.iter()? {
+            let (key, value) = result?;
+            let Ok(indicator) = bincode::deserialize::<ThreatIntelIndicator>(value.value()) else { continue };
+            entries.push((indicator.value, key.value().to_string()));
+        }
+        entries
+    };
+
+    write_txn.delete_multimap_table(INTEL_VALUES_TABLE)
+        .context("Failed to clear threat intel value index")?;
+    let mut values = write_txn.open_multimap_table(INTEL_VALUES_TABLE)
+        .context("Failed to open threat intel value index")?;
+    for (value, id) in &entries {
+        values.insert(value.as_str(), id.as_str())
+            .context("Failed to index threat intel value")?;
+    }
+    Ok(())
+}
+
+fn audit_actor() -> String {
+    ["PYRO_ACTOR", "USER", "USERNAME"].iter()
+        .find_map(|var| std::env::var(var).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Hash stored in `YaraRule::hash` for a rule's content.
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", md5::compute(content.as_bytes()))
+}
+
+// Hook integration functions
+pub async fn initialize_yara_rules_hook(db_path: &str) -> Result<YaraRulesRedbHook> {
+    YaraRulesRedbHook::new(db_path).await.with_context(|| {
+        PyroError::new(ErrorCategory::Database, format!("Failed to open database: {}", db_path))
+    })
+}
+
+/// Outcome of syncing one rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncStatus {
+    Added,
+    Updated,
+    /// Already stored with the same content.
+    Skipped,
+    Failed,
+    /// Deleted because its source file is gone (`prune_missing`).
+    Pruned,
+}
+
+impl SyncStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncStatus::Added => "added",
+            SyncStatus::Updated => "updated",
+            SyncStatus::Skipped => "skipped",
+            SyncStatus::Failed => "failed",
+            SyncStatus::Pruned => "pruned",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncEntry {
+    pub name: String,
+    pub source: String,
+    pub hash: Option<String>,
+    pub status: SyncStatus,
+    pub error: Option<String>,
+}
+
+/// Per-rule manifest of a directory sync.
+#[derive(Debug, Default, Serialize)]
+pub struct SyncReport {
+    pub directory: String,
+    pub entries: Vec<SyncEntry>,
+    /// Why the directory couldn't be synced, in a `sync_directories` run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Reports of a `sync_directories` run, one per directory in the order
+/// given.
+#[derive(Debug, Default, Serialize)]
+pub struct MultiSyncReport {
+    pub directories: Vec<SyncReport>,
+}
+
+impl MultiSyncReport {
+    pub fn count(&self, status: SyncStatus) -> u64 {
+        self.directories.iter().map(|report| report.count(status)).sum()
+    }
+
+    /// Directories that couldn't be synced at all.
+    pub fn failed_directories(&self) -> impl Iterator<Item = &SyncReport> {
+        self.directories.iter().filter(|report| report.error.is_some())
+    }
+}
+
+impl SyncReport {
+    /// Rules added or updated.
+    pub fn synced_count(&self) -> u64 {
+        self.entries.iter()
+            .filter(|entry| matches!(entry.status, SyncStatus::Added | SyncStatus::Updated))
+            .count() as u64
+    }
+
+    pub fn count(&self, status: SyncStatus) -> u64 {
+        self.entries.iter().filter(|entry| entry.status == status).count() as u64
+    }
+}
+
+/// Per-sync settings for `sync_yara_rules_from_directory`.
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    /// Author recorded on synced rules.
+    pub default_author: String,
+    /// Severity of rules with neither a `severity` meta entry nor a
+    /// `severity_from_tags` match.
+    pub default_severity: Severity,
+    /// After syncing, delete stored rules whose source file under the
+    /// directory no longer exists.
+    pub prune_missing: bool,
+    /// Extra tag recorded on synced rules; `sync_directories` sets it to
+    /// `root:<directory name>`.
+    pub root_tag: Option<String>,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self {
+            default_author: "Auto-imported".to_string(),
+            default_severity: Severity::Medium,
+            prune_missing: false,
+            root_tag: None,
+        }
+    }
+}
+
+/// Deletes stored rules whose source is a file directly in `rules_directory`
+/// that no longer exists, and returns them. With `dry_run` nothing is
+/// written and the rules that would be deleted are returned.
+pub async fn prune_missing_rules(hook: &YaraRulesRedbHook, rules_directory: &str, dry_run: bool) -> Result<Vec<DeletedRule>> {
+    hook.delete_rules_where(|rule| {
+        let source = Path::new(&rule.source);
+        source.parent() == Some(Path::new(rules_directory)) && !source.exists()
+    }, dry_run).await
+}
+
+/// Metadata kept next to a rule file as `<stem>.meta.yaml` (or `.meta.yml`,
+/// `.meta.json`). Each field given overrides what the sync would otherwise
+/// record for every rule in the file; tags are added to the rule's own.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RuleSidecar {
+    author: Option<String>,
+    description: Option<String>,
+    severity: Option<String>,
+    tags: Vec<String>,
+    #[serde(alias = "techniques")]
+    mitre_techniques: Vec<String>,
+    #[serde(alias = "tactics")]
+    mitre_tactics: Vec<String>,
+}
+
+impl RuleSidecar {
+    /// Reads the metadata file for `rule_path`, if there is one. A file that
+    /// can't be read or parsed is logged and ignored, as is an unknown
+    /// severity, so the rule is still synced with the usual defaults.
+    async fn load(rule_path: &Path) -> Option<Self> {
+        let stem = rule_path.file_stem()?.to_str()?;
+        for extension in ["meta.yaml", "meta.yml", "meta.json"] {
+            let path = rule_path.with_file_name(format!("{}.{}", stem, extension));
+            let content = match fs::read_to_string(&path).await {
+                Ok(content) => content,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    log::warn!("Ignoring unreadable rule metadata {}: {}", path.display(), e);
+                    return None;
+                }
+            };
+
+            let parsed: Result<Self> = if extension == "meta.json" {
+                serde_json::from_str(&content).map_err(Into::into)
+            } else {
+                serde_yaml::from_str(&content).map_err(Into::into)
+            };
+            let mut sidecar = match parsed {
+                Ok(sidecar) => sidecar,
+                Err(e) => {
+                    log::warn!("Ignoring malformed rule metadata {}: {}", path.display(), e);
+                    return None;
+                }
+            };
+            if let Some(Err(e)) = sidecar.severity.as_deref().map(str::parse::<Severity>) {
+                log::warn!("Ignoring severity in rule metadata {}: {}", path.display(), e);
+                sidecar.severity = None;
+            }
+            return Some(sidecar);
+        }
+        None
+    }
+
+    fn apply(&self, rule: &mut YaraRule) {
+        if let Some(author) = &self.author {
+            rule.author = author.clone();
+        }
+        if let Some(description) = &self.description {
+            rule.description = description.clone();
+        }
+        if let Some(severity) = self.severity.as_deref().and_then(|severity| severity.parse::<Severity>().ok()) {
+            rule.severity = severity.to_string();
+        }
+        for tag in &self.tags {
+            if !rule.tags.contains(tag) {
+                rule.tags.push(tag.clone());
+            }
+        }
+        if !self.mitre_techniques.is_empty() {
+            rule.mitre_techniques = self.mitre_techniques.clone();
+        }
+        if !self.mitre_tactics.is_empty() {
+            rule.mitre_tactics = self.mitre_tactics.clone();
+        }
+    }
+
+    /// Whether any field a metadata file can set differs between the rules.
+    fn fields_differ(stored: &YaraRule, rule: &YaraRule) -> bool {
+        stored.author != rule.author
+            || stored.description != rule.description
+            || stored.severity != rule.severity
+            || stored.tags != rule.tags
+            || stored.mitre_techniques != rule.mitre_techniques
+            || stored.mitre_tactics != rule.mitre_tactics
+    }
+}
+
+/// Stores every rule in `.yar`/`.yara` files in `rules_directory`.
+///
+/// Rules are matched to stored ones by source file and name: unchanged rules
+/// are skipped and changed ones updated in place, keeping their id. Files
+/// that can't be read are recorded as failed and the sync carries on.
+///
+/// A rule matching no stored one is then looked up by content hash, so it
+/// is never stored twice. If the stored rule's source file is gone, the file
+/// was moved or renamed and only the stored rule's source, `updated_at` and
+/// name (which follows the file for single-rule files, and keeps the rule
+/// matched on later syncs) are updated; otherwise the new file is a copy
+/// and is skipped.
+///
+/// MITRE tactics and techniques, threat actors and malware families come from
+/// `mitre_tactic`, `mitre_technique`, `threat_actor` and `malware_family`
+/// entries (or their plurals) in each rule's `meta:` section, which may be
+/// repeated or hold comma-separated lists.
+///
+/// A `<stem>.meta.yaml`/`.meta.json` file next to a rule file supplies its
+/// author, description, severity, tags and MITRE ids (see `RuleSidecar`);
+/// editing it updates the file's rules on the next sync.
+///
+/// With `prune_missing`, stored rules whose source is a file directly in
+/// `rules_directory` that no longer exists are then deleted in one
+/// transaction (see `prune_missing_rules`). Rules imported from elsewhere,
+/// and rules whose file still exists, are never pruned.
+pub async fn sync_yara_rules_from_directory(
+    hook: &YaraRulesRedbHook,
+    rules_directory: &str,
+    options: &SyncOptions,
+) -> Result<SyncReport> {
+    let mut report = SyncReport {
+        directory: rules_directory.to_string(),
+        ..Default::default()
+    };
+    let mut existing: std::collections::HashMap<(String, String), YaraRule> = hook.list_yara_rules().await?
+        .into_iter()
+        .map(|rule| ((rule.source.clone(), rule.name.clone()), rule))
+        .collect();
+
+    let mut entries = fs::read_dir(rules_directory).await
+        .context("Failed to read rules directory")?;
+
+    // Rules to store, and the index of each one's report entry
+    let mut pending: Vec<YaraRule> = Vec::new();
+    let mut pending_entries: Vec<usize> = Vec::new();
+    // Stored rules matched to a file in this sync, and hashes of new rules
+    let mut claimed_ids: BTreeSet<String> = BTreeSet::new();
+    let mut added_hashes: BTreeSet<String> = BTreeSet::new();
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        
+        if path.extension().and_then(|s| s.to_str()) == Some("yar") ||
+           path.extension().and_then(|s| s.to_str()) == Some("yara") {
+            
+            let source = path.to_string_lossy().to_string();
+            let file_stem = path.file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown");
+
+            let content = match fs::read_to_string(&path).await {
+                Ok(content) => content,
+                Err(e) => {
+                    log::warn!("Failed to read YARA rule file {}: {}", path.display(), e);
+                    report.entries.push(SyncEntry {
+                        name: file_stem.to_string(),
+                        source,
+                        hash: None,
+                        status: SyncStatus::Failed,
+                        error: Some(e.to_string()),
+                    });
+                    continue;
+                }
+            };
+
+            let sidecar = RuleSidecar::load(&path).await;
+
+            // Files with several rule blocks are stored one rule per block
+            let parsed = split_rules(&content);
+            let rules: Vec<(String, String, Option<&RuleBlock>)> = match &parsed {
+                Some(source) if source.rules.len() > 1 => source.rules.iter()
+                    .map(|block| (block.name.clone(), block.with_imports(&source.imports), Some(block)))
+                    .collect(),
+                Some(source) => vec![(file_stem.to_string(), content, source.rules.first())],
+                None => vec![(file_stem.to_string(), content, None)],
+            };
+            if rules.len() > 1 {
+                log::debug!("Split {} into {} rules", path.display(), rules.len());
+            }
+
+            for (name, content, block) in rules {
+                let mut rule = imported_rule(&path, name, content, block, &hook.severity_from_tags, options, hook.now());
+                if let Some(sidecar) = &sidecar {
+                    sidecar.apply(&mut rule);
+                }
+                let status = match existing.remove(&(source.clone(), rule.name.clone())) {
+                    // An edited metadata file changes the rule without touching its content
+                    Some(stored) if stored.hash == rule.hash
+                        && !(sidecar.is_some() && RuleSidecar::fields_differ(&stored, &rule))
+                        && !meta_lists_differ(&stored, &rule) => {
+                        claimed_ids.insert(stored.id);
+                        SyncStatus::Skipped
+                    }
+                    Some(stored) => {
+                        claimed_ids.insert(stored.id.clone());
+                        rule.id = stored.id;
+                        rule.created_at = stored.created_at;
+                        SyncStatus::Updated
+                    }
+                    None => {
+                        let holders = hook.find_rules_by_hash(&rule.hash).await?;
+                        // A stored rule with this content takes this file as its
+                        // source instead of a duplicate being added, preferring
+                        // one whose file is gone (moved or renamed). Rules another
+                        // file claimed in this sync keep their source, so a copy
+                        // next to its original doesn't move the rule back and forth.
+                        let unclaimed = || holders.iter().filter(|holder| !claimed_ids.contains(&holder.id));
+                        let holder = unclaimed().find(|holder| !Path::new(&holder.source).exists())
+                            .or_else(|| unclaimed().next());
+                        if let Some(holder) = holder {
+                            if Path::new(&holder.source).exists() {
+                                log::info!("YARA rule {} ({}) from {} also found in {}, which becomes its source",
+                                           holder.name, holder.id, holder.source, source);
+                            } else {
+                                log::info!("YARA rule {} ({}) moved from {} to {}", holder.name, holder.id, holder.source, source);
+                            }
+                            claimed_ids.insert(holder.id.clone());
+                            let mut tags = holder.tags.clone();
+                            tags.extend(options.root_tag.clone().filter(|tag| !holder.tags.contains(tag)));
+                            rule = YaraRule {
+                                name: rule.name,
+                                source: source.clone(),
+                                tags,
+                                updated_at: hook.now(),
+                                ..holder.clone()
+                            };
+                            SyncStatus::Updated
+                        } else if let Some(holder) = holders.first() {
+                            log::info!("Skipping {} in {}: same content as stored YARA rule {} ({}) from {}",
+                                       rule.name, source, holder.name, holder.id, holder.source);
+                            SyncStatus::Skipped
+                        } else if !added_hashes.insert(rule.hash.clone()) {
+                            log::info!("Skipping {} in {}: same content as a rule added earlier in this sync", rule.name, source);
+                            SyncStatus::Skipped
+                        } else {
+                            SyncStatus::Added
+                        }
+                    }
+                };
+
+                report.entries.push(SyncEntry {
+                    name: rule.name.clone(),
+                    source: source.clone(),
+                    hash: Some(rule.hash.clone()),
+                    status,
+                    error: None,
+                });
+                if status != SyncStatus::Skipped {
+                    pending_entries.push(report.entries.len() - 1);
+                    pending.push(rule);
+                }
+            }
+        }
+    }
+
+    if let Err(e) = hook.store_yara_rules(&pending).await {
+        // Store the rules one at a time instead, so only the failing ones
+        // are reported as failed
+        log::warn!("Failed to store {} YARA rules at once, storing them one by one: {:#}", pending.len(), e);
+        for (rule, index) in pending.iter().zip(pending_entries) {
+            if let Err(e) = hook.store_yara_rule(rule).await {
+                log::warn!("Failed to store YARA rule {}: {}", rule.name, e);
+                let entry = &mut report.entries[index];
+                entry.status = SyncStatus::Failed;
+                entry.error = Some(format!("{:#}", e));
+            }
+        }
+    }
+
+    if options.prune_missing {
+        for rule in prune_missing_rules(hook, rules_directory, false).await? {
+            report.entries.push(SyncEntry {
+                name: rule.name,
+                source: rule.source,
+                hash: None,
+                status: SyncStatus::Pruned,
+                error: None,
+            });
+        }
+    }
+    
+    log::info!("Synced YARA rules from directory {}: {} added, {} updated, {} unchanged, {} failed, {} pruned",
+              rules_directory, report.count(SyncStatus::Added), report.count(SyncStatus::Updated),
+              report.count(SyncStatus::Skipped), report.count(SyncStatus::Failed), report.count(SyncStatus::Pruned));
+    Ok(report)
+}
+
+/// Syncs several rule directories as `sync_yara_rules_from_directory` does,
+/// tagging each directory's rules with `root:<directory name>`. A directory
+/// that fails is recorded with its error and the others are still synced.
+/// As with severities, the tag is recorded when a rule is added or its
+/// content changes, and added to the stored rule when another directory
+/// holds a copy of it, which then becomes the rule's source.
+pub async fn sync_directories(
+    hook: &YaraRulesRedbHook,
+    rules_directories: &[String],
+    options: &SyncOptions,
+) -> MultiSyncReport {
+    let mut combined = MultiSyncReport::default();
+    for directory in rules_directories {
+        let options = SyncOptions {
+            root_tag: Some(format!("root:{}", root_name(directory))),
+            ..options.clone()
+        };
+        let report = match sync_yara_rules_from_directory(hook, directory, &options).await {
+            Ok(report) => report,
+            Err(e) => {
+                log::warn!("Failed to sync YARA rules from directory {}: {:#}", directory, e);
+                SyncReport {
+                    directory: directory.clone(),
+                    error: Some(format!("{:#}", e)),
+                    ..Default::default()
+                }
+            }
+        };
+        combined.directories.push(report);
+    }
+    combined
+}
+
+/// Last component of a rules directory, e.g. `vendor` for `rules/vendor/`.
+fn root_name(directory: &str) -> String {
+    Path::new(directory).file_name()
+        .map_or_else(|| directory.to_string(), |name| name.to_string_lossy().to_string())
+}
+
+fn oldest_first(indicators: Vec<(String, ThreatIntelIndicator)>) -> Vec<ThreatIntelIndicator> {
+    let mut indicators: Vec<ThreatIntelIndicator> = indicators.into_iter().map(|(_, indicator)| indicator).collect();
+    indicators.sort_by_key(|indicator| indicator.last_seen);
+    indicators
+}
+
+fn imported_rule(
+    path: &Path,
+    name: String,
+    content: String,
+    block: Option<&RuleBlock>,
+    severity_from_tags: &BTreeMap<String, Severity>,
+    options: &SyncOptions,
+    now: chrono::DateTime<chrono::Utc>,
+) -> YaraRule {
+    let hash = content_hash(&content);
+    let mut tags = vec!["auto-imported".to_string()];
+    if let Some(block) = block {
+        tags.extend(block.tags.iter().cloned());
+        // YARA tags must be identifiers, so rule sets often list names like
+        // `critical-ransomware` in a `tags` meta entry instead
+        if let Some(meta_tags) = block.meta_value("tags") {
+            tags.extend(meta_tags.split([',', ' ']).filter(|tag| !tag.is_empty()).map(str::to_string));
+        }
+    }
+    let severity = imported_severity(&name, block, &tags, severity_from_tags)
+        .unwrap_or(options.default_severity);
+    tags.extend(options.root_tag.clone());
+
+    YaraRule {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        content,
+        author: options.default_author.clone(),
+        description: format!("Imported from {}", path.display()),
+        tags,
+        severity: severity.to_string(),
+        created_at: now,
+        updated_at: now,
+        version: "1.0".to_string(),
+        hash,
+        source: path.to_string_lossy().to_string(),
+        mitre_tactics: meta_list(block, &["mitre_tactic", "mitre_tactics"]),
+        mitre_techniques: meta_list(block, &["mitre_technique", "mitre_techniques"]),
+        threat_actors: meta_list(block, &["threat_actor", "threat_actors"]),
+        malware_families: meta_list(block, &["malware_family", "malware_families"]),
+        scan_tier: block.and_then(|block| block.meta_value("scan_tier"))
+            .and_then(|tier| tier.parse().ok())
+            .unwrap_or_default(),
+    }
+}
+
+/// Values of the `meta:` entries named by `keys`, with comma-separated lists
+/// split and repeats dropped, e.g. `mitre_technique = "T1059, T1105"`.
+fn meta_list(block: Option<&RuleBlock>, keys: &[&str]) -> Vec<String> {
+    let mut values: Vec<String> = Vec::new();
+    let Some(block) = block else { return values };
+    for key in keys {
+        for value in block.meta_values(key) {
+            for item in value.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+                if !values.iter().any(|known| known == item) {
+                    values.push(item.to_string());
+                }
+            }
+        }
+    }
+    values
+}
+
+/// Whether `rule` parsed MITRE ids, actors or families from its `meta:`
+/// section that `stored` lacks, as rules synced before these were parsed do.
+fn meta_lists_differ(stored: &YaraRule, rule: &YaraRule) -> bool {
+    let differs = |parsed: &Vec<String>, stored: &Vec<String>| !parsed.is_empty() && parsed != stored;
+    differs(&rule.mitre_techniques, &stored.mitre_techniques)
+        || differs(&rule.mitre_tactics, &stored.mitre_tactics)
+        || differs(&rule.threat_actors, &stored.threat_actors)
+        || differs(&rule.malware_families, &stored.malware_families)
+}
+
+/// Severity of a synced rule: its `meta:` `severity` when that names a known
+/// level, otherwise the most severe `severity_from_tags` match among its
+/// tags. `None` leaves it to the sync's default severity.
+fn imported_severity(
+    name: &str,
+    block: Option<&RuleBlock>,
+    tags: &[String],
+    severity_from_tags: &BTreeMap<String, Severity>,
+) -> Option<Severity> {
+    if let Some(declared) = block.and_then(|block| block.meta_value("severity")) {
+        match declared.parse() {
+            Ok(severity) => return Some(severity),
+            Err(_) => log::warn!("Ignoring unknown severity {:?} in rule {}", declared, name),
+        }
+    }
+
+    severity_from_tags.iter()
+        .filter(|(tag, _)| tags.iter().any(|rule_tag| rule_tag.eq_ignore_ascii_case(tag)))
+        .map(|(_, severity)| *severity)
+        .max()
+}
+
+/// Writes every stored rule to `rules_directory` as `<id>.yar`, the inverse of
+/// `sync_yara_rules_from_directory`. Ids are used for file names because rule
+/// names are not unique across imports. With `max_tier`, rules of a more
+/// expensive scan tier are left out.
+pub async fn export_yara_rules_to_directory(
+    hook: &YaraRulesRedbHook,
+    rules_directory: &Path,
+    max_tier: Option<ScanTier>,
+) -> Result<u64> {
+    fs::create_dir_all(rules_directory).await
+        .context("Failed to create rules export directory")?;
+
+    let mut exported_count = 0u64;
+    for rule in hook.list_yara_rules().await? {
+        if max_tier.is_some_and(|max_tier| rule.scan_tier > max_tier) {
+            continue;
+        }
+        let file_name: String = rule.id.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        fs::write(rules_directory.join(format!("{}.yar", file_name)), &rule.content).await
+            .context("Failed to write exported YARA rule")?;
+        exported_count += 1;
+    }
+
+    log::info!("Exported {} YARA rules to directory: {}", exported_count, rules_directory.display());
+    Ok(exported_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{indicator, rule, temp_hook};
+
+    #[tokio::test]
+    async fn stored_rule_reads_back_by_id() {
+        let (_dir, hook) = temp_hook().await;
+        let stored = rule("Stored");
+        hook.store_yara_rule(&stored).await.unwrap();
+
+        let read = hook.get_yara_rule(&stored.id).await.unwrap().unwrap();
+        assert_eq!(read.name, "Stored");
+        assert_eq!(read.content, stored.content);
+        assert!(hook.get_yara_rule("missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn batch_insert_matches_one_at_a_time() {
+        let rules: Vec<YaraRule> = (0..500).map(|i| rule(&format!("Batch_{}", i))).collect();
+
+        let (_batch_dir, batched) = temp_hook().await;
+        assert_eq!(batched.store_yara_rules(&rules).await.unwrap(), 500);
+
+        let (_single_dir, single) = temp_hook().await;
+        for stored in &rules {
+            single.store_yara_rule(stored).await.unwrap();
+        }
+
+        let batched_count = batched.get_database_stats().await.unwrap().yara_rules_count;
+        let single_count = single.get_database_stats().await.unwrap().yara_rules_count;
+        assert_eq!(batched_count, 500);
+        assert_eq!(batched_count, single_count);
+    }
+
+    #[tokio::test]
+    async fn deleting_a_rule_reports_whether_it_existed() {
+        let (_dir, hook) = temp_hook().await;
+        let kept = rule("Kept");
+        let deleted = rule("Deleted");
+        hook.store_yara_rules(&[kept.clone(), deleted.clone()]).await.unwrap();
+        hook.update_rule_metadata(&RuleMetadata::new(&deleted.id)).await.unwrap();
+
+        assert!(hook.delete_yara_rule(&deleted.id).await.unwrap());
+        assert_eq!(hook.get_database_stats().await.unwrap().yara_rules_count, 1);
+        assert!(hook.get_yara_rule(&deleted.id).await.unwrap().is_none());
+        assert!(hook.get_rule_metadata(&deleted.id).await.unwrap().is_none());
+
+        assert!(!hook.delete_yara_rule(&deleted.id).await.unwrap());
+        assert!(!hook.delete_yara_rule("missing").await.unwrap());
+        assert_eq!(hook.get_database_stats().await.unwrap().yara_rules_count, 1);
+        assert!(hook.get_yara_rule(&kept.id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn deleting_an_indicator_reports_whether_it_existed() {
+        let (_dir, hook) = temp_hook().await;
+        hook.store_threat_intel(&indicator("domain", "evil.example", 0.9)).await.unwrap();
+        hook.store_threat_intel(&indicator("ip", "203.0.113.7", 0.9)).await.unwrap();
+
+        assert!(hook.delete_threat_intel(&intel_key("domain", "evil.example")).await.unwrap());
+        assert_eq!(hook.get_database_stats().await.unwrap().threat_intel_count, 1);
+        assert!(hook.get_threat_intel("domain", "evil.example").await.unwrap().is_none());
+
+        assert!(!hook.delete_threat_intel(&intel_key("domain", "evil.example")).await.unwrap());
+        assert!(!hook.delete_threat_intel("missing").await.unwrap());
+        assert_eq!(hook.get_database_stats().await.unwrap().threat_intel_count, 1);
+    }
+
+    #[tokio::test]
+    async fn malformed_rules_are_only_stored_unchecked() {
+        let (_dir, hook) = temp_hook().await;
+        let draft = YaraRule { content: "rule Draft {\n    condition:\n}\n".to_string(), ..rule("Draft") };
+
+        let error = hook.store_yara_rule(&draft).await.unwrap_err();
+        assert!(format!("{:#}", error).contains("YARA rule Draft"), "{:#}", error);
+        assert!(hook.get_yara_rule(&draft.id).await.unwrap().is_none());
+
+        hook.store_yara_rule_unchecked(&draft).await.unwrap();
+        assert!(hook.get_yara_rule(&draft.id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn exact_intel_lookup_finds_one_of_many() {
+        let (_dir, hook) = temp_hook().await;
+        for i in 0..1000 {
+            hook.store_threat_intel(&indicator("domain", &format!("host{}.example", i), 0.5)).await.unwrap();
+        }
+        hook.store_threat_intel(&indicator("url", "http://host42.example/", 0.5)).await.unwrap();
+
+        let found = hook.get_threat_intel_exact("host42.example").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, intel_key("domain", "host42.example"));
+        assert!(hook.get_threat_intel_exact("host1000.example").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn paging_covers_every_rule_once() {
+        let (_dir, hook) = temp_hook().await;
+        let rules: Vec<YaraRule> = (0..25).map(|i| rule(&format!("Paged_{}", i))).collect();
+        hook.store_yara_rules(&rules).await.unwrap();
+
+        let mut seen = BTreeSet::new();
+        let mut sizes = Vec::new();
+        for offset in [0, 10, 20, 30] {
+            let page = hook.list_yara_rules_paged(offset, 10).await.unwrap();
+            sizes.push(page.len());
+            for paged in page {
+                assert!(seen.insert(paged.id), "rule repeated at offset {}", offset);
+            }
+        }
+        assert_eq!(sizes, [10, 10, 5, 0]);
+        assert_eq!(seen.len(), 25);
+    }
+
+    /// `rule(name)` carrying `tags`.
+    fn tagged(name: &str, tags: &[&str]) -> YaraRule {
+        YaraRule { tags: tags.iter().map(|tag| tag.to_string()).collect(), ..rule(name) }
+    }
+
+    fn names(rules: Vec<YaraRule>) -> Vec<String> {
+        let mut names: Vec<String> = rules.into_iter().map(|rule| rule.name).collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn tag_search_is_case_insensitive_and_follows_deletes() {
+        let (_dir, hook) = temp_hook().await;
+        let deleted = tagged("Loader", &["APT29", "loader"]);
+        hook.store_yara_rules(&[
+            tagged("Dropper", &["apt29"]),
+            deleted.clone(),
+            tagged("Miner", &["crypto"]),
+        ]).await.unwrap();
+
+        assert_eq!(names(hook.search_rules_by_tag("apt29").await.unwrap()), ["Dropper", "Loader"]);
+        assert_eq!(names(hook.search_rules_by_tag("Apt29").await.unwrap()), ["Dropper", "Loader"]);
+        assert!(hook.search_rules_by_tag("ransomware").await.unwrap().is_empty());
+
+        hook.delete_yara_rule(&deleted.id).await.unwrap();
+        assert_eq!(names(hook.search_rules_by_tag("APT29").await.unwrap()), ["Dropper"]);
+        assert!(hook.search_rules_by_tag("loader").await.unwrap().is_empty());
+    }
+
+    fn mapped(name: &str, techniques: &[&str], tactics: &[&str]) -> YaraRule {
+        YaraRule {
+            mitre_techniques: techniques.iter().map(|id| id.to_string()).collect(),
+            mitre_tactics: tactics.iter().map(|id| id.to_string()).collect(),
+            ..rule(name)
+        }
+    }
+
+    #[tokio::test]
+    async fn mitre_lookups_return_only_the_rules_mapped_to_an_id() {
+        let (dir, hook) = temp_hook().await;
+        let deleted = mapped("Stager", &["T1105", "T1059.001"], &["TA0011"]);
+        hook.store_yara_rules(&[
+            mapped("Dropper", &["T1059.001", "T1105"], &["TA0002", "TA0011"]),
+            mapped("Loader", &[" t1059.001 "], &["TA0002"]),
+            mapped("Shell", &["T1059"], &["TA0002"]),
+            deleted.clone(),
+        ]).await.unwrap();
+
+        assert_eq!(names(hook.get_rules_by_mitre_technique("T1059.001").await.unwrap()), ["Dropper", "Loader", "Stager"]);
+        assert_eq!(names(hook.get_rules_by_mitre_technique("t1105").await.unwrap()), ["Dropper", "Stager"]);
+        assert_eq!(names(hook.get_rules_by_mitre_technique("T1059").await.unwrap()), ["Shell"]);
+        assert_eq!(names(hook.get_rules_by_mitre_tactic("TA0011").await.unwrap()), ["Dropper", "Stager"]);
+
+        hook.delete_yara_rule(&deleted.id).await.unwrap();
+        assert_eq!(names(hook.get_rules_by_mitre_technique("T1105").await.unwrap()), ["Dropper"]);
+
+        let mapping = dir.path().join("mapping.json");
+        std::fs::write(&mapping, r#"{"Loader": ["T1105"], "Shell": {"techniques": ["T1059"], "tactics": ["TA0011"]}}"#).unwrap();
+        hook.apply_mitre_mapping(&mapping).await.unwrap();
+        assert_eq!(names(hook.get_rules_by_mitre_technique("T1059.001").await.unwrap()), ["Dropper"]);
+        assert_eq!(names(hook.get_rules_by_mitre_technique("T1105").await.unwrap()), ["Dropper", "Loader"]);
+        assert_eq!(names(hook.get_rules_by_mitre_tactic("TA0002").await.unwrap()), ["Dropper", "Loader"]);
+        assert_eq!(names(hook.get_rules_by_mitre_tactic("TA0011").await.unwrap()), ["Dropper", "Shell"]);
+    }
+
+    #[tokio::test]
+    async fn sync_parses_mitre_ids_actors_and_families_from_meta() {
+        let (dir, hook) = temp_hook().await;
+        let rules_dir = dir.path().join("rules");
+        std::fs::create_dir(&rules_dir).unwrap();
+        std::fs::write(rules_dir.join("emotet.yar"), concat!(
+            "rule Emotet_Loader {\n",
+            "    meta:\n",
+            "        mitre_technique = \"T1059.001, T1105\"\n",
+            "        mitre_techniques = \"T1105\"\n",
+            "        mitre_tactic = \"TA0002\"\n",
+            "        threat_actor = \"TA542\"\n",
+            "        malware_family = \"Emotet\"\n",
+            "        malware_family = \"Heodo\"\n",
+            "    condition:\n",
+            "        true\n",
+            "}\n",
+        )).unwrap();
+
+        sync_yara_rules_from_directory(&hook, rules_dir.to_str().unwrap(), &SyncOptions::default()).await.unwrap();
+
+        let rules = hook.list_yara_rules().await.unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].mitre_techniques, ["T1059.001", "T1105"]);
+        assert_eq!(rules[0].mitre_tactics, ["TA0002"]);
+        assert_eq!(rules[0].threat_actors, ["TA542"]);
+        assert_eq!(rules[0].malware_families, ["Emotet", "Heodo"]);
+    }
+
+    #[tokio::test]
+    async fn syncing_twice_does_not_grow_the_rule_count() {
+        let (dir, hook) = temp_hook().await;
+        let rules_dir = dir.path().join("rules");
+        std::fs::create_dir(&rules_dir).unwrap();
+        std::fs::write(rules_dir.join("loader.yar"), rule("Loader").content).unwrap();
+        std::fs::write(rules_dir.join("loader_copy.yar"), rule("Loader").content).unwrap();
+        std::fs::write(rules_dir.join("miner.yar"), rule("Miner").content).unwrap();
+        let rules_dir = rules_dir.to_str().unwrap();
+
+        let first = sync_yara_rules_from_directory(&hook, rules_dir, &SyncOptions::default()).await.unwrap();
+        assert_eq!(first.count(SyncStatus::Added), 2);
+        assert_eq!(hook.list_yara_rules().await.unwrap().len(), 2);
+
+        let second = sync_yara_rules_from_directory(&hook, rules_dir, &SyncOptions::default()).await.unwrap();
+        assert_eq!(second.count(SyncStatus::Added) + second.count(SyncStatus::Updated), 0);
+        assert_eq!(hook.list_yara_rules().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn a_copy_in_a_second_root_updates_the_stored_rule() {
+        let (dir, hook) = temp_hook().await;
+        let roots: Vec<String> = ["internal", "vendor"].iter().map(|root| {
+            let root = dir.path().join(root);
+            std::fs::create_dir(&root).unwrap();
+            std::fs::write(root.join("loader.yar"), rule("Loader").content).unwrap();
+            root.to_str().unwrap().to_string()
+        }).collect();
+
+        let first = sync_directories(&hook, &roots[..1], &SyncOptions::default()).await;
+        assert_eq!(first.directories[0].count(SyncStatus::Added), 1);
+        let stored = hook.list_yara_rules().await.unwrap().remove(0);
+
+        let second = sync_directories(&hook, &roots[1..], &SyncOptions::default()).await;
+        assert_eq!(second.directories[0].count(SyncStatus::Updated), 1);
+        let rules = hook.list_yara_rules().await.unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].id, stored.id);
+        assert!(rules[0].source.starts_with(&roots[1]), "{}", rules[0].source);
+        assert!(rules[0].updated_at > stored.updated_at);
+        assert!(rules[0].tags.contains(&"root:internal".to_string()));
+        assert!(rules[0].tags.contains(&"root:vendor".to_string()));
+
+        sync_directories(&hook, &roots, &SyncOptions::default()).await;
+        assert_eq!(hook.list_yara_rules().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rule_metadata_round_trips() {
+        let (_dir, hook) = temp_hook().await;
+        let mut metadata = RuleMetadata::new("rule-1");
+        metadata.detection_count = 7;
+        hook.update_rule_metadata(&metadata).await.unwrap();
+
+        let read = hook.get_rule_metadata("rule-1").await.unwrap().unwrap();
+        assert_eq!(read.detection_count, 7);
+        assert!(hook.get_rule_metadata("rule-2").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn threat_intel_is_keyed_by_type_and_value() {
+        let (_dir, hook) = temp_hook().await;
+        hook.store_threat_intel(&indicator("Domain", "evil.example", 0.9)).await.unwrap();
+        hook.store_threat_intel(&indicator("domain", "evil.example", 0.4)).await.unwrap();
+        hook.store_threat_intel(&indicator("ip", "203.0.113.7", 0.95)).await.unwrap();
+
+        let stored = hook.get_threat_intel("domain", "evil.example").await.unwrap().unwrap();
+        assert_eq!(stored.confidence, 0.4);
+        assert!(hook.get_threat_intel("ip", "evil.example").await.unwrap().is_none());
+
+        let high: Vec<String> = hook.get_high_confidence_indicators(0.5).await.unwrap()
+            .into_iter().map(|indicator| indicator.value).collect();
+        assert_eq!(high, ["203.0.113.7"]);
+    }
+}
@@ -0,0 +1,320 @@
+/// A YARA source file split into its top-level pieces.
+#[derive(Debug, Default)]
+pub struct YaraSource {
+    /// `import` and `include` statements, which every rule in the file shares.
+    pub imports: Vec<String>,
+    pub rules: Vec<RuleBlock>,
+}
+
+/// One `rule NAME { ... }` block, including its modifiers, tags and any
+/// comments directly above it.
+#[derive(Debug)]
+pub struct RuleBlock {
+    pub name: String,
+    /// Tags listed after the rule name (`rule name : tag1 tag2 { ... }`).
+    pub tags: Vec<String>,
+    pub text: String,
+    /// Byte offset of `name` in the source that was split.
+    pub name_offset: usize,
+}
+
+impl RuleBlock {
+    /// The rule as a standalone source, prefixed with the file's imports.
+    pub fn with_imports(&self, imports: &[String]) -> String {
+        if imports.is_empty() {
+            return self.text.clone();
+        }
+        format!("{}\n\n{}\n", imports.join("\n"), self.text)
+    }
+
+    /// Value of `key` in the rule's `meta:` section, without quotes.
+    ///
+    /// Expects the usual one-entry-per-line layout; entries sharing a line
+    /// with a section header or another entry are not found.
+    pub fn meta_value(&self, key: &str) -> Option<String> {
+        self.meta_entries()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| value.to_string())
+    }
+
+    /// Every value of `key` in the rule's `meta:` section, in order, for
+    /// entries a rule may repeat to list several values.
+    pub fn meta_values(&self, key: &str) -> Vec<String> {
+        self.meta_entries()
+            .filter(|(name, _)| *name == key)
+            .map(|(_, value)| value.to_string())
+            .collect()
+    }
+
+    /// `(name, value)` pairs of the `meta:` section, values without quotes.
+    fn meta_entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        let mut in_meta = false;
+
+        self.text.lines().filter_map(move |line| {
+            let mut line = line.trim();
+            for section in ["meta:", "strings:", "condition:"] {
+                if let Some(rest) = line.strip_prefix(section) {
+                    in_meta = section == "meta:";
+                    line = rest.trim();
+                }
+            }
+            if !in_meta {
+                return None;
+            }
+
+            line.split_once('=')
+                .map(|(name, value)| (name.trim(), value.trim().trim_matches('"')))
+        })
+    }
+}
+
+/// Splits YARA source at rule boundaries.
+///
+/// This only understands enough of the grammar to find top-level blocks:
+/// comments, text strings and regular expressions are skipped so braces
+/// inside them don't count, and hex strings are balanced like any other
+/// braces. A `/` starts a regular expression only after `=` or `matches`;
+/// anywhere else it is division. Returns `None` when the braces don't balance, in which case the
+/// caller should treat the file as opaque.
+pub fn split_rules(content: &str) -> Option<YaraSource> {
+    let bytes = content.as_bytes();
+    let mut source = YaraSource::default();
+
+    let mut depth = 0usize;
+    let mut segment_start: Option<usize> = None;
+    let mut rule_name: Option<(String, usize)> = None;
+    let mut tags = Vec::new();
+    let mut expect_name = false;
+    // End of the last token that wasn't a comment
+    let mut token_end = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if depth == 0 && segment_start.is_none() {
+            segment_start = Some(i);
+        }
+
+        match c {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = content[i..].find('\n').map_or(bytes.len(), |end| i + end);
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = content[i + 2..].find("*/").map(|end| i + 2 + end + 2)?;
+                continue;
+            }
+            b'"' => i = skip_delimited(bytes, i, b'"')?,
+            b'/' if regex_can_start(&content[..token_end]) => i = skip_regex(bytes, i)?,
+            b'{' => {
+                depth += 1;
+                i += 1;
+            }
+            b'}' => {
+                depth = depth.checked_sub(1)?;
+                i += 1;
+                if depth == 0 {
+                    let start = segment_start.take()?;
+                    let (name, name_offset) = rule_name.take()?;
+                    source.rules.push(RuleBlock {
+                        name,
+                        tags: std::mem::take(&mut tags),
+                        text: content[start..i].trim_end().to_string(),
+                        name_offset,
+                    });
+                }
+            }
+            c if depth == 0 && (c.is_ascii_alphabetic() || c == b'_') => {
+                let end = i + bytes[i..].iter()
+                    .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+                    .count();
+                let word = &content[i..end];
+
+                if expect_name {
+                    rule_name = Some((word.to_string(), i));
+                    expect_name = false;
+                } else if rule_name.is_some() {
+                    tags.push(word.to_string());
+                } else if word == "rule" {
+                    expect_name = true;
+                } else if (word == "import" || word == "include") && rule_name.is_none() {
+                    let quote = end + bytes[end..].iter().take_while(|b| b.is_ascii_whitespace()).count();
+                    if bytes.get(quote) != Some(&b'"') {
+                        return None;
+                    }
+                    let statement_end = skip_delimited(bytes, quote, b'"')?;
+                    source.imports.push(content[i..statement_end].to_string());
+                    segment_start = None;
+                    i = statement_end;
+                    token_end = i;
+                    continue;
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+        token_end = i;
+    }
+
+    (depth == 0 && rule_name.is_none()).then_some(source)
+}
+
+/// Identifiers of the rules `content` declares, in order. Empty when the
+/// source can't be split.
+pub fn declared_rule_names(content: &str) -> Vec<String> {
+    split_rules(content)
+        .map(|source| source.rules.into_iter().map(|rule| rule.name).collect())
+        .unwrap_or_default()
+}
+
+/// `content` with the declaration of rule `old` renamed to `new`, or `None`
+/// when no rule of that name is found. References to the rule from other
+/// conditions are left alone.
+pub fn rename_rule(content: &str, old: &str, new: &str) -> Option<String> {
+    let block = split_rules(content)?.rules.into_iter().find(|rule| rule.name == old)?;
+    let mut renamed = content.to_string();
+    renamed.replace_range(block.name_offset..block.name_offset + old.len(), new);
+    Some(renamed)
+}
+
+/// Checks the structure of YARA source: at least one rule, and a non-empty
+/// `condition:` section in every rule. This is the check `split_rules`
+/// allows, not a compile, so errors in expressions or string definitions
+/// still only surface when Thor loads the rules. Source `split_rules` can't
+/// tokenize is accepted rather than risk refusing valid YARA.
+pub fn validate_rule_content(content: &str) -> anyhow::Result<()> {
+    let Some(source) = split_rules(content) else {
+        log::debug!("Skipping the YARA structure check of source that can't be split into rules");
+        return Ok(());
+    };
+    if source.rules.is_empty() {
+        return Err(anyhow::anyhow!("no rule declaration found"));
+    }
+    for rule in &source.rules {
+        match condition_body(&rule.text) {
+            None => return Err(anyhow::anyhow!("rule {} has no condition section", rule.name)),
+            Some(body) if body.trim().is_empty() => {
+                return Err(anyhow::anyhow!("rule {} has an empty condition", rule.name));
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// The text after a rule's `condition:` section header, up to its closing
+/// brace, or `None` when it has no such header.
+fn condition_body(text: &str) -> Option<&str> {
+    text.match_indices("condition").find_map(|(start, word)| {
+        let after = text[start + word.len()..].trim_start();
+        let preceded_by_word = text[..start].chars().next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+        let body = after.strip_prefix(':').filter(|_| !preceded_by_word)?;
+        Some(body.strip_suffix('}').unwrap_or(body))
+    })
+}
+
+/// Whether a `/` following `before` opens a regular expression: a string
+/// definition (`$a = /.../`) or the right side of `matches`.
+fn regex_can_start(before: &str) -> bool {
+    let before = before.trim_end();
+    if before.ends_with('=') {
+        return true;
+    }
+    before.strip_suffix("matches").is_some_and(|rest| {
+        !rest.ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Returns the index just past the closing `/` of a regular expression
+/// starting at `start`. A `/` inside a character class such as `[^/]`
+/// doesn't close it.
+fn skip_regex(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut in_class = false;
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\n' => return None,
+            b'[' if !in_class => {
+                in_class = true;
+                i += 1;
+                // A `]` first in the class (after any `^`) is a literal
+                if bytes.get(i) == Some(&b'^') {
+                    i += 1;
+                }
+                if bytes.get(i) == Some(&b']') {
+                    i += 1;
+                }
+            }
+            b']' if in_class => {
+                in_class = false;
+                i += 1;
+            }
+            b'/' if !in_class => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Returns the index just past the closing `delimiter` of a string or regex
+/// starting at `start`, honouring backslash escapes.
+fn skip_delimited(bytes: &[u8], start: usize, delimiter: u8) -> Option<usize> {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\n' => return None,
+            b if b == delimiter => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn structure_check_accepts_a_complete_rule() {
+        let content = "import \"pe\"\n\nrule Ok : tag {\n    strings:\n        $a = \"}\"\n    condition:\n        $a\n}\n";
+        validate_rule_content(content).unwrap();
+    }
+
+    #[test]
+    fn structure_check_rejects_broken_rules() {
+        let cases = [
+            ("// only a comment\n", "no rule declaration"),
+            ("rule NoCondition {\n    strings:\n        $a = \"x\"\n}\n", "no condition section"),
+            ("rule Empty {\n    condition:\n}\n", "empty condition"),
+        ];
+        for (content, expected) in cases {
+            let error = validate_rule_content(content).unwrap_err();
+            assert!(error.to_string().contains(expected), "{}: {}", content, error);
+        }
+    }
+
+    #[test]
+    fn structure_check_accepts_division_and_slashes_in_regexes() {
+        let content = concat!(
+            "rule Half {\n    condition:\n        filesize / 2 > 10\n}\n\n",
+            "rule Exe {\n    strings:\n        $a = /[^/]+\\.exe/\n",
+            "    condition:\n        $a and pe.sections[0].name matches /\\/text[/]/\n}\n",
+        );
+        validate_rule_content(content).unwrap();
+        assert_eq!(declared_rule_names(content), ["Half", "Exe"]);
+    }
+
+    #[test]
+    fn source_that_cant_be_split_is_not_refused() {
+        validate_rule_content("rule Open {\n    condition:\n        true\n").unwrap();
+    }
+}
@@ -0,0 +1,26 @@
+//! Library side of `pyro-thor`: the Thor scanner, the Pyro executor and the
+//! ReDB rules hook, shared by the binary and its tests.
+
+pub mod archive;
+pub mod clock;
+pub mod commands;
+pub mod config;
+pub mod context;
+pub mod error;
+pub mod executor;
+pub mod findings;
+pub mod hooks;
+pub mod package;
+pub mod platform;
+pub mod progress;
+pub mod quarantine;
+pub mod results_file;
+pub mod results_schema;
+pub mod retention;
+pub mod scanner;
+pub mod severity;
+pub mod tui;
+pub mod walk;
+
+#[cfg(test)]
+mod test_support;
@@ -1,18 +1,20 @@
-use clap::{Arg, Command};
+use clap::{Arg, ArgMatches, Command};
 use log::{error, info, warn};
-use std::env;
 
-mod config;
-mod executor;
-mod hooks;
-mod platform;
-mod scanner;
-
-use crate::config::PyroConfig;
-use crate::executor::PyroExecutor;
+use pyro_thor_yara::clock::{self, Clock, SharedClock};
+use pyro_thor_yara::commands;
+use pyro_thor_yara::config::{GateConfig, PyroConfig};
+use pyro_thor_yara::context::ScanContext;
+use pyro_thor_yara::error::{ErrorCategory, ErrorReport, PyroError};
+use pyro_thor_yara::executor::{PyroExecutor, ScanOutcome};
+use pyro_thor_yara::hooks::{initialize_yara_rules_hook, sync_yara_rules_from_directory, SyncOptions};
+use pyro_thor_yara::quarantine;
+use pyro_thor_yara::results_file;
+use pyro_thor_yara::scanner::ScanResults;
+use pyro_thor_yara::severity::Severity;
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() {
     env_logger::init();
 
     let matches = Command::new("pyro-thor")
@@ -25,14 +27,16 @@ async fn main() -> anyhow::Result<()> {
                 .long("config")
                 .value_name("FILE")
                 .help("Configuration file path")
-                .default_value("config.yaml"),
+                .default_value("config.yaml")
+                .global(true),
         )
         .arg(
             Arg::new("scan-path")
                 .short('p')
                 .long("path")
                 .value_name("PATH")
-                .help("Path to scan")
+                .help("Path to scan; repeat to scan several paths in one Thor run")
+                .action(clap::ArgAction::Append)
                 .default_value("/"),
         )
         .arg(
@@ -40,7 +44,7 @@ async fn main() -> anyhow::Result<()> {
                 .short('o')
                 .long("output")
                 .value_name("FILE")
-                .help("Output file for results")
+                .help("Output file for results, or - to write them to stdout")
                 .default_value("scan_results.json"),
         )
         .arg(
@@ -50,12 +54,26 @@ async fn main() -> anyhow::Result<()> {
                 .help("Enable verbose logging")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .help("Don't report Thor's progress while it scans")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("redb-enabled")
                 .long("redb-enabled")
                 .help("Enable ReDB optimization for YARA rules")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("require-redb")
+                .long("require-redb")
+                .help("Abort instead of scanning without ReDB when it can't be opened")
+                .requires("redb-enabled")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("enterprise-mode")
                 .long("enterprise-mode")
@@ -68,12 +86,155 @@ async fn main() -> anyhow::Result<()> {
                 .value_name("UUID")
                 .help("Unique scan identifier"),
         )
+        .arg(
+            Arg::new("meta")
+                .long("meta")
+                .value_name("KEY=VALUE")
+                .help("Attach metadata to the scan results (repeatable)")
+                .value_parser(parse_meta)
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("fail-on-match")
+                .long("fail-on-match")
+                .help("Exit non-zero when the scan has findings, using scanning.severity_exit_codes")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("thor-output-file")
+                .long("thor-output-file")
+                .help("Have Thor write its results to a file and read them from there instead of stdout")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("expand-archives")
+                .long("expand-archives")
+                .help("Extract archives under the scan path and scan their contents too")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("tier")
+                .long("tier")
+                .value_name("TIER")
+                .help("Only use ReDB rules of this scan tier and cheaper ones")
+                .value_parser(["quick", "standard", "deep"]),
+        )
+        .arg(
+            Arg::new("time-budget")
+                .long("time-budget")
+                .value_name("SECONDS")
+                .help("Stop Thor after this many seconds and report the partial results, exiting with scanning.partial_exit_code")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("compress-output")
+                .long("compress-output")
+                .help("Gzip the results file, writing it as <output>.gz")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("pretty")
+                .long("pretty")
+                .help("Write the results file as indented JSON with sorted keys (the default)")
+                .conflicts_with("compact")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("compact")
+                .long("compact")
+                .help("Write the results file as Thor emits it, without indenting")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("rule-stats-json")
+                .long("rule-stats-json")
+                .value_name("FILE")
+                .help("Write per-rule match counts to a JSON file"),
+        )
+        .arg(
+            Arg::new("quarantine")
+                .long("quarantine")
+                .help("Move flagged files into the quarantine directory after the scan")
+                .requires("quarantine-dir")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("quarantine-dir")
+                .long("quarantine-dir")
+                .value_name("DIR")
+                .help("Directory quarantined files are moved into"),
+        )
+        .arg(
+            Arg::new("quarantine-min-severity")
+                .long("quarantine-min-severity")
+                .value_name("SEVERITY")
+                .help("Lowest finding severity that triggers quarantine")
+                .value_parser(["info", "low", "medium", "high", "critical"])
+                .default_value("high"),
+        )
+        .arg(
+            Arg::new("yes")
+                .long("yes")
+                .help("Skip the interactive confirmation for destructive actions")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("db")
+                .long("db")
+                .value_name("FILE")
+                .help("ReDB database path")
+                .default_value("yara_rules.redb")
+                .global(true),
+        )
+        .arg(
+            Arg::new("error-json")
+                .long("error-json")
+                .value_name("FILE")
+                .help("On failure, also write a JSON error object (category, exit code, message, context) \
+                       to stderr, or to FILE with --error-json=FILE")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("-")
+                .global(true),
+        )
+        .subcommand(commands::audit::command())
+        .subcommand(commands::db::command())
+        .subcommand(commands::feed::command())
+        .subcommand(commands::feedback::command())
+        .subcommand(commands::intel::command())
+        .subcommand(commands::metadata::command())
+        .subcommand(commands::package::command())
+        .subcommand(commands::rule::command())
+        .subcommand(commands::show_command::command())
+        .subcommand(commands::tui::command())
+        .subcommand(commands::validate_results::command())
+        .subcommand(commands::version::command())
         .get_matches();
 
+    if let Err(e) = run(&matches).await {
+        let report = ErrorReport::new(&e);
+        eprintln!("Error: {:?}", e);
+        if let Some(target) = matches.get_one::<String>("error-json") {
+            if let Err(write_error) = report.write(target) {
+                eprintln!("Failed to write the JSON error object to {}: {:#}", target, write_error);
+            }
+        }
+        std::process::exit(report.exit_code);
+    }
+}
+
+/// Runs the subcommand, or the scan when there is none. Failures are
+/// reported by `main` with an exit code from their `ErrorCategory`.
+async fn run(matches: &ArgMatches) -> anyhow::Result<()> {
+    if let Some((name, sub_matches)) = matches.subcommand() {
+        return commands::run(name, sub_matches).await;
+    }
+
     let config_path = matches.get_one::<String>("config").unwrap();
-    let scan_path = matches.get_one::<String>("scan-path").unwrap();
+    let scan_paths: Vec<String> = matches.get_many::<String>("scan-path").unwrap().cloned().collect();
     let output_path = matches.get_one::<String>("output").unwrap();
-    let redb_enabled = matches.get_flag("redb-enabled");
+    let mut redb_enabled = matches.get_flag("redb-enabled");
+    let require_redb = matches.get_flag("require-redb");
     let enterprise_mode = matches.get_flag("enterprise-mode");
     let scan_uuid = matches.get_one::<String>("scan-uuid");
 
@@ -83,48 +244,280 @@ async fn main() -> anyhow::Result<()> {
         info!("Starting Pyro Thor YARA scanner");
     }
     
-    info!("Config: {}, Scan path: {}, Output: {}", config_path, scan_path, output_path);
+    info!("Config: {}, Scan paths: {}, Output: {}", config_path, scan_paths.join(", "), output_path);
     
     if redb_enabled {
         info!("ReDB optimization enabled");
     }
 
     let mut config = PyroConfig::load(config_path)?;
+    let clock = clock::from_env()?;
     
     // Override config with CLI flags
+    if matches.get_flag("pretty") {
+        config.scanning.pretty_output = true;
+    }
+    if matches.get_flag("compact") {
+        config.scanning.pretty_output = false;
+    }
+    if matches.get_flag("thor-output-file") {
+        config.scanning.thor_output_file = true;
+    }
+    if matches.get_flag("expand-archives") {
+        config.scanning.expand_archives = true;
+    }
+    if matches.get_flag("compress-output") {
+        config.scanning.compress_output = true;
+    }
+    if matches.get_flag("quiet") {
+        config.scanning.progress = false;
+    }
+    if let Some(&seconds) = matches.get_one::<u64>("time-budget") {
+        config.scanning.time_budget_seconds = seconds;
+    }
+    if config.scanning.compress_output && output_path == "-" {
+        info!("Results go to stdout, writing them uncompressed");
+        config.scanning.compress_output = false;
+    }
+    if output_path == "-" {
+        // There is no output directory to rotate
+        config.scanning.max_result_files = 0;
+        config.scanning.result_retention_days = 0;
+    }
+
     if redb_enabled {
         info!("Initializing ReDB YARA rules database...");
-        let redb_hook = crate::hooks::initialize_yara_rules_hook("yara_rules.redb").await?;
-        
-        // Sync rules from directory if it exists
-        if std::path::Path::new("custom-signatures/yara").exists() {
-            let synced_count = crate::hooks::sync_yara_rules_from_directory(
-                &redb_hook, 
-                "custom-signatures/yara"
-            ).await?;
-            info!("Synced {} YARA rules to ReDB", synced_count);
+        let db_path = matches.get_one::<String>("db").unwrap();
+        if let Err(e) = prepare_redb(db_path, &config, clock.clone()).await {
+            if require_redb {
+                return Err(e.context("ReDB is required (--require-redb) but could not be initialized"));
+            }
+            warn!("⚠️  ReDB initialization failed, continuing WITHOUT ReDB optimization: {:#}", e);
+            redb_enabled = false;
         }
     }
 
-    let executor = PyroExecutor::new(config);
-
-    let result = if enterprise_mode {
-        executor.execute_enterprise_scan(scan_path, output_path, redb_enabled).await
-    } else {
-        executor.execute_scan(scan_path, output_path).await
+    // With `--output -` results go to a temporary file that is copied to
+    // stdout, so everything that re-reads the results file keeps working.
+    // Logging goes to stderr, which keeps stdout clean JSON.
+    let stdout_results = (output_path == "-")
+        .then(|| std::env::temp_dir().join(format!("pyro-thor-{}.json", uuid::Uuid::new_v4())));
+    let results_path = match &stdout_results {
+        Some(path) => path.to_string_lossy().to_string(),
+        None if config.scanning.compress_output => results_file::compressed_path(output_path),
+        None => output_path.clone(),
     };
 
-    match result {
-        Ok(_) => {
-            info!("✅ Scan completed successfully");
-            if let Some(uuid) = scan_uuid {
-                info!("Scan UUID: {}", uuid);
-            }
-            Ok(())
+    let ctx = ScanContext::new(&scan_paths, &results_path, clock.now())
+        .with_scan_uuid(scan_uuid.map(String::as_str))
+        .with_enterprise_mode(enterprise_mode)
+        .with_redb(redb_enabled.then(|| matches.get_one::<String>("db").unwrap().as_str()))
+        .with_redb_required(require_redb)
+        .with_scan_tier(matches.get_one::<String>("tier").map(|tier| tier.parse()).transpose()?)
+        .with_time_budget(
+            (config.scanning.time_budget_seconds > 0)
+                .then(|| std::time::Duration::from_secs(config.scanning.time_budget_seconds))
+        )
+        .with_metadata(
+            matches.get_many::<(String, String)>("meta")
+                .map(|pairs| pairs.cloned().collect())
+                .unwrap_or_default()
+        );
+
+    let severity_exit_codes = config.scanning.severity_exit_codes.clone();
+    let partial_exit_code = config.scanning.partial_exit_code;
+    let gate = config.gate.clone();
+    // First Ctrl-C requests a graceful shutdown; a second one exits at once
+    let shutdown = tokio_util::sync::CancellationToken::new();
+    let shutdown_trigger = shutdown.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            warn!("Interrupt received, shutting down (press Ctrl-C again to exit immediately)");
+            shutdown_trigger.cancel();
+        }
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
+
+    let executor = PyroExecutor::new(config)?
+        .with_shutdown(shutdown)
+        .with_clock(clock.clone());
+
+    let result = executor.execute_scan(&ctx).await;
+
+    let result = match (result, &stdout_results) {
+        (Ok(outcome), Some(path)) => {
+            let copied = outcome.csv_path.as_ref().map_or_else(|| path.clone(), std::path::PathBuf::from);
+            copy_to_stdout(&copied).map(|_| outcome)
         }
+        (result, _) => result,
+    };
+
+    let exit_code = match result {
+        Ok(outcome) => finish_scan(matches, &outcome, &severity_exit_codes, clock.as_ref())
+            .and_then(|code| check_gate(&gate, &outcome).map(|_| code))
+            .map(|code| match code {
+                None if outcome.incomplete => {
+                    warn!("Exiting with code {} for the incomplete scan", partial_exit_code);
+                    Some(partial_exit_code)
+                }
+                code => code,
+            }),
         Err(e) => {
             error!("❌ Scan failed: {}", e);
-            Err(e)
+            // Failures no step tagged more precisely are the scan's own
+            match ErrorCategory::of(&e) {
+                ErrorCategory::Other => Err(e.context(PyroError::new(
+                    ErrorCategory::Scan,
+                    "Scan failed",
+                ))),
+                _ => Err(e),
+            }
         }
+    };
+
+    if let Some(path) = &stdout_results {
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(results_file::csv_path(&path.to_string_lossy()));
+    }
+
+    if let Some(code) = exit_code? {
+        std::process::exit(code);
+    }
+    Ok(())
+}
+
+/// Post-scan reporting and actions; returns the exit code `--fail-on-match`
+/// asks for, if any.
+fn finish_scan(
+    matches: &clap::ArgMatches,
+    outcome: &ScanOutcome,
+    severity_exit_codes: &std::collections::BTreeMap<Severity, i32>,
+    clock: &dyn Clock,
+) -> anyhow::Result<Option<i32>> {
+    if outcome.incomplete {
+        warn!("⚠️  Scan INCOMPLETE: the time budget ran out; findings cover only part of the scan path");
+    } else {
+        info!("✅ Scan completed successfully");
+    }
+    info!("Scan UUID: {}", outcome.scan_uuid);
+    info!("{} findings {:?} in {:.1}s{}",
+          outcome.findings_count, outcome.by_severity, outcome.duration.as_secs_f64(),
+          if outcome.uploaded { ", uploaded to Pyro" } else { "" });
+    let risk = &outcome.results().summary.risk;
+    if let (Some(max), Some(mean)) = (risk.max, risk.mean()) {
+        info!("Risk: max {}, mean {:.1} over {} scored findings", max, mean, risk.scored);
+    }
+    if let Some(code) = outcome.thor_exit_code.filter(|&code| code != 0) {
+        warn!("Thor exited with code {}, accepted by thor.success_exit_codes", code);
+    }
+
+    if let Some(rule_stats_path) = matches.get_one::<String>("rule-stats-json") {
+        std::fs::write(rule_stats_path, serde_json::to_string_pretty(&outcome.results().summary.by_rule)?)?;
+        info!("Rule match counts written to: {}", rule_stats_path);
+    }
+
+    if matches.get_flag("quarantine") {
+        run_quarantine(matches, outcome.results(), &outcome.output_path, clock)?;
+    }
+
+    if matches.get_flag("fail-on-match") && outcome.findings_count > 0 {
+        let highest = outcome.results().summary.highest_severity();
+        let code = highest
+            .and_then(|severity| severity_exit_codes.get(&severity).copied())
+            .unwrap_or(1);
+        warn!("Scan has findings (worst: {}), exiting with code {}",
+              highest.map_or("unmapped".to_string(), |s| s.to_string()), code);
+        return Ok(Some(code));
+    }
+    Ok(None)
+}
+
+/// Fails the run when findings exceed any `gate` threshold.
+fn check_gate(gate: &GateConfig, outcome: &ScanOutcome) -> anyhow::Result<()> {
+    let exceeded: Vec<String> = gate.thresholds()
+        .into_iter()
+        .filter_map(|(key, severity, max)| {
+            let count = outcome.by_severity.get(&severity).copied().unwrap_or(0);
+            (count > max).then(|| format!("{} {} findings exceed gate.{} = {}", count, severity, key, max))
+        })
+        .collect();
+
+    if exceeded.is_empty() {
+        return Ok(());
+    }
+    for violation in &exceeded {
+        error!("Detection gate exceeded: {}", violation);
+    }
+    Err(anyhow::anyhow!("Detection gate failed: {}", exceeded.join("; ")))
+}
+
+fn copy_to_stdout(path: &std::path::Path) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut stdout = std::io::stdout().lock();
+    std::io::copy(&mut file, &mut stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Opens the ReDB database and syncs the local rules directory into it.
+async fn prepare_redb(db_path: &str, config: &PyroConfig, clock: SharedClock) -> anyhow::Result<()> {
+    let redb_hook = initialize_yara_rules_hook(db_path).await?
+        .with_integrity_policy(config.database.integrity_policy)
+        .with_duplicate_name_policy(config.database.duplicate_rule_names)
+        .with_content_dedup(config.database.dedup_rule_bodies)
+        .with_rule_compression(config.database.compress_rule_bodies)
+        .with_severity_from_tags(config.database.severity_from_tags.clone())
+        .with_skip_corrupt_records(config.database.skip_corrupt_records)
+        .with_clock(clock);
+    
+    // Sync rules from directory if it exists
+    if std::path::Path::new("custom-signatures/yara").exists() {
+        let report = sync_yara_rules_from_directory(
+            &redb_hook, 
+            "custom-signatures/yara",
+            &SyncOptions::default(),
+        ).await?;
+        info!("Synced {} YARA rules to ReDB", report.synced_count());
+    }
+    Ok(())
+}
+
+fn run_quarantine(
+    matches: &clap::ArgMatches,
+    results: &ScanResults,
+    output_path: &str,
+    clock: &dyn Clock,
+) -> anyhow::Result<()> {
+    let quarantine_dir = std::path::Path::new(matches.get_one::<String>("quarantine-dir").unwrap());
+    let min_severity: Severity = matches
+        .get_one::<String>("quarantine-min-severity")
+        .unwrap()
+        .parse()?;
+
+    let candidates = quarantine::find_candidates(results, output_path, min_severity)?;
+    if candidates.is_empty() {
+        info!("No files at or above {} severity to quarantine", min_severity);
+        return Ok(());
+    }
+
+    if !matches.get_flag("yes") && !quarantine::confirm(&candidates, quarantine_dir)? {
+        warn!("Quarantine cancelled; no files were moved");
+        return Ok(());
+    }
+
+    let quarantined = quarantine::quarantine_files(&candidates, quarantine_dir, clock)?;
+    info!("Quarantined {} of {} flagged files into {}",
+          quarantined.len(), candidates.len(), quarantine_dir.display());
+    Ok(())
+}
+
+fn parse_meta(pair: &str) -> Result<(String, String), String> {
+    match pair.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => Ok((key.trim().to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got {:?}", pair)),
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,172 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// File name of the cached Thor package, locally and on the Pyro server.
+pub const THOR_PACKAGE_FILE: &str = "Custom.DFIR.Yara.AllRules.zip";
+
+/// Sidecar metadata recorded next to a downloaded package so later runs can
+/// issue conditional requests and report what is cached.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageMetadata {
+    pub version: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub sha256: String,
+    pub size: u64,
+    pub downloaded_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl PackageMetadata {
+    /// Builds metadata for freshly downloaded bytes from the response headers.
+    pub fn from_download(bytes: &[u8], headers: &reqwest::header::HeaderMap, downloaded_at: chrono::DateTime<chrono::Utc>) -> Self {
+        Self::from_response(headers, sha256_hex(bytes), bytes.len() as u64, downloaded_at)
+    }
+
+    /// Builds metadata from the response headers for a package that was
+    /// hashed while it was streamed to disk.
+    pub fn from_response(
+        headers: &reqwest::header::HeaderMap,
+        sha256: String,
+        size: u64,
+        downloaded_at: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        let header = |name: &str| {
+            headers.get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+
+        Self {
+            version: header("x-package-version"),
+            etag: header("etag"),
+            last_modified: header("last-modified"),
+            sha256,
+            size,
+            downloaded_at: Some(downloaded_at),
+        }
+    }
+
+    /// Describes a package that is on disk, using its sidecar when present.
+    pub async fn for_package(package: &Path) -> Result<Self> {
+        let bytes = tokio::fs::read(package).await
+            .with_context(|| format!("Failed to read Thor package: {}", package.display()))?;
+        let sha256 = sha256_hex(&bytes);
+
+        match Self::load(package).await? {
+            Some(metadata) if metadata.sha256 == sha256 => Ok(metadata),
+            Some(_) => {
+                log::warn!("Thor package {} no longer matches its recorded metadata", package.display());
+                Ok(Self::unrecorded(sha256, bytes.len() as u64))
+            }
+            None => Ok(Self::unrecorded(sha256, bytes.len() as u64)),
+        }
+    }
+
+    fn unrecorded(sha256: String, size: u64) -> Self {
+        Self {
+            version: None,
+            etag: None,
+            last_modified: None,
+            sha256,
+            size,
+            downloaded_at: None,
+        }
+    }
+
+    pub fn sidecar_path(package: &Path) -> PathBuf {
+        let mut name = package.as_os_str().to_os_string();
+        name.push(".meta.json");
+        PathBuf::from(name)
+    }
+
+    pub async fn load(package: &Path) -> Result<Option<Self>> {
+        let path = Self::sidecar_path(package);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = tokio::fs::read_to_string(&path).await
+            .context("Failed to read package metadata")?;
+        match serde_json::from_str(&content) {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(e) => {
+                log::warn!("Ignoring unreadable package metadata {}: {}", path.display(), e);
+                Ok(None)
+            }
+        }
+    }
+
+    pub async fn save(&self, package: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)
+            .context("Failed to serialize package metadata")?;
+        tokio::fs::write(Self::sidecar_path(package), content).await
+            .context("Failed to write package metadata")?;
+        Ok(())
+    }
+}
+
+/// Checks a downloaded package before it replaces the cached one: its
+/// SHA-256 (`sha256`, as computed while downloading) against `expected`
+/// when given, otherwise that the file opens as a zip archive, which
+/// catches truncated downloads.
+pub fn verify_package(path: &Path, sha256: &str, expected: Option<&str>) -> Result<()> {
+    if let Some(expected) = expected {
+        if !sha256.eq_ignore_ascii_case(expected.trim()) {
+            return Err(anyhow::anyhow!(
+                "Thor package checksum mismatch: expected SHA-256 {}, got {}",
+                expected.trim(), sha256
+            ));
+        }
+        return Ok(());
+    }
+
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    zip::ZipArchive::new(file)
+        .context("Downloaded Thor package is not a valid zip archive (truncated download?)")?;
+    Ok(())
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_zip(path: &Path) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        zip.start_file("Thor/thor-lite", zip::write::FileOptions::default()).unwrap();
+        zip.write_all(b"binary").unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn checksum_mismatch_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let package = dir.path().join(THOR_PACKAGE_FILE);
+        write_zip(&package);
+        let sha256 = sha256_hex(&std::fs::read(&package).unwrap());
+
+        let error = verify_package(&package, &sha256, Some(&"0".repeat(64))).unwrap_err();
+        assert!(error.to_string().contains("checksum mismatch"), "{}", error);
+        verify_package(&package, &sha256, Some(&sha256.to_uppercase())).unwrap();
+    }
+
+    #[test]
+    fn without_a_checksum_the_archive_must_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let package = dir.path().join(THOR_PACKAGE_FILE);
+        write_zip(&package);
+        let bytes = std::fs::read(&package).unwrap();
+        verify_package(&package, &sha256_hex(&bytes), None).unwrap();
+
+        std::fs::write(&package, &bytes[..bytes.len() / 2]).unwrap();
+        let error = verify_package(&package, "", None).unwrap_err();
+        assert!(error.to_string().contains("not a valid zip archive"), "{}", error);
+    }
+}
@@ -91,6 +91,31 @@ pub mod windows {
 
         Ok(())
     }
+
+    /// Asks process `pid` and its children to close.
+    pub fn terminate_process(pid: u32) -> Result<()> {
+        taskkill(pid, false)
+    }
+
+    /// Ends process `pid` and its children at once.
+    pub fn kill_process(pid: u32) -> Result<()> {
+        taskkill(pid, true)
+    }
+
+    fn taskkill(pid: u32, force: bool) -> Result<()> {
+        let pid = pid.to_string();
+        let mut args = vec!["/PID", pid.as_str(), "/T"];
+        if force {
+            args.push("/F");
+        }
+        let output = Command::new("taskkill").args(&args).output()?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("taskkill /PID {} failed: {}",
+                pid, String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(unix)]
@@ -123,7 +148,7 @@ pub mod unix {
 
     pub fn set_executable_permissions(path: &str) -> Result<()> {
         let output = Command::new("chmod")
-            .args(&["+x", path])
+            .args(["+x", path])
             .output()?;
 
         if !output.status.success() {
@@ -133,4 +158,64 @@ pub mod unix {
 
         Ok(())
     }
+
+    /// Removes macOS's `com.apple.quarantine` attribute from `path`; a file
+    /// without it is left as is.
+    pub fn remove_quarantine_attribute(path: &str) -> Result<()> {
+        let output = quarantine_removal_command(path).output()?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() && !stderr.contains("No such xattr") {
+            return Err(anyhow::anyhow!("xattr -d com.apple.quarantine {} failed: {}", path, stderr.trim()));
+        }
+        Ok(())
+    }
+
+    fn quarantine_removal_command(path: &str) -> Command {
+        let mut command = Command::new("xattr");
+        command.args(["-d", "com.apple.quarantine", path]);
+        command
+    }
+
+    /// Sends SIGTERM to process `pid`.
+    pub fn terminate_process(pid: u32) -> Result<()> {
+        signal_process(pid, "TERM")
+    }
+
+    /// Sends SIGKILL to process `pid`.
+    pub fn kill_process(pid: u32) -> Result<()> {
+        signal_process(pid, "KILL")
+    }
+
+    fn signal_process(pid: u32, signal: &str) -> Result<()> {
+        let output = Command::new("kill")
+            .args([format!("-{}", signal), pid.to_string()])
+            .output()?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("kill -{} {} failed: {}",
+                signal, pid, String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn quarantine_removal_deletes_the_attribute() {
+            let command = quarantine_removal_command("/tmp/Thor/thor-lite-macosx");
+            assert_eq!(command.get_program(), "xattr");
+            let args: Vec<_> = command.get_args().collect();
+            assert_eq!(args, ["-d", "com.apple.quarantine", "/tmp/Thor/thor-lite-macosx"]);
+        }
+
+        #[cfg(target_os = "macos")]
+        #[test]
+        fn removing_a_missing_attribute_succeeds() {
+            let file = tempfile::NamedTempFile::new().unwrap();
+            remove_quarantine_attribute(file.path().to_str().unwrap()).unwrap();
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,178 @@
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// Width the progress bar line is cut to when `COLUMNS` isn't set.
+const DEFAULT_TERMINAL_WIDTH: usize = 100;
+const BAR_WIDTH: usize = 24;
+
+/// What one Thor progress line says. Fields Thor left out are `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgressUpdate {
+    pub files_scanned: Option<u64>,
+    pub percent: Option<f64>,
+    pub current_path: Option<String>,
+    pub eta: Option<String>,
+}
+
+impl ProgressUpdate {
+    /// Reads a Thor stderr line mentioning progress, e.g.
+    /// `Progress: 42% 12345 files scanned ETA: 00:12:03 Current: /usr/bin/ls`.
+    /// Lines without the word "progress" or without any recognized field
+    /// are not progress lines.
+    pub fn parse(line: &str) -> Option<Self> {
+        let lower = line.to_ascii_lowercase();
+        if !lower.contains("progress") {
+            return None;
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let lower_words: Vec<String> = words.iter().map(|word| word.to_ascii_lowercase()).collect();
+        let number = |word: &str| word.trim_matches(|c: char| !c.is_ascii_digit()).replace(',', "").parse::<u64>().ok();
+
+        let mut update = ProgressUpdate::default();
+        for (index, word) in lower_words.iter().enumerate() {
+            if let Some(percent) = word.strip_suffix('%').and_then(|p| p.trim_start_matches('(').parse::<f64>().ok()) {
+                update.percent.get_or_insert(percent.clamp(0.0, 100.0));
+            }
+            if word.trim_end_matches([',', ':']) == "files" {
+                // "12345 files" or "files: 12345" / "files scanned: 12345"
+                let count = index.checked_sub(1).and_then(|previous| number(words[previous]))
+                    .or_else(|| words.get(index + 1).and_then(|next| number(next)))
+                    .or_else(|| words.get(index + 2).filter(|_| lower_words[index + 1].starts_with("scanned")).and_then(|next| number(next)));
+                if count.is_some() {
+                    update.files_scanned = count;
+                }
+            }
+            if word.trim_end_matches(':') == "eta" {
+                update.eta = words.get(index + 1).map(|eta| eta.trim_end_matches(',').to_string());
+            }
+            if matches!(word.trim_end_matches(':'), "current" | "scanning" | "file") && word.ends_with(':') {
+                let path = words[index + 1..].join(" ");
+                if !path.is_empty() {
+                    update.current_path = Some(path);
+                }
+                break;
+            }
+        }
+
+        (update != ProgressUpdate::default()).then_some(update)
+    }
+}
+
+/// Shows Thor's progress while a scan runs: a redrawn status line when
+/// stderr is a terminal, otherwise an info log line every `log_interval`.
+/// Without an interval nothing is shown.
+pub struct ProgressReporter {
+    mode: Mode,
+    state: ProgressUpdate,
+    started: Instant,
+    last_report: Option<Instant>,
+    drawn: bool,
+}
+
+enum Mode {
+    Off,
+    Bar,
+    Log(Duration),
+}
+
+impl ProgressReporter {
+    pub fn new(log_interval: Option<Duration>) -> Self {
+        let mode = match log_interval {
+            None => Mode::Off,
+            Some(_) if std::io::stderr().is_terminal() => Mode::Bar,
+            Some(interval) => Mode::Log(interval),
+        };
+        Self { mode, state: ProgressUpdate::default(), started: Instant::now(), last_report: None, drawn: false }
+    }
+
+    /// Feeds one stderr line; returns whether it was a progress line.
+    pub fn line(&mut self, line: &str) -> bool {
+        let Some(update) = ProgressUpdate::parse(line) else { return false };
+        if matches!(self.mode, Mode::Off) {
+            return true;
+        }
+
+        self.state.files_scanned = update.files_scanned.or(self.state.files_scanned);
+        self.state.percent = update.percent.or(self.state.percent);
+        self.state.current_path = update.current_path.or(self.state.current_path.take());
+        self.state.eta = update.eta.or(self.state.eta.take());
+
+        match self.mode {
+            Mode::Bar => self.draw(),
+            Mode::Log(interval) if self.last_report.is_none_or(|last| last.elapsed() >= interval) => {
+                log::info!("Scan progress: {}", self.summary());
+                self.last_report = Some(Instant::now());
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Ends the status line so later output starts on a fresh line.
+    pub fn finish(&mut self) {
+        if self.drawn {
+            eprintln!();
+            self.drawn = false;
+        }
+    }
+
+    fn draw(&mut self) {
+        let mut line = String::new();
+        if let Some(percent) = self.state.percent {
+            let filled = (percent / 100.0 * BAR_WIDTH as f64).round() as usize;
+            line.push_str(&format!("[{}{}] ", "#".repeat(filled), ".".repeat(BAR_WIDTH - filled.min(BAR_WIDTH))));
+        }
+        line.push_str(&self.summary());
+
+        let width = std::env::var("COLUMNS").ok()
+            .and_then(|columns| columns.parse().ok())
+            .unwrap_or(DEFAULT_TERMINAL_WIDTH);
+        let line: String = line.chars().take(width.saturating_sub(1)).collect();
+
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[K{}", line);
+        let _ = stderr.flush();
+        self.drawn = true;
+    }
+
+    /// e.g. `42% 12345 files (310/s) ETA 00:12:03 /usr/bin/ls`.
+    fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(percent) = self.state.percent {
+            parts.push(format!("{:.0}%", percent));
+        }
+        if let Some(files) = self.state.files_scanned {
+            let elapsed = self.started.elapsed().as_secs_f64();
+            if elapsed >= 1.0 {
+                parts.push(format!("{} files ({:.0}/s)", files, files as f64 / elapsed));
+            } else {
+                parts.push(format!("{} files", files));
+            }
+        }
+        if let Some(eta) = self.eta() {
+            parts.push(format!("ETA {}", eta));
+        }
+        if let Some(path) = &self.state.current_path {
+            parts.push(path.clone());
+        }
+        parts.join(" ")
+    }
+
+    /// Thor's own ETA, or one extrapolated from the percentage done.
+    fn eta(&self) -> Option<String> {
+        if let Some(eta) = &self.state.eta {
+            return Some(eta.clone());
+        }
+        let percent = self.state.percent.filter(|percent| *percent > 0.0 && *percent < 100.0)?;
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let remaining = (elapsed * (100.0 - percent) / percent) as u64;
+        Some(format!("{:02}:{:02}:{:02}", remaining / 3600, remaining / 60 % 60, remaining % 60))
+    }
+}
+
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
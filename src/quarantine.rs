@@ -0,0 +1,147 @@
+use crate::clock::Clock;
+use crate::findings::parse_findings;
+use crate::scanner::ScanResults;
+use crate::severity::Severity;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+const MANIFEST_FILE: &str = "quarantine-manifest.json";
+
+/// A quarantined file and where it came from, so it can be restored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub original_path: PathBuf,
+    pub quarantined_path: PathBuf,
+    pub severity: Severity,
+    pub quarantined_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Collects the files flagged at or above `min_severity`, deduplicated.
+///
+/// Results that were streamed to disk are re-read from `output_path` one
+/// document at a time.
+pub fn find_candidates(results: &ScanResults, output_path: &str, min_severity: Severity) -> Result<Vec<(PathBuf, Severity)>> {
+    let mut candidates: Vec<(PathBuf, Severity)> = Vec::new();
+    let mut add_document = |document: Value| {
+        for finding in parse_findings(document) {
+            let Some(severity) = finding.severity.filter(|severity| *severity >= min_severity) else { continue };
+            // Files extracted from an archive are gone after the scan; move the archive
+            if let Some(path) = finding.archive_file.or(finding.path) {
+                let path = PathBuf::from(path);
+                match candidates.iter_mut().find(|(p, _)| *p == path) {
+                    Some((_, existing)) => *existing = (*existing).max(severity),
+                    None => candidates.push((path, severity)),
+                }
+            }
+        }
+    };
+
+    match &results.value {
+        Some(value) => add_document(value.clone()),
+        None => {
+            let file = crate::results_file::open(Path::new(output_path))?;
+            for document in serde_json::Deserializer::from_reader(file).into_iter::<Value>() {
+                add_document(document.context("Failed to parse scan results file")?);
+            }
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// Lists the candidates and asks the operator to type `yes` on stdin.
+pub fn confirm(candidates: &[(PathBuf, Severity)], quarantine_dir: &Path) -> Result<bool> {
+    eprintln!("The following {} file(s) will be moved to {}:", candidates.len(), quarantine_dir.display());
+    for (path, severity) in candidates {
+        eprintln!("  [{}] {}", severity, path.display());
+    }
+    eprint!("Type 'yes' to quarantine these files: ");
+    std::io::stderr().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)
+        .context("Failed to read confirmation")?;
+    Ok(answer.trim().eq_ignore_ascii_case("yes"))
+}
+
+/// Moves each candidate into `quarantine_dir`, strips its execute permissions
+/// and appends it to the directory's manifest. Files that fail to move are
+/// logged and skipped.
+pub fn quarantine_files(
+    candidates: &[(PathBuf, Severity)],
+    quarantine_dir: &Path,
+    clock: &dyn Clock,
+) -> Result<Vec<QuarantineEntry>> {
+    std::fs::create_dir_all(quarantine_dir)
+        .context("Failed to create quarantine directory")?;
+
+    let manifest_path = quarantine_dir.join(MANIFEST_FILE);
+    let mut manifest: Vec<QuarantineEntry> = if manifest_path.exists() {
+        let content = std::fs::read_to_string(&manifest_path)
+            .context("Failed to read quarantine manifest")?;
+        serde_json::from_str(&content)
+            .context("Failed to parse quarantine manifest")?
+    } else {
+        Vec::new()
+    };
+
+    let mut quarantined = Vec::new();
+    for (original_path, severity) in candidates {
+        let file_name = original_path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "unnamed".to_string());
+        let quarantined_path = quarantine_dir.join(format!("{}_{}", uuid::Uuid::new_v4(), file_name));
+
+        if let Err(e) = move_file(original_path, &quarantined_path) {
+            log::error!("Failed to quarantine {}: {}", original_path.display(), e);
+            continue;
+        }
+        if let Err(e) = strip_permissions(&quarantined_path) {
+            log::warn!("Failed to restrict permissions on {}: {}", quarantined_path.display(), e);
+        }
+
+        log::warn!("Quarantined {} -> {}", original_path.display(), quarantined_path.display());
+        let entry = QuarantineEntry {
+            original_path: original_path.clone(),
+            quarantined_path,
+            severity: *severity,
+            quarantined_at: clock.now(),
+        };
+        manifest.push(entry.clone());
+        quarantined.push(entry);
+    }
+
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+        .context("Failed to write quarantine manifest")?;
+
+    Ok(quarantined)
+}
+
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    // Fall back to copy + remove across filesystems
+    std::fs::copy(from, to).context("Failed to copy file into quarantine")?;
+    std::fs::remove_file(from).context("Failed to remove original file")?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn strip_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o400))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn strip_permissions(path: &Path) -> Result<()> {
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    permissions.set_readonly(true);
+    std::fs::set_permissions(path, permissions)?;
+    Ok(())
+}
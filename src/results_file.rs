@@ -0,0 +1,224 @@
+use anyhow::{Context, Result};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Extension added to the results file name when `scanning.compress_output` is set.
+pub const COMPRESSED_EXTENSION: &str = "gz";
+
+/// Extension of the CSV written next to the results file for `scanning.output_format: csv`.
+pub const CSV_EXTENSION: &str = "csv";
+
+/// `path` with `.gz` appended, unless it already ends in it.
+pub fn compressed_path(path: &str) -> String {
+    let suffix = format!(".{}", COMPRESSED_EXTENSION);
+    if path.ends_with(&suffix) {
+        path.to_string()
+    } else {
+        format!("{}{}", path, suffix)
+    }
+}
+
+/// The CSV file written next to results file `path`: its `.json` extension
+/// replaced with `.csv`, keeping a `.gz` suffix.
+pub fn csv_path(path: &str) -> String {
+    let gz_suffix = format!(".{}", COMPRESSED_EXTENSION);
+    let (base, gz) = match path.strip_suffix(&gz_suffix) {
+        Some(base) => (base, gz_suffix.as_str()),
+        None => (path, ""),
+    };
+    let base = base.strip_suffix(".json").unwrap_or(base);
+    format!("{}.{}{}", base, CSV_EXTENSION, gz)
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+pub(crate) fn csv_field(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+/// Whether the file starts with the gzip magic bytes.
+pub fn is_compressed(path: &Path) -> Result<bool> {
+    let mut magic = [0u8; 2];
+    let mut file = File::open(path)
+        .context("Failed to open scan results file")?;
+    let read = file.read(&mut magic)?;
+    Ok(read == magic.len() && magic == [0x1f, 0x8b])
+}
+
+/// Opens a results file for reading, decompressing it if it is gzipped.
+pub fn open(path: &Path) -> Result<Box<dyn BufRead + Send>> {
+    let compressed = is_compressed(path)?;
+    let file = File::open(path)
+        .context("Failed to open scan results file")?;
+
+    Ok(if compressed {
+        Box::new(BufReader::new(MultiGzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    })
+}
+
+/// A results file being written, gzip-compressed when requested.
+///
+/// `finish` must be called to write the gzip trailer; dropping the writer
+/// without it leaves a truncated file.
+pub enum ResultsWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl ResultsWriter {
+    pub fn create(path: &Path, compress: bool) -> Result<Self> {
+        let file = BufWriter::new(File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?);
+
+        Ok(if compress {
+            Self::Gzip(GzEncoder::new(file, flate2::Compression::default()))
+        } else {
+            Self::Plain(file)
+        })
+    }
+
+    pub fn finish(self) -> Result<()> {
+        let mut file = match self {
+            Self::Plain(file) => file,
+            Self::Gzip(encoder) => encoder.finish()?,
+        };
+        file.flush()?;
+        Ok(())
+    }
+}
+
+impl Write for ResultsWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(file) => file.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(file) => file.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Writes the findings in JSON results file `path` as CSV to `csv_path`,
+/// through a temporary file next to it: a header row, then one row per
+/// finding with a column for every top-level field any finding has, in
+/// order of first appearance. Nested values are written as JSON. The JSON
+/// file is left as it is. Returns the number of rows.
+pub fn convert_to_csv(path: &Path, csv_path: &Path, compress: bool) -> Result<u64> {
+    let mut columns: Vec<String> = Vec::new();
+    let mut seen = BTreeSet::new();
+    for_each_finding(path, |finding| {
+        for key in finding.keys() {
+            if seen.insert(key.clone()) {
+                columns.push(key.clone());
+            }
+        }
+        Ok(())
+    })?;
+
+    let mut converted_name = csv_path.as_os_str().to_os_string();
+    converted_name.push(".tmp");
+    let converted_path = std::path::PathBuf::from(converted_name);
+
+    let mut writer = ResultsWriter::create(&converted_path, compress)?;
+    let header: Vec<_> = columns.iter().map(|column| csv_field(column)).collect();
+    writeln!(writer, "{}", header.join(","))?;
+
+    let mut rows = 0;
+    for_each_finding(path, |finding| {
+        let row: Vec<String> = columns.iter()
+            .map(|column| match finding.get(column) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(text)) => csv_field(text).into_owned(),
+                Some(value) => csv_field(&value.to_string()).into_owned(),
+            })
+            .collect();
+        writeln!(writer, "{}", row.join(",")).context("Failed to write CSV results")?;
+        rows += 1;
+        Ok(())
+    })?;
+    writer.finish().context("Failed to write CSV results")?;
+
+    std::fs::rename(&converted_path, csv_path)
+        .with_context(|| format!("Failed to write {}", csv_path.display()))?;
+    Ok(rows)
+}
+
+/// Calls `f` with every finding object in a results file, stopping at the
+/// first error. Findings that aren't objects are skipped with a warning, and
+/// so is a last document cut off by the time budget stopping Thor.
+fn for_each_finding(path: &Path, mut f: impl FnMut(&serde_json::Map<String, Value>) -> Result<()>) -> Result<()> {
+    let reader = open(path)?;
+    for document in serde_json::Deserializer::from_reader(reader).into_iter::<Value>() {
+        let document = match document {
+            Ok(document) => document,
+            Err(e) if e.is_eof() => {
+                log::warn!("Scan results file ends mid-document, skipping its incomplete tail");
+                break;
+            }
+            Err(e) => return Err(e).context("Failed to parse scan results file"),
+        };
+        let findings = match &document {
+            Value::Array(findings) => findings.iter().collect(),
+            finding => vec![finding],
+        };
+        for finding in findings {
+            match finding.as_object() {
+                Some(object) => f(object)?,
+                None => log::warn!("Skipping a finding that isn't a JSON object: {}", finding),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_path_replaces_the_json_extension() {
+        assert_eq!(csv_path("out/scan_results.json"), "out/scan_results.csv");
+        assert_eq!(csv_path("scan_results.json.gz"), "scan_results.csv.gz");
+        assert_eq!(csv_path("results"), "results.csv");
+    }
+
+    #[test]
+    fn two_findings_become_a_header_and_two_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = dir.path().join("scan_results.json");
+        let csv = dir.path().join("scan_results.csv");
+        std::fs::write(&json, r#"[{"rule":"A","path":"/a","score":80},{"rule":"B, quoted","reasons":[1]}]"#).unwrap();
+
+        assert_eq!(convert_to_csv(&json, &csv, false).unwrap(), 2);
+        let written = std::fs::read_to_string(&csv).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines, ["path,rule,score,reasons", "/a,A,80,", ",\"B, quoted\",,[1]"]);
+        assert!(json.exists());
+    }
+
+    #[test]
+    fn a_truncated_last_finding_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = dir.path().join("scan_results.json");
+        let csv = dir.path().join("scan_results.csv");
+        std::fs::write(&json, "{\"rule\":\"A\"}\n{\"rule\":\"B\"}\n{\"rule\":\"C").unwrap();
+
+        assert_eq!(convert_to_csv(&json, &csv, false).unwrap(), 2);
+        assert_eq!(std::fs::read_to_string(&csv).unwrap().lines().count(), 3);
+    }
+}
@@ -0,0 +1,151 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// JSON Schema for results documents. Only the keywords it uses are
+/// implemented: `type`, `enum`, `required`, `properties`,
+/// `additionalProperties`, `items`, `anyOf` and local `$ref`s.
+const RESULTS_SCHEMA: &str = include_str!("results.schema.json");
+
+/// A place where a document doesn't match the schema.
+#[derive(Debug, Clone, Serialize)]
+pub struct Violation {
+    /// JSON path of the offending value, e.g. `$[3].level`.
+    pub path: String,
+    pub message: String,
+}
+
+pub struct ResultsSchema {
+    root: Value,
+}
+
+impl ResultsSchema {
+    pub fn embedded() -> Self {
+        Self {
+            root: serde_json::from_str(RESULTS_SCHEMA).expect("embedded results schema is valid JSON"),
+        }
+    }
+
+    /// Checks one top-level results document.
+    pub fn validate(&self, document: &Value) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        self.check(&self.root, document, "$", &mut violations);
+        violations
+    }
+
+    /// Follows a `$ref` of the form `#/definitions/...` to its schema.
+    fn resolve<'a>(&'a self, schema: &'a Value) -> &'a Value {
+        match schema.get("$ref").and_then(Value::as_str) {
+            Some(reference) => reference.strip_prefix('#')
+                .and_then(|pointer| self.root.pointer(pointer))
+                .expect("embedded results schema has only local references"),
+            None => schema,
+        }
+    }
+
+    fn check(&self, schema: &Value, value: &Value, path: &str, out: &mut Vec<Violation>) {
+        let schema = self.resolve(schema);
+        let violation = |message: String| Violation { path: path.to_string(), message };
+
+        if let Some(types) = schema.get("type") {
+            if !allows_type(types, value) {
+                out.push(violation(format!("expected {}, found {}", allowed_types(types).join(" or "), type_name(value))));
+                return;
+            }
+        }
+
+        if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+            if !options.contains(value) {
+                let options: Vec<String> = options.iter().map(Value::to_string).collect();
+                out.push(violation(format!("must be one of {}", options.join(", "))));
+            }
+        }
+
+        if let Some(branches) = schema.get("anyOf").and_then(Value::as_array) {
+            // Branches for another type would only report the type mismatch
+            let results: Vec<Vec<Violation>> = branches.iter()
+                .map(|branch| self.resolve(branch))
+                .filter(|branch| branch.get("type").is_none_or(|types| allows_type(types, value)))
+                .map(|branch| {
+                    let mut violations = Vec::new();
+                    self.check(branch, value, path, &mut violations);
+                    violations
+                })
+                .collect();
+            if results.is_empty() {
+                out.push(violation(format!("{} matches none of the allowed shapes", type_name(value))));
+            } else if !results.iter().any(Vec::is_empty) {
+                // Report the closest branch rather than every branch's complaints
+                out.extend(results.into_iter().min_by_key(Vec::len).unwrap_or_default());
+            }
+        }
+
+        if let Value::Object(object) = value {
+            for name in schema.get("required").and_then(Value::as_array).into_iter().flatten() {
+                if let Some(name) = name.as_str().filter(|name| !object.contains_key(*name)) {
+                    out.push(violation(format!("missing required property `{}`", name)));
+                }
+            }
+
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, child) in object {
+                let child_path = property_path(path, key);
+                match (properties.and_then(|properties| properties.get(key)), schema.get("additionalProperties")) {
+                    (Some(property), _) => self.check(property, child, &child_path, out),
+                    (None, Some(Value::Bool(false))) => out.push(Violation {
+                        path: child_path,
+                        message: "unexpected property".to_string(),
+                    }),
+                    (None, Some(additional @ Value::Object(_))) => self.check(additional, child, &child_path, out),
+                    (None, _) => {}
+                }
+            }
+        }
+
+        if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+            for (index, item) in items.iter().enumerate() {
+                self.check(item_schema, item, &format!("{}[{}]", path, index), out);
+            }
+        }
+    }
+}
+
+fn allowed_types(types: &Value) -> Vec<&str> {
+    match types {
+        Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+        other => other.as_str().into_iter().collect(),
+    }
+}
+
+fn allows_type(types: &Value, value: &Value) -> bool {
+    allowed_types(types).iter().any(|expected| type_matches(expected, value))
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        other => type_name(value) == other,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// `path.key`, or `path["key"]` when the key isn't a plain identifier.
+fn property_path(path: &str, key: &str) -> String {
+    let plain = !key.is_empty() && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        format!("{}.{}", path, key)
+    } else {
+        format!("{}[{}]", path, Value::String(key.to_string()))
+    }
+}
@@ -0,0 +1,73 @@
+use crate::config::ScanConfig;
+use crate::hooks::indicator_query::wildcard_match;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Which old result files to delete from the output directory after a scan.
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    /// Result files to keep, newest first; 0 for no limit.
+    pub max_files: usize,
+    pub max_age: Option<chrono::Duration>,
+    /// File name glob; only matching files are ever deleted.
+    pub pattern: String,
+}
+
+impl RetentionPolicy {
+    /// The configured policy, or `None` when neither limit is set.
+    pub fn from_config(scanning: &ScanConfig) -> Option<Self> {
+        if scanning.max_result_files == 0 && scanning.result_retention_days == 0 {
+            return None;
+        }
+        Some(Self {
+            max_files: scanning.max_result_files,
+            max_age: (scanning.result_retention_days > 0)
+                .then(|| chrono::Duration::days(scanning.result_retention_days as i64)),
+            pattern: scanning.result_file_pattern.clone(),
+        })
+    }
+
+    /// Deletes the regular files in `directory` matching the pattern that are
+    /// beyond `max_files` (by modification time) or older than `max_age`, and
+    /// returns them. `current`, the results file just written, is counted
+    /// but never deleted. Files that can't be deleted are logged and skipped.
+    pub fn enforce(&self, directory: &Path, current: &Path, now: chrono::DateTime<chrono::Utc>) -> Result<Vec<PathBuf>> {
+        let current = current.file_name();
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(directory)
+            .with_context(|| format!("Failed to read output directory: {}", directory.display()))?
+        {
+            let entry = entry?;
+            let name = entry.file_name();
+            if !wildcard_match(self.pattern.as_bytes(), name.to_string_lossy().as_bytes()) {
+                continue;
+            }
+            // Symlinks and directories are never result files
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            let modified = chrono::DateTime::<chrono::Utc>::from(entry.metadata()?.modified()?);
+            files.push((Some(name.as_os_str()) == current, modified, entry.path()));
+        }
+
+        // Newest first, with the current results file ahead of everything
+        files.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+
+        let mut deleted = Vec::new();
+        for (index, (is_current, modified, path)) in files.into_iter().enumerate() {
+            let over_count = self.max_files > 0 && index >= self.max_files;
+            let too_old = self.max_age.is_some_and(|max_age| now - modified > max_age);
+            if is_current || !(over_count || too_old) {
+                continue;
+            }
+            match std::fs::remove_file(&path) {
+                Ok(()) => {
+                    log::info!("Removed old results file {} (modified {})", path.display(), modified.format("%Y-%m-%d %H:%M"));
+                    deleted.push(path);
+                }
+                Err(e) => log::warn!("Failed to remove old results file {}: {}", path.display(), e),
+            }
+        }
+        Ok(deleted)
+    }
+}
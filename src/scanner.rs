@@ -1,19 +1,643 @@
-use crate::config::{PyroConfig, ThorConfig};
+use crate::clock::SharedClock;
+use crate::config::{DatabaseConfig, RiskConfig, ThorConfig, DEFAULT_LICENSE_PATH, DEFAULT_RULES_PATH, DEFAULT_THOR_CONFIG_PATH};
+use crate::context::ScanContext;
+use crate::findings::{finding_path, FindingsSummary};
 use crate::platform::PlatformInfo;
-use crate::hooks::{YaraRulesRedbHook, initialize_yara_rules_hook};
+use crate::progress::ProgressReporter;
+use crate::results_file::{self, ResultsWriter};
+use crate::archive::{expand_archives, ArchiveExpansion, ArchiveOptions};
+use crate::walk::{walk_files, WalkOptions};
+use crate::hooks::{YaraRulesRedbHook, ScanTier, initialize_yara_rules_hook, export_yara_rules_to_directory};
 use anyhow::{Context, Result};
 use serde_json::Value;
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::process::{Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
 use tempfile::TempDir;
-use tokio::fs;
+
+#[cfg(unix)]
+use crate::platform::unix::{kill_process, terminate_process};
+#[cfg(windows)]
+use crate::platform::windows::{kill_process, terminate_process};
+
+/// Default in-memory limit for Thor output before results are streamed to disk.
+pub const DEFAULT_MAX_BUFFER_BYTES: u64 = 64 * 1024 * 1024;
+
+/// File Thor writes its JSON results to in `--thor-output-file` mode.
+pub(crate) const THOR_OUTPUT_FILE: &str = "thor-output.json";
+
+/// Directory under the temp dir that ReDB rules are exported to.
+pub(crate) const REDB_EXPORT_DIR: &str = "redb-signatures";
+/// Directory under the temp dir that archives in the scan path are expanded into.
+pub(crate) const ARCHIVE_EXTRACT_DIR: &str = "archives";
+
+/// How long Thor gets to exit after the time budget or the scan timeout
+/// asks it to stop before it is killed.
+const TIME_BUDGET_GRACE: Duration = Duration::from_secs(10);
 
 pub struct ThorScanner {
     config: ThorConfig,
     platform: PlatformInfo,
     temp_dir: Option<TempDir>,
     redb_hook: Option<YaraRulesRedbHook>,
-    enterprise_mode: bool,
+    clock: SharedClock,
+    export_redb_rules: bool,
+    max_buffer_bytes: u64,
+    extract_workers: usize,
+    extract_limits: ExtractLimits,
+    retry_policy: RetryPolicy,
+    pretty_output: bool,
+    thor_output_file: bool,
+    /// Thor binary and the directory to run it from, as found in the
+    /// extracted package.
+    thor_location: Option<(PathBuf, PathBuf)>,
+    temp_base: Option<PathBuf>,
+    allowed_extract_roots: Vec<PathBuf>,
+    exclude_paths: Vec<PathBuf>,
+    max_file_size: Option<u64>,
+    /// Longest one Thor run may take before it is stopped and the scan fails.
+    scan_timeout: Option<Duration>,
+    archive_options: Option<ArchiveOptions>,
+    compress_output: bool,
+    /// Log interval for progress when stderr isn't a terminal; `None` turns
+    /// progress reporting off.
+    progress_interval: Option<Duration>,
+    /// Maps finding scores to the risk aggregated in the results summary.
+    risk_config: RiskConfig,
+}
+
+/// Everything on Thor's command line besides `thor.flags`, resolved.
+#[derive(Debug, Default)]
+pub struct ThorInvocation {
+    pub license: Option<PathBuf>,
+    pub custom_signatures: Option<PathBuf>,
+    pub template: Option<PathBuf>,
+    pub enterprise_mode: bool,
+    pub redb_optimized: bool,
+    pub json_file: Option<PathBuf>,
+    pub scan_paths: Vec<PathBuf>,
+    /// Directories Thor skips, each passed with `--exclude-path`.
+    pub exclude_paths: Vec<PathBuf>,
+    /// Largest file Thor checks, in bytes, passed with `--max_file_size`.
+    pub max_file_size: Option<u64>,
+    pub rebase_dir: PathBuf,
+}
+
+/// Builds Thor's argv (without the binary) in the order `run_scan` passes it.
+pub fn thor_args(config: &ThorConfig, invocation: &ThorInvocation) -> Vec<OsString> {
+    let mut args: Vec<OsString> = config.flags.iter().map(OsString::from).collect();
+    let push = |args: &mut Vec<OsString>, flag: &str, value: &Path| {
+        args.push(flag.into());
+        args.push(value.into());
+    };
+
+    if let Some(license) = &invocation.license {
+        push(&mut args, "--licensefile", license);
+    }
+    if let Some(rules_dir) = &invocation.custom_signatures {
+        push(&mut args, "--customsigs", rules_dir);
+    }
+    if let Some(template) = &invocation.template {
+        push(&mut args, "--template", template);
+    }
+    if invocation.enterprise_mode {
+        args.push("--enterprise-mode".into());
+        args.push("--ai-enhanced".into());
+        if invocation.redb_optimized {
+            args.push("--redb-optimized".into());
+        }
+    }
+    if let Some(json_file) = &invocation.json_file {
+        push(&mut args, "--jsonfile", json_file);
+    }
+    for scan_path in &invocation.scan_paths {
+        push(&mut args, "--path", scan_path);
+    }
+    for exclude_path in &invocation.exclude_paths {
+        push(&mut args, "--exclude-path", exclude_path);
+    }
+    if let Some(max_file_size) = invocation.max_file_size {
+        args.push("--max_file_size".into());
+        args.push(max_file_size.to_string().into());
+    }
+    push(&mut args, "--rebase-dir", &invocation.rebase_dir);
+
+    args
+}
+
+/// The entries of `scanning.exclude_paths` that exist on this host, so Thor
+/// isn't handed e.g. `C:\\Windows\\System32` on Linux.
+pub fn existing_exclude_paths(configured: &[PathBuf]) -> Vec<PathBuf> {
+    configured.iter()
+        .filter(|path| {
+            let exists = path.exists();
+            if !exists {
+                log::debug!("Not excluding {}: it does not exist on this system", path.display());
+            }
+            exists
+        })
+        .cloned()
+        .collect()
+}
+
+/// Whether `path` is named `thor-lite*` and is an `.exe` on Windows, or an
+/// executable without extension elsewhere, which leaves out files like
+/// `thor-lite-license.lic`.
+fn looks_like_thor_binary(path: &Path) -> bool {
+    if !path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with("thor-lite")) {
+        return false;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.extension().is_none()
+            && std::fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("exe"))
+}
+
+/// Resolves a path from `ThorConfig` against the extracted package: the
+/// `Thor` directory, then the package root Thor runs in, then the current
+/// directory. Absolute paths are used as-is; when no candidate exists the
+/// `Thor` directory one is returned so errors point where Thor would look.
+fn resolve_config_path(package_root: &Path, configured: &str) -> PathBuf {
+    let configured = Path::new(configured);
+    if configured.is_absolute() {
+        return configured.to_path_buf();
+    }
+
+    let candidates = [
+        package_root.join("Thor").join(configured),
+        package_root.join(configured),
+        configured.to_path_buf(),
+    ];
+    candidates.iter()
+        .find(|candidate| candidate.exists())
+        .unwrap_or(&candidates[0])
+        .clone()
+}
+
+/// Finds the Thor binary in an extracted package and the package root to run
+/// it from.
+///
+/// The expected layout is `<root>/Thor/<binary>`. Packages that nest that
+/// layout under another folder (`thor-10.7/Thor/...`) or ship the binary
+/// without a `Thor` folder are found by searching the tree; the shallowest
+/// match wins.
+///
+/// Packages naming the binary differently (`thor-lite`, `thor-lite_amd64`)
+/// fall back to the shallowest file that looks like a Thor binary.
+fn locate_thor_binary(extract_to: &Path, binary_name: &str) -> Result<(PathBuf, PathBuf)> {
+    let expected = extract_to.join("Thor").join(binary_name);
+    if expected.is_file() {
+        return Ok((expected, extract_to.to_path_buf()));
+    }
+
+    let files = walk_files(extract_to, &WalkOptions::default())?;
+    let shallowest = |path: &&PathBuf| (path.components().count(), (*path).clone());
+    let thor_binary = match files.iter()
+        .filter(|path| path.file_name().is_some_and(|name| name == binary_name))
+        .min_by_key(shallowest)
+    {
+        Some(path) => path.clone(),
+        None => {
+            let path = files.iter()
+                .filter(|path| looks_like_thor_binary(path))
+                .min_by_key(shallowest)
+                .with_context(|| format!(
+                    "Thor binary {} (or another thor-lite executable) not found anywhere in the extracted package at {}",
+                    binary_name, extract_to.display()
+                ))?;
+            log::info!("Thor binary {} not in the package, using {}", binary_name, path.display());
+            path.clone()
+        }
+    };
+
+    let binary_dir = thor_binary.parent().unwrap_or(extract_to);
+    let package_root = match binary_dir.file_name() {
+        Some(name) if name == "Thor" => binary_dir.parent().unwrap_or(extract_to),
+        _ => binary_dir,
+    };
+    Ok((thor_binary.clone(), package_root.to_path_buf()))
+}
+
+/// Thor ran but exited unsuccessfully.
+#[derive(Debug)]
+pub struct ThorExitError {
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+}
+
+impl std::fmt::Display for ThorExitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Thor scan failed: {}", self.stderr)
+    }
+}
+
+impl std::error::Error for ThorExitError {}
+
+/// When to re-run Thor after it exits unsuccessfully.
+///
+/// Only `ThorExitError`s are considered, so failures to find or spawn the
+/// binary are never retried. A failure is retriable when its exit code is
+/// listed or its stderr contains one of the patterns (case-insensitive).
+#[derive(Debug, Clone, Default)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub delay: std::time::Duration,
+    pub exit_codes: Vec<i32>,
+    pub error_patterns: Vec<String>,
+}
+
+impl RetryPolicy {
+    fn is_retriable(&self, error: &ThorExitError) -> bool {
+        if error.exit_code.is_some_and(|code| self.exit_codes.contains(&code)) {
+            return true;
+        }
+
+        let stderr = error.stderr.to_lowercase();
+        self.error_patterns.iter().any(|pattern| stderr.contains(&pattern.to_lowercase()))
+    }
+}
+
+fn extract_entry(
+    archive: &mut zip::ZipArchive<std::fs::File>,
+    index: usize,
+    extract_to: &Path,
+    platform: &PlatformInfo,
+    limits: &ExtractLimits,
+    extracted_bytes: &AtomicU64,
+) -> Result<()> {
+    let mut file = archive.by_index(index)
+        .context("Failed to read file from archive")?;
+
+    // The declared size can lie, so it is checked again while copying
+    if file.size() > limits.max_entry_bytes {
+        return Err(anyhow::anyhow!(
+            "Thor package entry {} is {} bytes uncompressed, over the {} byte limit (scanning.package_max_entry_mb)",
+            file.name(), file.size(), limits.max_entry_bytes
+        ));
+    }
+
+    let outpath = entry_output_path(extract_to, file.name(), file.enclosed_name())?;
+
+    if file.name().ends_with('/') {
+        // Directory
+        std::fs::create_dir_all(&outpath)
+            .with_context(|| format!("Failed to create directory for entry {}", file.name()))?;
+    } else {
+        // File
+        if let Some(parent) = outpath.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create parent directory for entry {}", file.name()))?;
+        }
+
+        let mut outfile = std::fs::File::create(&outpath)
+            .with_context(|| format!("Failed to create output file for entry {}", file.name()))?;
+
+        let remaining = limits.max_total_bytes.saturating_sub(extracted_bytes.load(Ordering::Relaxed));
+        let allowed = limits.max_entry_bytes.min(remaining);
+        let written = std::io::copy(&mut (&mut file).take(allowed + 1), &mut outfile)
+            .with_context(|| format!("Failed to extract entry {}", file.name()))?;
+
+        if written > limits.max_entry_bytes {
+            return Err(anyhow::anyhow!(
+                "Thor package entry {} unpacks to more than {} bytes (scanning.package_max_entry_mb)",
+                file.name(), limits.max_entry_bytes
+            ));
+        }
+        if extracted_bytes.fetch_add(written, Ordering::Relaxed) + written > limits.max_total_bytes {
+            return Err(anyhow::anyhow!(
+                "Thor package unpacks to more than {} bytes (scanning.package_max_total_mb)",
+                limits.max_total_bytes
+            ));
+        }
+    }
+
+    // Set executable permissions on Unix systems
+    #[cfg(unix)]
+    if platform.is_unix() && file.name().contains("thor-lite") {
+        if let Err(e) = crate::platform::unix::set_executable_permissions(
+            outpath.to_str().unwrap()
+        ) {
+            log::warn!("Failed to set executable permissions: {}", e);
+        }
+        // Gatekeeper kills quarantined binaries without a useful error
+        if platform.os == "macos" {
+            if let Err(e) = crate::platform::unix::remove_quarantine_attribute(
+                outpath.to_str().unwrap()
+            ) {
+                log::warn!("Failed to remove quarantine attribute: {}", e);
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = platform;
+
+    Ok(())
+}
+
+/// Where archive entry `name` is extracted under `extract_to`, which must be
+/// canonical. Checked before anything is created: names that are absolute
+/// or climb out with `..` (no `enclosed`), and paths whose existing part
+/// resolves outside `extract_to` through a symlink, are refused.
+fn entry_output_path(extract_to: &Path, name: &str, enclosed: Option<&Path>) -> Result<PathBuf> {
+    let escapes = || anyhow::anyhow!("Archive entry escapes extraction directory: {}", name);
+
+    let outpath = extract_to.join(enclosed.ok_or_else(escapes)?);
+    let existing = outpath.ancestors()
+        .find(|ancestor| ancestor.symlink_metadata().is_ok())
+        .unwrap_or(extract_to);
+    let resolved = std::fs::canonicalize(existing)
+        .with_context(|| format!("Failed to resolve output path for entry {}", name))?;
+    if !resolved.starts_with(extract_to) {
+        return Err(escapes());
+    }
+    Ok(outpath)
+}
+
+/// Size caps for unpacking the Thor package, guarding against zip bombs.
+#[derive(Debug, Clone)]
+pub struct ExtractLimits {
+    pub max_entry_bytes: u64,
+    pub max_total_bytes: u64,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_entry_bytes: 512 * 1024 * 1024,
+            max_total_bytes: 2048 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScanResults {
+    pub summary: FindingsSummary,
+    /// Parsed Thor output; `None` when it was too large to buffer and was
+    /// streamed straight to the results file instead.
+    pub value: Option<Value>,
+    /// Thor's exit code, one of `thor.success_exit_codes`.
+    pub thor_exit_code: Option<i32>,
+    /// Thor was stopped by the time budget, so only part of the scan path
+    /// was covered.
+    pub incomplete: bool,
+}
+
+/// How Thor's run ended.
+#[derive(Debug, Clone, Copy)]
+struct ThorExit {
+    code: Option<i32>,
+    /// Stopped by the time budget; its output may end mid-document.
+    stopped: bool,
+}
+
+/// Stops Thor when the time budget runs out: asks it to exit, then kills it
+/// if it is still running `TIME_BUDGET_GRACE` later.
+struct BudgetWatchdog {
+    exited: mpsc::Sender<()>,
+    thread: std::thread::JoinHandle<bool>,
+}
+
+impl BudgetWatchdog {
+    fn start(pid: u32, deadline: Instant) -> Self {
+        let (exited, thor_exited) = mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if thor_exited.recv_timeout(remaining) != Err(mpsc::RecvTimeoutError::Timeout) {
+                return false;
+            }
+
+            log::warn!("Time budget exhausted, stopping Thor");
+            if let Err(e) = terminate_process(pid) {
+                log::warn!("Failed to stop Thor: {:#}", e);
+            }
+            if thor_exited.recv_timeout(TIME_BUDGET_GRACE) == Err(mpsc::RecvTimeoutError::Timeout) {
+                log::warn!("Thor still running {}s after being asked to stop, killing it", TIME_BUDGET_GRACE.as_secs());
+                if let Err(e) = kill_process(pid) {
+                    log::warn!("Failed to kill Thor: {:#}", e);
+                }
+            }
+            true
+        });
+        Self { exited, thread }
+    }
+
+    /// To be called once Thor has exited; returns whether the budget
+    /// stopped it.
+    fn finish(self) -> bool {
+        drop(self.exited);
+        self.thread.join().unwrap_or(false)
+    }
+}
+
+/// Thor's process during one attempt, shared between the blocking task
+/// running it and `run_thor`, which stops it at the scan timeout.
+#[derive(Debug, Clone, Copy)]
+enum ThorProcess {
+    Pending,
+    Running(u32),
+    Exited,
+    /// Timed out before Thor was started, so it never is.
+    Cancelled,
+}
+
+impl ThorProcess {
+    /// Sends `signal` to Thor if it is still running, and keeps it from
+    /// starting if it hasn't yet. Returns whether it was still running.
+    fn signal(process: &Mutex<ThorProcess>, signal: fn(u32) -> Result<()>) -> bool {
+        let mut state = process.lock().unwrap_or_else(|e| e.into_inner());
+        match *state {
+            ThorProcess::Running(pid) => {
+                if let Err(e) = signal(pid) {
+                    log::warn!("Failed to stop Thor: {:#}", e);
+                }
+                true
+            }
+            ThorProcess::Pending => {
+                *state = ThorProcess::Cancelled;
+                false
+            }
+            ThorProcess::Exited | ThorProcess::Cancelled => false,
+        }
+    }
+}
+
+/// What reading one Thor run's output needs from `ThorScanner`, owned so
+/// the run can go on a blocking thread.
+#[derive(Debug, Clone)]
+struct OutputSettings {
+    success_exit_codes: Vec<i32>,
+    progress_interval: Option<Duration>,
+    max_buffer_bytes: u64,
+    pretty_output: bool,
+    compress_output: bool,
+    risk_config: RiskConfig,
+}
+
+/// Fields added to every finding before the results file is written.
+struct Annotations<'a> {
+    metadata: Option<Value>,
+    archives: Option<&'a ArchiveExpansion>,
+    /// Each finding gets the `scan_path` it was found under, however many
+    /// paths were scanned.
+    scan_paths: &'a [String],
+    /// Marks findings with `scan_incomplete: true`.
+    incomplete: bool,
+}
+
+impl Annotations<'_> {
+    fn is_empty(&self) -> bool {
+        self.metadata.is_none() && self.archives.is_none() && self.scan_paths.is_empty() && !self.incomplete
+    }
+
+    /// The most specific scan path `finding` lies under, going by the
+    /// archive it came from when it was extracted from one. With a single
+    /// scan path every finding is under it, whatever its path says.
+    fn scan_path_of(&self, finding: &Value) -> Option<&str> {
+        if let [scan_path] = self.scan_paths {
+            return Some(scan_path);
+        }
+        let path = finding.get("archive_file").and_then(Value::as_str)
+            .or_else(|| finding_path(finding))?;
+        self.scan_paths.iter()
+            .filter(|scan_path| Path::new(path).starts_with(scan_path))
+            .max_by_key(|scan_path| scan_path.len())
+            .map(String::as_str)
+    }
+
+    /// Annotates every finding object in a results document.
+    fn apply(&self, document: &mut Value) {
+        match document {
+            Value::Array(findings) => findings.iter_mut().for_each(|f| self.apply(f)),
+            Value::Object(_) => {
+                if let Some(archives) = self.archives {
+                    archives.annotate(document);
+                }
+                let scan_path = self.scan_path_of(document).map(str::to_string);
+                let Value::Object(finding) = document else { return };
+                if let Some(scan_path) = scan_path {
+                    finding.insert("scan_path".to_string(), Value::String(scan_path));
+                }
+                if let Some(metadata) = &self.metadata {
+                    finding.insert("scan_metadata".to_string(), metadata.clone());
+                }
+                if self.incomplete {
+                    finding.insert("scan_incomplete".to_string(), Value::Bool(true));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Rewrites a streamed results file one document at a time, through a
+/// temporary file next to it, applying the pretty-printing and annotations
+/// the raw Thor output was written without. For an incomplete scan an
+/// unparseable last document is dropped rather than failing the rewrite.
+fn rewrite_results_file(path: &Path, pretty: bool, compress: bool, annotations: &Annotations) -> Result<()> {
+    let mut rewritten_name = path.as_os_str().to_os_string();
+    rewritten_name.push(".tmp");
+    let rewritten_path = PathBuf::from(rewritten_name);
+
+    {
+        let file = results_file::open(path)?;
+        let mut writer = ResultsWriter::create(&rewritten_path, compress)
+            .context("Failed to create rewritten results file")?;
+
+        for document in serde_json::Deserializer::from_reader(file).into_iter::<Value>() {
+            let mut document = match document {
+                Ok(document) => document,
+                Err(_) if annotations.incomplete => break,
+                Err(e) => return Err(anyhow::Error::new(e).context("Failed to parse scan results file")),
+            };
+            annotations.apply(&mut document);
+            if pretty {
+                serde_json::to_writer_pretty(&mut writer, &document)?;
+            } else {
+                serde_json::to_writer(&mut writer, &document)?;
+            }
+            writer.write_all(b"\n")?;
+        }
+        writer.finish().context("Failed to write rewritten results file")?;
+    }
+
+    std::fs::rename(&rewritten_path, path)
+        .context("Failed to replace scan results file")?;
+    Ok(())
+}
+
+/// The documents at the start of `output` that parse, for output cut short
+/// by stopping Thor, as an array however many there are.
+fn complete_documents(output: &[u8]) -> Value {
+    let mut documents = Vec::new();
+    for document in serde_json::Deserializer::from_slice(output).into_iter::<Value>() {
+        match document {
+            Ok(document) => documents.push(document),
+            Err(e) => {
+                log::warn!("Dropping the incomplete end of Thor's output: {}", e);
+                break;
+            }
+        }
+    }
+    Value::Array(documents)
+}
+
+/// Whether `output` is line-delimited JSON, as `thor --json` writes it: the
+/// first line that starts a JSON object or array is a complete document.
+/// Log lines before it are allowed.
+fn is_json_lines(output: &[u8]) -> bool {
+    output.split(|b| *b == b'\n')
+        .map(<[u8]>::trim_ascii)
+        .find(|line| line.starts_with(b"{") || line.starts_with(b"["))
+        .is_some_and(|line| serde_json::from_slice::<Value>(line).is_ok())
+}
+
+/// Every document in line-delimited JSON `output`, as an array.
+fn parse_json_lines(output: &[u8]) -> Value {
+    Value::Array(output.split(|b| *b == b'\n').filter_map(parse_json_line).collect())
+}
+
+/// One line of line-delimited JSON output. Blank lines are `None`, and so,
+/// with a warning, are lines that aren't JSON, such as Thor log messages or
+/// a last line cut off by stopping Thor.
+fn parse_json_line(line: &[u8]) -> Option<Value> {
+    let line = line.trim_ascii();
+    if line.is_empty() {
+        return None;
+    }
+    match serde_json::from_slice(line) {
+        Ok(document) => Some(document),
+        Err(e) => {
+            log::warn!("Skipping non-JSON line in Thor output ({}): {}", e, String::from_utf8_lossy(line));
+            None
+        }
+    }
+}
+
+fn default_extract_workers() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Copies everything read from `inner` into `copy`.
+struct TeeReader<R, W> {
+    inner: R,
+    copy: W,
+}
+
+impl<R: Read, W: Write> Read for TeeReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.copy.write_all(&buf[..n])?;
+        Ok(n)
+    }
 }
 
 impl ThorScanner {
@@ -25,19 +649,166 @@ impl ThorScanner {
             platform,
             temp_dir: None,
             redb_hook: None,
-            enterprise_mode: false,
+            clock: crate::clock::system(),
+            export_redb_rules: false,
+            max_buffer_bytes: DEFAULT_MAX_BUFFER_BYTES,
+            extract_workers: default_extract_workers(),
+            extract_limits: ExtractLimits::default(),
+            retry_policy: RetryPolicy::default(),
+            pretty_output: false,
+            thor_output_file: false,
+            thor_location: None,
+            temp_base: None,
+            allowed_extract_roots: Vec::new(),
+            exclude_paths: Vec::new(),
+            max_file_size: None,
+            scan_timeout: None,
+            archive_options: None,
+            compress_output: false,
+            progress_interval: None,
+            risk_config: RiskConfig::default(),
         }
     }
 
-    pub fn with_enterprise_mode(mut self, enabled: bool) -> Self {
-        self.enterprise_mode = enabled;
+    /// Reports Thor's progress lines from stderr while it runs; `interval`
+    /// is how often they are logged when stderr isn't a terminal.
+    pub fn with_progress(mut self, interval: Option<Duration>) -> Self {
+        self.progress_interval = interval;
+        self
+    }
+
+    /// Mapping used for the findings' risk in the results summary.
+    pub fn with_risk_config(mut self, risk_config: RiskConfig) -> Self {
+        self.risk_config = risk_config;
+        self
+    }
+
+    /// Expands archives under the scan path before scanning and scans their
+    /// contents too; findings in them are traced back to the archive.
+    pub fn with_archive_expansion(mut self, options: Option<ArchiveOptions>) -> Self {
+        self.archive_options = options;
+        self
+    }
+
+    /// Gzips the results file; its name is expected to end in `.gz` already.
+    pub fn with_compressed_output(mut self, enabled: bool) -> Self {
+        self.compress_output = enabled;
+        self
+    }
+
+    pub fn with_max_buffer_bytes(mut self, max_buffer_bytes: u64) -> Self {
+        self.max_buffer_bytes = max_buffer_bytes;
+        self
+    }
+
+    /// Writes the results file as indented JSON with sorted keys instead of
+    /// Thor's raw output.
+    pub fn with_pretty_output(mut self, enabled: bool) -> Self {
+        self.pretty_output = enabled;
+        self
+    }
+
+    /// Has Thor write its results to a file in the temp dir, which is read
+    /// instead of stdout.
+    pub fn with_thor_output_file(mut self, enabled: bool) -> Self {
+        self.thor_output_file = enabled;
+        self
+    }
+
+    /// Creates the scan's temp dir under `temp_base` instead of the system
+    /// temp directory.
+    pub fn with_temp_base(mut self, temp_base: Option<PathBuf>) -> Self {
+        self.temp_base = temp_base;
+        self
+    }
+
+    /// Refuses to extract anywhere that, once symlinks are resolved, is not
+    /// under one of `roots`. An empty list allows any location.
+    pub fn with_allowed_extract_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.allowed_extract_roots = roots;
         self
     }
 
-    pub async fn enable_redb_optimization(&mut self, db_path: &str) -> Result<()> {
+    /// Directories Thor is told to skip. Those that don't exist here, like
+    /// the Windows defaults on Linux, are left out.
+    pub fn with_exclude_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.exclude_paths = paths;
+        self
+    }
+
+    /// Has Thor skip files larger than `bytes`; `None` leaves Thor's own limit.
+    pub fn with_max_file_size(mut self, bytes: Option<u64>) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// Stops Thor and fails the scan when one run takes longer than `timeout`.
+    pub fn with_scan_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.scan_timeout = timeout;
+        self
+    }
+
+    /// Checks `path` against `allowed_extract_roots` after canonicalizing both.
+    fn check_extract_location(&self, path: &Path) -> Result<()> {
+        if self.allowed_extract_roots.is_empty() {
+            return Ok(());
+        }
+
+        let canonical = std::fs::canonicalize(path)
+            .with_context(|| format!("Failed to resolve extraction path: {}", path.display()))?;
+
+        for root in &self.allowed_extract_roots {
+            match std::fs::canonicalize(root) {
+                Ok(root) if canonical.starts_with(&root) => return Ok(()),
+                Ok(_) => {}
+                Err(e) => log::warn!("Ignoring unusable allowed extract root {}: {}", root.display(), e),
+            }
+        }
+
+        Err(anyhow::anyhow!(
+            "Refusing to extract into {} (resolves to {}): not under any of scanning.allowed_extract_roots",
+            path.display(), canonical.display()
+        ))
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the number of extraction threads; `0` uses one per available CPU.
+    pub fn with_extract_workers(mut self, workers: usize) -> Self {
+        self.extract_workers = if workers == 0 { default_extract_workers() } else { workers };
+        self
+    }
+
+    pub fn with_extract_limits(mut self, limits: ExtractLimits) -> Self {
+        self.extract_limits = limits;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Scans with the rules exported from ReDB instead of `rules_path` once
+    /// ReDB optimization is enabled.
+    pub fn with_redb_rule_export(mut self, enabled: bool) -> Self {
+        self.export_redb_rules = enabled;
+        self
+    }
+
+    pub async fn enable_redb_optimization(&mut self, db_path: &str, database: &DatabaseConfig) -> Result<()> {
         log::info!("🔧 Initializing ReDB optimization for YARA rules");
         let redb_hook = initialize_yara_rules_hook(db_path).await
-            .context("Failed to initialize ReDB hook")?;
+            .context("Failed to initialize ReDB hook")?
+            .with_integrity_policy(database.integrity_policy)
+            .with_duplicate_name_policy(database.duplicate_rule_names)
+            .with_content_dedup(database.dedup_rule_bodies)
+            .with_rule_compression(database.compress_rule_bodies)
+            .with_skip_corrupt_records(database.skip_corrupt_records)
+            .with_clock(self.clock.clone());
         
         self.redb_hook = Some(redb_hook);
         log::info!("✅ ReDB optimization enabled");
@@ -46,10 +817,14 @@ impl ThorScanner {
 
     pub async fn prepare_environment(&mut self) -> Result<PathBuf> {
         // Create temporary directory
-        let temp_dir = tempfile::tempdir()
-            .context("Failed to create temporary directory")?;
+        let temp_dir = match &self.temp_base {
+            Some(temp_base) => tempfile::tempdir_in(temp_base),
+            None => tempfile::tempdir(),
+        }
+        .context("Failed to create temporary directory")?;
         
         let temp_path = temp_dir.path().to_path_buf();
+        self.check_extract_location(&temp_path)?;
         
         // Add Windows Defender exclusion if on Windows
         #[cfg(windows)]
@@ -65,151 +840,643 @@ impl ThorScanner {
         Ok(temp_path)
     }
 
-    pub async fn extract_thor_package(&self, package_path: &Path, extract_to: &Path) -> Result<()> {
+    /// Extracts the package using up to `extract_workers` threads.
+    ///
+    /// Each worker opens its own handle on the archive and claims entries from a
+    /// shared counter, so the result doesn't depend on extraction order. The
+    /// first failing entry stops the remaining workers and is reported.
+    pub async fn extract_thor_package(&mut self, package_path: &Path, extract_to: &Path) -> Result<()> {
+        // Checked again here in case the directory was swapped since creation
+        self.check_extract_location(extract_to)?;
+        self.unpack_thor_package(package_path, extract_to).await?;
+
+        let binary_name = self.platform.get_thor_binary_name();
+        let (thor_binary, package_root) = locate_thor_binary(extract_to, &binary_name)?;
+        if package_root != extract_to {
+            log::info!("Thor package is nested, using base directory: {}", package_root.display());
+        }
+        self.thor_location = Some((thor_binary, package_root));
+        Ok(())
+    }
+
+    async fn unpack_thor_package(&self, package_path: &Path, extract_to: &Path) -> Result<()> {
         log::info!("Extracting Thor package to: {}", extract_to.display());
 
-        let file = std::fs::File::open(package_path)
-            .context("Failed to open Thor package")?;
-        
-        let mut archive = zip::ZipArchive::new(file)
-            .context("Failed to read ZIP archive")?;
+        let entry_count = {
+            let file = std::fs::File::open(package_path)
+                .context("Failed to open Thor package")?;
+            zip::ZipArchive::new(file)
+                .context("Failed to read ZIP archive")?
+                .len()
+        };
 
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i)
-                .context("Failed to read file from archive")?;
-            
-            let outpath = extract_to.join(file.name());
+        let workers = self.extract_workers.clamp(1, entry_count.max(1));
+        log::debug!("Extracting {} entries with {} workers", entry_count, workers);
 
-            if file.name().ends_with('/') {
-                // Directory
-                fs::create_dir_all(&outpath).await
-                    .context("Failed to create directory")?;
-            } else {
-                // File
-                if let Some(parent) = outpath.parent() {
-                    fs::create_dir_all(parent).await
-                        .context("Failed to create parent directory")?;
-                }
+        let package_path = package_path.to_path_buf();
+        // Entry paths are checked against the resolved directory
+        let extract_to = std::fs::canonicalize(extract_to)
+            .with_context(|| format!("Failed to resolve extraction directory: {}", extract_to.display()))?;
+        let platform = self.platform.clone();
+        let limits = self.extract_limits.clone();
 
-                let mut outfile = std::fs::File::create(&outpath)
-                    .context("Failed to create output file")?;
-                
-                std::io::copy(&mut file, &mut outfile)
-                    .context("Failed to extract file")?;
-            }
+        tokio::task::spawn_blocking(move || {
+            let next_entry = AtomicUsize::new(0);
+            let failed = AtomicBool::new(false);
+            let extracted_bytes = AtomicU64::new(0);
 
-            // Set executable permissions on Unix systems
-            #[cfg(unix)]
-            if self.platform.is_unix() && file.name().contains("thor-lite") {
-                if let Err(e) = crate::platform::unix::set_executable_permissions(
-                    outpath.to_str().unwrap()
-                ) {
-                    log::warn!("Failed to set executable permissions: {}", e);
-                }
-            }
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = (0..workers)
+                    .map(|_| scope.spawn(|| -> Result<()> {
+                        let file = std::fs::File::open(&package_path)
+                            .context("Failed to open Thor package")?;
+                        let mut archive = zip::ZipArchive::new(file)
+                            .context("Failed to read ZIP archive")?;
+
+                        loop {
+                            let i = next_entry.fetch_add(1, Ordering::Relaxed);
+                            if i >= entry_count || failed.load(Ordering::Relaxed) {
+                                return Ok(());
+                            }
+
+                            if let Err(e) = extract_entry(&mut archive, i, &extract_to, &platform, &limits, &extracted_bytes) {
+                                failed.store(true, Ordering::Relaxed);
+                                return Err(e);
+                            }
+                        }
+                    }))
+                    .collect();
+
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap_or_else(|_| Err(anyhow::anyhow!("Extraction worker panicked"))))
+                    .collect::<Result<Vec<_>>>()
+                    .map(|_| ())
+            })
+        })
+        .await
+        .context("Extraction task failed")?
+    }
+
+    /// Locates the configured license file, if any.
+    ///
+    /// Relative paths are looked up in the extracted package first (see
+    /// `resolve_config_path`). An empty `license_path` means no license is
+    /// passed, for Thor builds that don't need one, and so does the default
+    /// path when the package has no file there.
+    fn resolve_license(&self, package_root: &Path) -> Result<Option<PathBuf>> {
+        if self.config.license_path.is_empty() {
+            return Ok(None);
         }
 
-        Ok(())
+        let license = resolve_config_path(package_root, &self.config.license_path);
+        if !license.exists() && self.config.license_path == DEFAULT_LICENSE_PATH {
+            log::info!("No Thor license at {}, scanning without one", license.display());
+            return Ok(None);
+        }
+        if !license.is_file() {
+            return Err(anyhow::anyhow!(
+                "Thor license file not found: {} (thor.license_path = {:?})",
+                license.display(), self.config.license_path
+            ));
+        }
+        std::fs::File::open(&license)
+            .with_context(|| format!("Thor license file is not readable: {}", license.display()))?;
+
+        Ok(Some(license))
     }
 
-    pub async fn run_scan(&self, scan_path: &str, output_path: &str) -> Result<Value> {
+    /// Picks the custom signatures directory for the scan.
+    ///
+    /// With ReDB rule export enabled the stored rules are written to a fresh
+    /// directory under the temp dir and used instead of `rules_path`.
+    /// Otherwise `rules_path` is resolved like the license and must be a
+    /// directory; an empty `rules_path` leaves Thor's default. The scan tier
+    /// only filters exported rules.
+    async fn prepare_signatures(&self, temp_path: &Path, package_root: &Path, scan_tier: Option<ScanTier>) -> Result<Option<PathBuf>> {
+        if let (true, Some(redb_hook)) = (self.export_redb_rules, &self.redb_hook) {
+            let export_dir = temp_path.join(REDB_EXPORT_DIR);
+            export_yara_rules_to_directory(redb_hook, &export_dir.join("yara"), scan_tier).await
+                .context("Failed to export ReDB rules")?;
+            return Ok(Some(export_dir));
+        }
+
+        if self.config.rules_path.is_empty() {
+            return Ok(None);
+        }
+
+        let rules_dir = resolve_config_path(package_root, &self.config.rules_path);
+        if !rules_dir.exists() && self.config.rules_path == DEFAULT_RULES_PATH {
+            log::info!("No custom signatures at {}, using Thor's built-in rules", rules_dir.display());
+            return Ok(None);
+        }
+        if !rules_dir.is_dir() {
+            return Err(anyhow::anyhow!(
+                "Thor rules directory not found: {} (thor.rules_path = {:?})",
+                rules_dir.display(), self.config.rules_path
+            ));
+        }
+        Ok(Some(rules_dir))
+    }
+
+    pub async fn run_scan(&self, ctx: &ScanContext) -> Result<ScanResults> {
+        let output_path = ctx.output_path.as_str();
+
         let temp_path = self.temp_dir.as_ref()
             .context("Temporary directory not initialized")?
             .path();
 
-        let thor_binary = temp_path
-            .join("Thor")
-            .join(self.platform.get_thor_binary_name());
+        let (thor_binary, package_root) = match &self.thor_location {
+            Some((thor_binary, package_root)) => (thor_binary.clone(), package_root.as_path()),
+            None => (temp_path.join("Thor").join(self.platform.get_thor_binary_name()), temp_path),
+        };
 
         if !thor_binary.exists() {
             return Err(anyhow::anyhow!("Thor binary not found: {}", thor_binary.display()));
         }
 
-        if self.enterprise_mode {
+        if ctx.enterprise_mode {
             log::info!("🚀 Running Thor Enterprise scan with binary: {}", thor_binary.display());
         } else {
             log::info!("Running Thor scan with binary: {}", thor_binary.display());
         }
 
-        let mut cmd = Command::new(&thor_binary);
-        
-        // Add configuration flags
-        for flag in &self.config.flags {
-            cmd.arg(flag);
+        let mut invocation = ThorInvocation {
+            enterprise_mode: ctx.enterprise_mode,
+            redb_optimized: self.redb_hook.is_some(),
+            rebase_dir: package_root.to_path_buf(),
+            ..Default::default()
+        };
+
+        if let Some(license) = self.resolve_license(package_root)? {
+            log::info!("Using Thor license: {}", license.display());
+            invocation.license = Some(license);
         }
 
-        // Add enterprise-specific flags
-        if self.enterprise_mode {
-            cmd.arg("--enterprise-mode");
-            cmd.arg("--ai-enhanced");
-            
-            if self.redb_hook.is_some() {
-                cmd.arg("--redb-optimized");
-                log::info("🔧 ReDB optimization enabled for scan");
+        if let Some(rules_dir) = self.prepare_signatures(temp_path, package_root, ctx.scan_tier).await? {
+            log::info!("Using custom signatures from: {}", rules_dir.display());
+            invocation.custom_signatures = Some(rules_dir);
+        }
+
+        if !self.config.config_path.is_empty() {
+            let thor_config = resolve_config_path(package_root, &self.config.config_path);
+            if !thor_config.exists() && self.config.config_path == DEFAULT_THOR_CONFIG_PATH {
+                log::info!("No Thor config at {}, running without --template", thor_config.display());
+            } else if !thor_config.is_file() {
+                return Err(anyhow::anyhow!(
+                    "Thor config file not found: {} (thor.config_path = {:?})",
+                    thor_config.display(), self.config.config_path
+                ));
+            } else {
+                log::info!("Using Thor config: {}", thor_config.display());
+                invocation.template = Some(thor_config);
             }
         }
 
-        // Add scan path
-        cmd.arg("--path").arg(scan_path);
-        
-        // Add rebase directory
-        cmd.arg("--rebase-dir").arg(temp_path);
+        if ctx.enterprise_mode && self.redb_hook.is_some() {
+            log::info!("🔧 ReDB optimization enabled for scan");
+        }
 
-        // Set working directory
-        cmd.current_dir(temp_path);
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
+        let thor_output = self.thor_output_file.then(|| temp_path.join(THOR_OUTPUT_FILE));
+        invocation.json_file = thor_output.clone();
 
-        if self.enterprise_mode {
-            log::info!("🎯 Executing enterprise command: {:?}", cmd);
-        } else {
-            log::info!("Executing command: {:?}", cmd);
-        }
+        invocation.scan_paths.extend(ctx.scan_paths.iter().map(PathBuf::from));
+        invocation.exclude_paths = existing_exclude_paths(&self.exclude_paths);
+        invocation.max_file_size = self.max_file_size;
+
+        let archives = match &self.archive_options {
+            Some(options) => {
+                let mut options = options.clone();
+                // The Thor package itself lives here
+                options.exclude.push(temp_path.to_path_buf());
+
+                let work_dir = temp_path.join(ARCHIVE_EXTRACT_DIR);
+                let max_total_bytes = options.max_total_bytes;
+                let mut expansion = ArchiveExpansion::default();
+                for (index, scan_path) in ctx.scan_paths.iter().enumerate() {
+                    // Each scan path extracts into its own directory and gets
+                    // what is left of the size limit
+                    options.max_total_bytes = max_total_bytes.saturating_sub(expansion.bytes_extracted);
+                    let expanded = expand_archives(Path::new(scan_path), &work_dir.join(index.to_string()), &options)
+                        .with_context(|| format!("Failed to expand archives in {}", scan_path))?;
+                    expansion.bytes_extracted += expanded.bytes_extracted;
+                    expansion.truncated |= expanded.truncated;
+                    expansion.extracted.extend(expanded.extracted);
+                }
+                if expansion.extracted.is_empty() {
+                    None
+                } else {
+                    log::info!("Scanning the contents of {} archives ({} bytes extracted)",
+                              expansion.extracted.len(), expansion.bytes_extracted);
+                    invocation.scan_paths.push(work_dir);
+                    Some(expansion)
+                }
+            }
+            None => None,
+        };
+
+        let args = thor_args(&self.config, &invocation);
+        let thor_command = || {
+            let mut cmd = Command::new(&thor_binary);
+            cmd.args(&args);
 
-        let output = cmd.output()
-            .context("Failed to execute Thor scanner")?;
+            // Set working directory
+            cmd.current_dir(package_root);
+            cmd.stdout(Stdio::piped());
+            cmd.stderr(Stdio::piped());
+            cmd
+        };
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!("Thor scan failed: {}", stderr));
+        if ctx.enterprise_mode {
+            log::info!("🎯 Executing enterprise command: {:?}", thor_command());
+        } else {
+            log::info!("Executing command: {:?}", thor_command());
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        
-        // Parse JSON output
-        let scan_results: Value = serde_json::from_str(&stdout)
-            .context("Failed to parse Thor output as JSON")?;
+        // Retries share the budget
+        let deadline = ctx.time_budget.map(|budget| {
+            log::info!("Time budget: Thor is stopped after {}s", budget.as_secs());
+            Instant::now() + budget
+        });
 
-        // Save results to file
-        fs::write(output_path, &stdout).await
-            .context("Failed to write scan results")?;
+        let archives = Arc::new(archives);
+        let mut attempt = 0;
+        let results = loop {
+            match self.run_thor(thor_command(), ctx, thor_output.clone(), archives.clone(), deadline).await {
+                Ok(results) => break results,
+                Err(e) => {
+                    let retriable = e.downcast_ref::<ThorExitError>()
+                        .is_some_and(|exit| self.retry_policy.is_retriable(exit));
+                    let out_of_time = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+                    if !retriable || attempt >= self.retry_policy.max_retries || out_of_time {
+                        return Err(e);
+                    }
 
-        if self.enterprise_mode {
+                    attempt += 1;
+                    log::warn!("Thor failed with a retriable error, retrying in {}s (retry {}/{}): {}",
+                              self.retry_policy.delay.as_secs(), attempt, self.retry_policy.max_retries, e);
+                    tokio::time::sleep(self.retry_policy.delay).await;
+                }
+            }
+        };
+
+        if ctx.enterprise_mode {
             log::info!("🎯 Enterprise scan results saved to: {}", output_path);
             
             // Update ReDB with scan metadata if enabled
             if let Some(redb_hook) = &self.redb_hook {
                 if let Ok(stats) = redb_hook.get_database_stats().await {
-                    log::info!("📊 ReDB Stats - Rules: {}, Intel: {}", 
-                              stats.yara_rules_count, stats.threat_intel_count);
+                    log::info!("📊 ReDB Stats - Rules: {}, Intel: {}, Blob dedup ratio: {:.2}", 
+                              stats.yara_rules_count, stats.threat_intel_count, stats.dedup_ratio());
                 }
             }
         } else {
             log::info!("Scan results saved to: {}", output_path);
         }
+        if results.incomplete {
+            log::warn!("Thor was stopped by the time budget; the results cover only part of the scan path");
+        }
+
+        // Feed per-rule match counts back into the rule metadata
+        if let Some(redb_hook) = &self.redb_hook {
+            if !results.summary.by_rule.is_empty() {
+                match redb_hook.record_detections(&results.summary.by_rule, redb_hook.now()).await {
+                    Ok(updated) => log::info!("Updated detection counts for {} rules", updated),
+                    Err(e) => log::warn!("Failed to record rule detections: {}", e),
+                }
+            }
+        }
+
+        Ok(results)
+    }
 
-        Ok(scan_results)
+    fn output_settings(&self) -> OutputSettings {
+        OutputSettings {
+            success_exit_codes: self.config.success_exit_codes.clone(),
+            progress_interval: self.progress_interval,
+            max_buffer_bytes: self.max_buffer_bytes,
+            pretty_output: self.pretty_output,
+            compress_output: self.compress_output,
+            risk_config: self.risk_config.clone(),
+        }
     }
 
+    /// Runs one Thor attempt on a blocking thread. When the scan timeout
+    /// passes first Thor is asked to stop, killed if it is still running
+    /// `TIME_BUDGET_GRACE` later, and waited for, so no process is left
+    /// behind.
+    async fn run_thor(
+        &self,
+        mut cmd: Command,
+        ctx: &ScanContext,
+        thor_output: Option<PathBuf>,
+        archives: Arc<Option<ArchiveExpansion>>,
+        deadline: Option<Instant>,
+    ) -> Result<ScanResults> {
+        let settings = self.output_settings();
+        let process = Arc::new(Mutex::new(ThorProcess::Pending));
+        let task_process = process.clone();
+        let ctx = ctx.clone();
+        let mut task = tokio::task::spawn_blocking(move || {
+            settings.collect_output(&mut cmd, &ctx, thor_output.as_deref(), archives.as_ref().as_ref(), deadline, &task_process)
+        });
+
+        let Some(timeout) = self.scan_timeout else {
+            return task.await.context("Thor task failed")?;
+        };
+        if let Ok(finished) = tokio::time::timeout(timeout, &mut task).await {
+            return finished.context("Thor task failed")?;
+        }
+
+        log::warn!("Scan timeout reached, stopping Thor");
+        let running = ThorProcess::signal(&process, terminate_process);
+        let stopped = running && tokio::time::timeout(TIME_BUDGET_GRACE, &mut task).await.is_ok();
+        if !stopped {
+            if running {
+                log::warn!("Thor still running {}s after being asked to stop, killing it", TIME_BUDGET_GRACE.as_secs());
+                ThorProcess::signal(&process, kill_process);
+            }
+            let _ = task.await;
+        }
+        Err(anyhow::anyhow!("Thor scan timed out after {}s", timeout.as_secs()))
+    }
+}
+
+impl OutputSettings {
+    /// Runs Thor and writes its stdout to `output_path`.
+    ///
+    /// Output up to `max_buffer_bytes` is read fully and parsed in one go. Larger
+    /// output is copied to the file as it arrives while each JSON document is
+    /// parsed and summarised individually, so memory stays bounded for Thor's
+    /// line-delimited `--json` output (a single huge JSON array is still parsed
+    /// as one document).
+    ///
+    /// At `deadline` Thor is stopped and whatever it produced until then is
+    /// kept, with the results marked incomplete.
+    fn collect_output(
+        &self,
+        cmd: &mut Command,
+        ctx: &ScanContext,
+        thor_output: Option<&Path>,
+        archives: Option<&ArchiveExpansion>,
+        deadline: Option<Instant>,
+        process: &Arc<Mutex<ThorProcess>>,
+    ) -> Result<ScanResults> {
+        if let Some(thor_output) = thor_output {
+            // Never pick up a previous attempt's file
+            let _ = std::fs::remove_file(thor_output);
+        }
+
+        let mut child = {
+            let mut state = process.lock().unwrap_or_else(|e| e.into_inner());
+            if matches!(*state, ThorProcess::Cancelled) {
+                return Err(anyhow::anyhow!("Thor scan cancelled before it started"));
+            }
+            let child = cmd.spawn()
+                .context("Failed to execute Thor scanner")?;
+            *state = ThorProcess::Running(child.id());
+            child
+        };
+        let watchdog = deadline.map(|deadline| BudgetWatchdog::start(child.id(), deadline));
+
+        // Drain stderr concurrently so a chatty Thor can't block on a full
+        // pipe, picking out progress lines as they arrive
+        let mut stderr = child.stderr.take().context("Thor stderr not captured")?;
+        let mut progress = ProgressReporter::new(self.progress_interval);
+        let stderr_reader = std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let mut chunk = [0u8; 8192];
+            let mut line_start = 0;
+            // Thor redraws its progress with `\r`, so that ends a line too
+            while let Ok(read @ 1..) = stderr.read(&mut chunk) {
+                buffer.extend_from_slice(&chunk[..read]);
+                while let Some(end) = buffer[line_start..].iter().position(|&b| b == b'\n' || b == b'\r') {
+                    progress.line(&String::from_utf8_lossy(&buffer[line_start..line_start + end]));
+                    line_start += end + 1;
+                }
+            }
+            progress.line(&String::from_utf8_lossy(&buffer[line_start..]));
+            progress.finish();
+            buffer
+        });
+
+        let stdout = child.stdout.take().context("Thor stdout not captured")?;
+        let success_exit_codes = self.success_exit_codes.clone();
+        let process = process.clone();
+        let wait_for_thor = move || -> Result<ThorExit> {
+            let status = child.wait().context("Failed to wait for Thor scanner")?;
+            *process.lock().unwrap_or_else(|e| e.into_inner()) = ThorProcess::Exited;
+            let stopped = watchdog.is_some_and(BudgetWatchdog::finish);
+            let stderr = stderr_reader.join().unwrap_or_default();
+            let stderr = String::from_utf8_lossy(&stderr).to_string();
+
+            if stopped {
+                log::warn!("Thor stopped by the time budget ({})", status);
+                return Ok(ThorExit { code: status.code(), stopped });
+            }
+            match status.code() {
+                Some(0) => Ok(ThorExit { code: Some(0), stopped }),
+                Some(code) if success_exit_codes.contains(&code) => {
+                    log::warn!("Thor completed with exit code {}", code);
+                    if !stderr.trim().is_empty() {
+                        log::warn!("Thor stderr: {}", stderr.trim());
+                    }
+                    Ok(ThorExit { code: Some(code), stopped })
+                }
+                exit_code => Err(ThorExitError { exit_code, stderr }.into()),
+            }
+        };
+
+        let Some(thor_output) = thor_output else {
+            return self.read_results(stdout, ctx, archives, wait_for_thor);
+        };
+
+        // Thor writes its results to the file; stdout only carries its log
+        let stdout_logger = std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                log::debug!("[thor] {}", line);
+            }
+        });
+        let finished = wait_for_thor();
+        let _ = stdout_logger.join();
+        let exit = finished?;
+
+        let file = match std::fs::File::open(thor_output) {
+            Ok(file) => file,
+            Err(_) if exit.stopped => return self.read_results(std::io::empty(), ctx, archives, || Ok(exit)),
+            Err(e) => return Err(anyhow::Error::new(e)
+                .context(format!("Thor did not write its output file: {}", thor_output.display()))),
+        };
+        self.read_results(file, ctx, archives, || Ok(exit))
+    }
+
+    /// Parses Thor's results from `output` and writes the results file.
+    ///
+    /// Output up to `max_buffer_bytes` is parsed in memory; anything larger is
+    /// copied to the results file while being summarized one document at a
+    /// time. Line-delimited JSON, one document per line as `thor --json`
+    /// writes it, may have non-JSON log lines between documents; they are
+    /// skipped. `finish` is called once `output` has been read to check that Thor
+    /// succeeded, and returns how it exited. Output of a Thor stopped by the
+    /// time budget is kept up to its last complete document.
+    fn read_results<R: Read>(
+        &self,
+        mut output: R,
+        ctx: &ScanContext,
+        archives: Option<&ArchiveExpansion>,
+        finish: impl FnOnce() -> Result<ThorExit>,
+    ) -> Result<ScanResults> {
+        let output_path = ctx.output_path.as_str();
+        let mut annotations = Annotations {
+            metadata: (!ctx.metadata.is_empty())
+                .then(|| serde_json::to_value(&ctx.metadata))
+                .transpose()?,
+            archives,
+            scan_paths: &ctx.scan_paths,
+            incomplete: false,
+        };
+
+        let mut buffer = Vec::new();
+        (&mut output).take(self.max_buffer_bytes + 1).read_to_end(&mut buffer)
+            .context("Failed to read Thor output")?;
+
+        if buffer.len() as u64 <= self.max_buffer_bytes {
+            let exit = finish()?;
+            annotations.incomplete = exit.stopped;
+
+            // Line-delimited output is collected into one array document,
+            // even when it is a single line; a single-line array stays as is
+            let json_lines = is_json_lines(&buffer);
+            let mut scan_results: Value = match serde_json::from_slice(&buffer) {
+                Ok(Value::Array(documents)) => Value::Array(documents),
+                _ if json_lines => parse_json_lines(&buffer),
+                Ok(document) => document,
+                Err(_) if exit.stopped => complete_documents(&buffer),
+                Err(e) => return Err(anyhow::Error::new(e).context("Failed to parse Thor output as JSON")),
+            };
+            annotations.apply(&mut scan_results);
+
+            let reserialized;
+            let contents = if self.pretty_output {
+                // serde_json's maps are ordered, so reserializing sorts the keys
+                reserialized = serde_json::to_vec_pretty(&scan_results)?;
+                &reserialized
+            } else if !annotations.is_empty() || json_lines {
+                reserialized = serde_json::to_vec(&scan_results)?;
+                &reserialized
+            } else {
+                &buffer
+            };
+
+            let mut writer = ResultsWriter::create(Path::new(output_path), self.compress_output)?;
+            writer.write_all(contents).context("Failed to write scan results")?;
+            writer.finish().context("Failed to write scan results")?;
+
+            return Ok(ScanResults {
+                summary: FindingsSummary::from_results(&scan_results, self.risk_config.clone()),
+                value: Some(scan_results),
+                thor_exit_code: exit.code,
+                incomplete: exit.stopped,
+            });
+        }
+
+        log::info!("Thor output exceeds {} bytes, streaming results to {}",
+                  self.max_buffer_bytes, output_path);
+
+        let mut file = ResultsWriter::create(Path::new(output_path), self.compress_output)
+            .context("Failed to create scan results file")?;
+        let mut summary = FindingsSummary::new(self.risk_config.clone());
+        let mut parse_error = None;
+
+        // The buffer may be too small to tell whether the output is
+        // line-delimited, so read up to the line that starts the first document
+        let mut output = BufReader::new(std::io::Cursor::new(buffer).chain(output));
+        let mut head = Vec::new();
+        loop {
+            let start = head.len();
+            if output.read_until(b'\n', &mut head).context("Failed to read Thor output")? == 0 {
+                break;
+            }
+            let line = head[start..].trim_ascii();
+            if line.starts_with(b"{") || line.starts_with(b"[") {
+                break;
+            }
+        }
+        let json_lines = is_json_lines(&head);
+        let output = std::io::Cursor::new(head).chain(output);
+
+        if json_lines {
+            // Only the lines that parse are written, so the results file holds
+            // one document per line without Thor's log lines
+            let mut reader = BufReader::new(output);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                if reader.read_until(b'\n', &mut line).context("Failed to read Thor output")? == 0 {
+                    break;
+                }
+                if let Some(document) = parse_json_line(&line) {
+                    summary.record_document(&document);
+                    file.write_all(line.trim_ascii())
+                        .and_then(|_| file.write_all(b"\n"))
+                        .context("Failed to write scan results")?;
+                }
+            }
+            file.finish().context("Failed to write scan results")?;
+        } else {
+            let mut reader = BufReader::new(TeeReader {
+                inner: output,
+                copy: file,
+            });
+
+            for document in serde_json::Deserializer::from_reader(&mut reader).into_iter::<Value>() {
+                match document {
+                    Ok(document) => summary.record_document(&document),
+                    Err(e) => {
+                        parse_error = Some(e);
+                        break;
+                    }
+                }
+            }
+
+            // Keep copying whatever is left so the results file stays complete
+            std::io::copy(&mut reader, &mut std::io::sink())
+                .context("Failed to write scan results")?;
+            reader.into_inner().copy.finish()
+                .context("Failed to write scan results")?;
+        }
+
+        let exit = match finish() {
+            Ok(exit) => exit,
+            Err(e) => {
+                let _ = std::fs::remove_file(output_path);
+                return Err(e);
+            }
+        };
+        annotations.incomplete = exit.stopped;
+
+        match parse_error {
+            Some(e) if exit.stopped => log::warn!("Dropping the incomplete end of Thor's output: {}", e),
+            Some(e) => return Err(anyhow::Error::new(e).context("Failed to parse Thor output as JSON")),
+            None => {}
+        }
+
+        if self.pretty_output || !annotations.is_empty() {
+            rewrite_results_file(Path::new(output_path), self.pretty_output, self.compress_output, &annotations)?;
+        }
+
+        Ok(ScanResults { summary, value: None, thor_exit_code: exit.code, incomplete: exit.stopped })
+    }
+}
+
+impl ThorScanner {
     pub async fn cleanup(&mut self) -> Result<()> {
+        // Remove Windows Defender exclusion if on Windows
+        #[cfg(windows)]
         if let Some(temp_dir) = &self.temp_dir {
-            let temp_path = temp_dir.path();
-
-            // Remove Windows Defender exclusion if on Windows
-            #[cfg(windows)]
             if self.platform.is_windows() {
                 if let Err(e) = crate::platform::windows::remove_defender_exclusion(
-                    temp_path.to_str().unwrap()
+                    temp_dir.path().to_str().unwrap()
                 ) {
                     log::warn!("Failed to remove Windows Defender exclusion: {}", e);
                 }
@@ -222,4 +1489,311 @@ impl ThorScanner {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A config that needs nothing from the package but the binary.
+    fn thor_config() -> ThorConfig {
+        ThorConfig {
+            binary_path: String::new(),
+            license_path: String::new(),
+            rules_path: String::new(),
+            config_path: String::new(),
+            flags: vec!["--json".to_string()],
+            success_exit_codes: vec![0],
+        }
+    }
+
+    /// A scanner with a prepared temp dir whose Thor binary is a shell
+    /// script running `script`.
+    #[cfg(unix)]
+    async fn fake_thor(config: ThorConfig, script: &str) -> ThorScanner {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut scanner = ThorScanner::new(config);
+        let temp_path = scanner.prepare_environment().await.unwrap();
+        let binary = temp_path.join("Thor").join(scanner.platform.get_thor_binary_name());
+        std::fs::create_dir_all(binary.parent().unwrap()).unwrap();
+        std::fs::write(&binary, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        scanner
+    }
+
+    /// Thor output echoing its arguments, for checking the command line.
+    #[cfg(unix)]
+    const ECHO_ARGS: &str = r#"printf '{"args":"%s"}\n' "$*""#;
+
+    /// The arguments `ECHO_ARGS` printed, from its one-line results array.
+    #[cfg(unix)]
+    fn echoed_args(results: ScanResults) -> String {
+        results.value.unwrap()[0]["args"].as_str().unwrap().to_string()
+    }
+
+    fn context(output_dir: &Path, scan_paths: &[&str]) -> ScanContext {
+        let scan_paths: Vec<String> = scan_paths.iter().map(|path| path.to_string()).collect();
+        let output_path = output_dir.join("results.json");
+        ScanContext::new(&scan_paths, output_path.to_str().unwrap(), chrono::Utc::now())
+    }
+
+    #[test]
+    fn json_lines_skip_garbage() {
+        let output = b"{\"id\":1}\n{\"id\":2}\nThor: scanning /data\n\n{\"id\":3}\n";
+        assert!(is_json_lines(output));
+
+        let parsed = parse_json_lines(output);
+        let ids: Vec<i64> = parsed.as_array().unwrap().iter()
+            .map(|document| document["id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(ids, [1, 2, 3]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn single_line_json_output_is_still_an_array() {
+        let dir = tempfile::tempdir().unwrap();
+        let scanner = fake_thor(thor_config(), r#"echo 'Thor: scanning'; echo '{"id":1}'"#).await;
+
+        let results = scanner.run_scan(&context(dir.path(), &["/data"])).await.unwrap();
+
+        assert_eq!(results.value.unwrap(), serde_json::json!([{"id": 1, "scan_path": "/data"}]));
+        let written: Value = serde_json::from_slice(&std::fs::read(dir.path().join("results.json")).unwrap()).unwrap();
+        assert_eq!(written, serde_json::json!([{"id": 1, "scan_path": "/data"}]));
+    }
+
+    #[test]
+    fn every_finding_gets_its_scan_path() {
+        let annotate = |scan_paths: &[String], mut document: Value| {
+            Annotations { metadata: None, archives: None, scan_paths, incomplete: false }.apply(&mut document);
+            document
+        };
+
+        let several = ["/srv".to_string(), "/srv/data".to_string(), "/home".to_string()];
+        let document = annotate(&several, serde_json::json!([
+            {"file": "/srv/data/a.exe"},
+            {"file": "/srv/b.exe"},
+            {"file": "/opt/c.exe"},
+        ]));
+        assert_eq!(document, serde_json::json!([
+            {"file": "/srv/data/a.exe", "scan_path": "/srv/data"},
+            {"file": "/srv/b.exe", "scan_path": "/srv"},
+            {"file": "/opt/c.exe"},
+        ]));
+
+        let one = ["data".to_string()];
+        let document = annotate(&one, serde_json::json!({"file": "/abs/data/a.exe"}));
+        assert_eq!(document, serde_json::json!({"file": "/abs/data/a.exe", "scan_path": "data"}));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn differently_named_thor_binary_is_found() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let thor_dir = dir.path().join("Thor");
+        std::fs::create_dir(&thor_dir).unwrap();
+        std::fs::write(thor_dir.join("thor-lite-license.lic"), "license").unwrap();
+        let binary = thor_dir.join("thor-lite_amd64");
+        std::fs::write(&binary, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let (found, package_root) = locate_thor_binary(dir.path(), "thor-lite_x86_64").unwrap();
+        assert_eq!(found, binary);
+        assert_eq!(package_root, dir.path());
+    }
+
+    /// A zip at `dir/package.zip` holding one small file per entry name.
+    fn zip_with_entries(dir: &Path, names: &[&str]) -> PathBuf {
+        let path = dir.join("package.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        for name in names {
+            zip.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            zip.write_all(b"payload").unwrap();
+        }
+        zip.finish().unwrap();
+        path
+    }
+
+    /// Unpacks a package with `entry` into `root/extract` and checks it is
+    /// refused without writing into `root/outside`.
+    async fn assert_entry_refused(root: &Path, entry: &str) {
+        let extract_to = root.join("extract");
+        let outside = root.join("outside");
+        std::fs::create_dir_all(&extract_to).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        let package = zip_with_entries(root, &["Thor/readme.txt", entry]);
+
+        let scanner = ThorScanner::new(thor_config());
+        let error = scanner.unpack_thor_package(&package, &extract_to).await.unwrap_err();
+        assert!(error.to_string().contains("escapes extraction directory"), "{:#}", error);
+        assert_eq!(std::fs::read_dir(&outside).unwrap().count(), 0, "wrote outside for {}", entry);
+    }
+
+    #[tokio::test]
+    async fn package_entries_climbing_out_are_refused() {
+        let root = tempfile::tempdir().unwrap();
+        assert_entry_refused(root.path(), "../outside/x").await;
+    }
+
+    #[tokio::test]
+    async fn absolute_package_entries_are_refused() {
+        let root = tempfile::tempdir().unwrap();
+        let entry = root.path().join("outside").join("x");
+        assert_entry_refused(root.path(), entry.to_str().unwrap()).await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn package_entries_under_a_symlinked_directory_are_refused() {
+        let root = tempfile::tempdir().unwrap();
+        let extract_to = root.path().join("extract");
+        std::fs::create_dir_all(&extract_to).unwrap();
+        std::os::unix::fs::symlink(root.path().join("outside"), extract_to.join("link")).unwrap();
+        assert_entry_refused(root.path(), "link/x").await;
+    }
+
+    #[tokio::test]
+    async fn package_entries_inside_are_extracted() {
+        let root = tempfile::tempdir().unwrap();
+        let package = zip_with_entries(root.path(), &["Thor/readme.txt"]);
+
+        let scanner = ThorScanner::new(thor_config());
+        scanner.unpack_thor_package(&package, root.path()).await.unwrap();
+        assert_eq!(std::fs::read(root.path().join("Thor/readme.txt")).unwrap(), b"payload");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn missing_default_license_scans_without_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ThorConfig { license_path: DEFAULT_LICENSE_PATH.to_string(), ..thor_config() };
+        let scanner = fake_thor(config, ECHO_ARGS).await;
+
+        let results = scanner.run_scan(&context(dir.path(), &["/data"])).await.unwrap();
+        assert!(!echoed_args(results).contains("--licensefile"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn missing_configured_license_fails_the_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ThorConfig { license_path: "site.lic".to_string(), ..thor_config() };
+        let scanner = fake_thor(config, ECHO_ARGS).await;
+
+        let error = scanner.run_scan(&context(dir.path(), &["/data"])).await.unwrap_err();
+        assert!(error.to_string().contains("Thor license file not found"), "{:#}", error);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn missing_default_rules_and_config_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ThorConfig {
+            rules_path: DEFAULT_RULES_PATH.to_string(),
+            config_path: DEFAULT_THOR_CONFIG_PATH.to_string(),
+            ..thor_config()
+        };
+        let scanner = fake_thor(config, ECHO_ARGS).await;
+
+        let results = scanner.run_scan(&context(dir.path(), &["/data"])).await.unwrap();
+        let args = echoed_args(results);
+        assert!(!args.contains("--customsigs"), "{}", args);
+        assert!(!args.contains("--template"), "{}", args);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn missing_configured_rules_or_config_fail_the_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ThorConfig { rules_path: "site-rules".to_string(), ..thor_config() };
+        let scanner = fake_thor(config, ECHO_ARGS).await;
+        let error = scanner.run_scan(&context(dir.path(), &["/data"])).await.unwrap_err();
+        assert!(error.to_string().contains("Thor rules directory not found"), "{:#}", error);
+
+        let config = ThorConfig { config_path: "site.yml".to_string(), ..thor_config() };
+        let scanner = fake_thor(config, ECHO_ARGS).await;
+        let error = scanner.run_scan(&context(dir.path(), &["/data"])).await.unwrap_err();
+        assert!(error.to_string().contains("Thor config file not found"), "{:#}", error);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn existing_exclude_paths_are_passed_to_thor() {
+        let dir = tempfile::tempdir().unwrap();
+        let excluded = dir.path().join("excluded");
+        std::fs::create_dir(&excluded).unwrap();
+        let scanner = fake_thor(thor_config(), ECHO_ARGS).await
+            .with_exclude_paths(vec![excluded.clone(), PathBuf::from("/no/such/exclude")]);
+
+        let results = scanner.run_scan(&context(dir.path(), &["/data"])).await.unwrap();
+        let args = echoed_args(results);
+        assert!(args.contains(&format!("--exclude-path {}", excluded.display())), "{}", args);
+        assert!(!args.contains("/no/such/exclude"), "{}", args);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn max_file_size_is_passed_in_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut scanning = crate::config::PyroConfig::default().scanning;
+        scanning.max_file_size_mb = 50;
+        let scanner = fake_thor(thor_config(), ECHO_ARGS).await
+            .with_max_file_size(scanning.max_file_size_bytes());
+
+        let results = scanner.run_scan(&context(dir.path(), &["/data"])).await.unwrap();
+        let args = echoed_args(results);
+        assert!(args.contains("--max_file_size 52428800"), "{}", args);
+
+        scanning.max_file_size_mb = 0;
+        assert_eq!(scanning.max_file_size_bytes(), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn each_scan_path_gets_its_own_path_argument() {
+        let dir = tempfile::tempdir().unwrap();
+        let scanner = fake_thor(thor_config(), ECHO_ARGS).await;
+
+        let results = scanner.run_scan(&context(dir.path(), &["/data", "/srv"])).await.unwrap();
+        let args = echoed_args(results);
+        assert_eq!(args.matches("--path ").count(), 2, "{}", args);
+        assert!(args.contains("--path /data --path /srv"), "{}", args);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn scan_timeout_stops_a_hung_thor() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("thor.pid");
+        let script = format!("echo $$ > {}\nexec sleep 60", pid_file.display());
+        let scanner = fake_thor(thor_config(), &script).await
+            .with_scan_timeout(Some(Duration::from_secs(1)));
+
+        let started = Instant::now();
+        let error = scanner.run_scan(&context(dir.path(), &["/data"])).await.unwrap_err();
+        assert_eq!(error.to_string(), "Thor scan timed out after 1s");
+        assert!(started.elapsed() < Duration::from_secs(30));
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let alive = Command::new("kill").args(["-0", pid.trim()]).stderr(Stdio::null()).status().unwrap();
+        assert!(!alive.success(), "Thor process {} is still around", pid.trim());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn enterprise_scan_with_redb_passes_the_redb_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut scanner = fake_thor(thor_config(), ECHO_ARGS).await;
+        let db_path = dir.path().join("rules.redb");
+        scanner.enable_redb_optimization(db_path.to_str().unwrap(), &DatabaseConfig::default()).await.unwrap();
+
+        let ctx = context(dir.path(), &["/data"]).with_enterprise_mode(true);
+        let results = scanner.run_scan(&ctx).await.unwrap();
+
+        let args = echoed_args(results);
+        assert!(args.contains("--enterprise-mode --ai-enhanced --redb-optimized"), "{}", args);
+        assert!(dir.path().join("results.json").is_file());
+    }
+}
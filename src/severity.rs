@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// Normalized finding/rule severity, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+
+    /// Maps a Thor log level (or one of our own severity names) to a severity.
+    ///
+    /// Thor levels map as `Alert` → critical, `Warning` → high, `Notice` → medium
+    /// and `Info` → info. Anything else (`Error`, `Debug`, unknown) is unmapped.
+    pub fn from_thor_level(level: &str) -> Option<Severity> {
+        match level.to_ascii_lowercase().as_str() {
+            "alert" => Some(Severity::Critical),
+            "warning" => Some(Severity::High),
+            "notice" => Some(Severity::Medium),
+            "info" => Some(Severity::Info),
+            other => other.parse().ok(),
+        }
+    }
+
+    /// Severity of a Thor finding, read from its `level` (or `severity`) field.
+    pub fn of_finding(finding: &Value) -> Option<Severity> {
+        finding.get("level")
+            .or_else(|| finding.get("severity"))
+            .and_then(Value::as_str)
+            .and_then(Severity::from_thor_level)
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "info" | "informational" => Ok(Severity::Info),
+            "low" => Ok(Severity::Low),
+            "medium" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            "critical" => Ok(Severity::Critical),
+            other => Err(anyhow::anyhow!(
+                "Invalid severity '{}': expected one of info, low, medium, high, critical",
+                other
+            )),
+        }
+    }
+}
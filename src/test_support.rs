@@ -0,0 +1,115 @@
+//! Fixtures shared by the unit tests.
+
+use crate::hooks::yara_rules_redb::content_hash;
+use crate::hooks::{initialize_yara_rules_hook, ThreatIntelIndicator, YaraRule, YaraRulesRedbHook};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// A hook on a fresh database in a temp dir, which must outlive the hook.
+pub async fn temp_hook() -> (TempDir, YaraRulesRedbHook) {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("rules.redb");
+    let hook = initialize_yara_rules_hook(db_path.to_str().unwrap()).await.unwrap();
+    (dir, hook)
+}
+
+/// A valid rule declaring `rule <name>`, with a fresh id and no tags.
+pub fn rule(name: &str) -> YaraRule {
+    let content = format!("rule {} {{\n    condition:\n        true\n}}\n", name);
+    let now = chrono::Utc::now();
+    YaraRule {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        hash: content_hash(&content),
+        content,
+        author: "test".to_string(),
+        description: String::new(),
+        tags: Vec::new(),
+        severity: "medium".to_string(),
+        created_at: now,
+        updated_at: now,
+        version: "1.0".to_string(),
+        source: "test".to_string(),
+        mitre_tactics: Vec::new(),
+        mitre_techniques: Vec::new(),
+        threat_actors: Vec::new(),
+        malware_families: Vec::new(),
+        scan_tier: Default::default(),
+    }
+}
+
+/// An indicator of `indicator_type` for `value`, seen now.
+pub fn indicator(indicator_type: &str, value: &str, confidence: f64) -> ThreatIntelIndicator {
+    let now = chrono::Utc::now();
+    ThreatIntelIndicator {
+        id: String::new(),
+        indicator_type: indicator_type.to_string(),
+        value: value.to_string(),
+        confidence,
+        threat_score: 50.0,
+        first_seen: now,
+        last_seen: now,
+        source_feeds: vec!["test".to_string()],
+        associated_campaigns: Vec::new(),
+        mitre_mapping: Vec::new(),
+        quantum_resistant: false,
+    }
+}
+
+/// A canned HTTP response: status, extra headers and body.
+pub type Response = (u16, Vec<(&'static str, String)>, Vec<u8>);
+
+/// Serves `responses` (status, body) to successive connections on a local
+/// port, one request each, and returns the endpoint URL and a count of the
+/// requests answered.
+pub async fn http_server(responses: Vec<(u16, Vec<u8>)>) -> (String, Arc<AtomicUsize>) {
+    let responses = responses.into_iter().map(|(status, body)| (status, Vec::new(), body)).collect();
+    let (endpoint, served, _) = serve(responses).await;
+    (endpoint, served)
+}
+
+/// Like `http_server`, but each response also carries `headers`, and the
+/// request line of every request answered is recorded.
+pub async fn recording_http_server(responses: Vec<Response>) -> (String, Arc<Mutex<Vec<String>>>) {
+    let (endpoint, _, requests) = serve(responses).await;
+    (endpoint, requests)
+}
+
+async fn serve(responses: Vec<Response>) -> (String, Arc<AtomicUsize>, Arc<Mutex<Vec<String>>>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let served = Arc::new(AtomicUsize::new(0));
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let counter = served.clone();
+    let request_lines = requests.clone();
+    tokio::spawn(async move {
+        for (status, headers, body) in responses {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                let read = stream.read(&mut buf).await.unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..read]);
+            }
+            let request_line = String::from_utf8_lossy(&request).lines().next().unwrap_or_default().to_string();
+            request_lines.lock().unwrap().push(request_line);
+
+            let mut head = format!("HTTP/1.1 {} Test\r\nContent-Length: {}\r\nConnection: close\r\n", status, body.len());
+            for (name, value) in headers {
+                head.push_str(&format!("{}: {}\r\n", name, value));
+            }
+            head.push_str("\r\n");
+            stream.write_all(head.as_bytes()).await.unwrap();
+            stream.write_all(&body).await.unwrap();
+            stream.shutdown().await.unwrap();
+            counter.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+    (endpoint, served, requests)
+}
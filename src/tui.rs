@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::io::IsTerminal;
+
+use anyhow::{Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Tabs, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::findings::Finding;
+use crate::hooks::YaraRule;
+use crate::severity::Severity;
+
+const KEY_HELP: &str = "Tab switch  ↑↓ move  / search  t tag  s severity  Esc clear  J/K scroll details  q quit";
+
+/// Read-only terminal browser over the stored rules and the findings of one
+/// results file. Nothing it does writes to the database.
+pub struct Browser {
+    rules: Vec<YaraRule>,
+    findings: Vec<Finding>,
+    /// Where the findings were read from; `None` when there was no results file.
+    results_source: Option<String>,
+    /// Tags by rule name, so findings can be filtered by their rules' tags.
+    rule_tags: HashMap<String, Vec<String>>,
+    tab: Tab,
+    filter: Filter,
+    /// Filter being typed, shown in the status line until Enter or Esc.
+    input: Option<(InputField, String)>,
+    /// Indices into `rules` or `findings` (per `tab`) that pass the filter.
+    visible: Vec<usize>,
+    list: ListState,
+    detail_scroll: u16,
+    /// Rows in the list pane at the last draw, for paging.
+    page: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Rules,
+    Findings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputField {
+    Search,
+    Tag,
+}
+
+/// Shared by both tabs, so switching keeps the same view of the data.
+#[derive(Debug, Default)]
+struct Filter {
+    /// Case-insensitive substring of names, paths, descriptions and tags.
+    search: String,
+    /// Exact tag, case-insensitive; findings match through their rules.
+    tag: String,
+    min_severity: Option<Severity>,
+}
+
+impl Filter {
+    fn is_empty(&self) -> bool {
+        self.search.is_empty() && self.tag.is_empty() && self.min_severity.is_none()
+    }
+
+    fn severity_passes(&self, severity: Option<Severity>) -> bool {
+        match self.min_severity {
+            Some(min) => severity.is_some_and(|severity| severity >= min),
+            None => true,
+        }
+    }
+
+    fn search_passes<'a>(&self, fields: impl IntoIterator<Item = &'a str>) -> bool {
+        let search = self.search.to_lowercase();
+        search.is_empty() || fields.into_iter().any(|field| field.to_lowercase().contains(&search))
+    }
+
+    fn tag_passes<'a>(&self, tags: impl IntoIterator<Item = &'a String>) -> bool {
+        self.tag.is_empty() || tags.into_iter().any(|tag| tag.eq_ignore_ascii_case(&self.tag))
+    }
+}
+
+impl Browser {
+    pub fn new(mut rules: Vec<YaraRule>, findings: Vec<Finding>, results_source: Option<String>) -> Self {
+        rules.sort_by(|a, b| a.name.cmp(&b.name));
+        let rule_tags = rules.iter().map(|rule| (rule.name.clone(), rule.tags.clone())).collect();
+
+        let mut browser = Self {
+            rules,
+            findings,
+            results_source,
+            rule_tags,
+            tab: Tab::Rules,
+            filter: Filter::default(),
+            input: None,
+            visible: Vec::new(),
+            list: ListState::default(),
+            detail_scroll: 0,
+            page: 10,
+        };
+        browser.refresh();
+        browser
+    }
+
+    /// Takes over the terminal until the user quits, restoring it afterwards
+    /// even when drawing fails.
+    pub fn run(mut self) -> Result<()> {
+        if !std::io::stdout().is_terminal() {
+            return Err(anyhow::anyhow!("The rule browser needs an interactive terminal"));
+        }
+
+        let mut terminal = ratatui::try_init().context("Failed to set up the terminal")?;
+        let result = self.event_loop(&mut terminal);
+        ratatui::restore();
+        result
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Applies one key press; `false` when it asks to quit.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return false;
+        }
+
+        if let Some((field, text)) = &mut self.input {
+            match key.code {
+                KeyCode::Enter => {
+                    let text = text.trim().to_string();
+                    match field {
+                        InputField::Search => self.filter.search = text,
+                        InputField::Tag => self.filter.tag = text,
+                    }
+                    self.input = None;
+                    self.refresh();
+                }
+                KeyCode::Esc => self.input = None,
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                _ => {}
+            }
+            return true;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.tab = match self.tab {
+                    Tab::Rules => Tab::Findings,
+                    Tab::Findings => Tab::Rules,
+                };
+                self.list.select(None);
+                self.refresh();
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-(self.page as isize)),
+            KeyCode::PageDown => self.move_selection(self.page as isize),
+            KeyCode::Home | KeyCode::Char('g') => self.move_selection(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => self.move_selection(isize::MAX),
+            KeyCode::Char('K') => self.detail_scroll = self.detail_scroll.saturating_sub(1),
+            KeyCode::Char('J') => self.detail_scroll = self.detail_scroll.saturating_add(1),
+            KeyCode::Char('/') => self.input = Some((InputField::Search, self.filter.search.clone())),
+            KeyCode::Char('t') => self.input = Some((InputField::Tag, self.filter.tag.clone())),
+            KeyCode::Char('s') => {
+                // All, then each severity from info up, then all again
+                self.filter.min_severity = match self.filter.min_severity {
+                    None => Some(Severity::Info),
+                    Some(Severity::Info) => Some(Severity::Low),
+                    Some(Severity::Low) => Some(Severity::Medium),
+                    Some(Severity::Medium) => Some(Severity::High),
+                    Some(Severity::High) => Some(Severity::Critical),
+                    Some(Severity::Critical) => None,
+                };
+                self.refresh();
+            }
+            KeyCode::Esc => {
+                self.filter = Filter::default();
+                self.refresh();
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let Some(last) = self.visible.len().checked_sub(1) else { return };
+        let current = self.list.selected().unwrap_or(0);
+        let target = current.saturating_add_signed(delta).min(last);
+        if Some(target) != self.list.selected() {
+            self.list.select(Some(target));
+            self.detail_scroll = 0;
+        }
+    }
+
+    /// Recomputes the visible entries of the current tab after a filter or
+    /// tab change, keeping the selection in range.
+    fn refresh(&mut self) {
+        self.visible = match self.tab {
+            Tab::Rules => (0..self.rules.len()).filter(|&index| self.rule_passes(&self.rules[index])).collect(),
+            Tab::Findings => (0..self.findings.len()).filter(|&index| self.finding_passes(&self.findings[index])).collect(),
+        };
+        let selected = match self.visible.len() {
+            0 => None,
+            len => Some(self.list.selected().unwrap_or(0).min(len - 1)),
+        };
+        self.list.select(selected);
+        self.detail_scroll = 0;
+    }
+
+    fn rule_passes(&self, rule: &YaraRule) -> bool {
+        self.filter.severity_passes(rule.severity.parse().ok())
+            && self.filter.tag_passes(&rule.tags)
+            && self.filter.search_passes(
+                [rule.name.as_str(), &rule.description, &rule.author, &rule.source]
+                    .into_iter()
+                    .chain(rule.tags.iter().map(String::as_str)),
+            )
+    }
+
+    fn finding_passes(&self, finding: &Finding) -> bool {
+        self.filter.severity_passes(finding.severity)
+            && self.filter.tag_passes(finding.rules.iter().filter_map(|rule| self.rule_tags.get(rule)).flatten())
+            && self.filter.search_passes(
+                finding.path.as_deref().into_iter()
+                    .chain(finding.module.as_deref())
+                    .chain(finding.rules.iter().map(String::as_str)),
+            )
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [tabs_area, body, status_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ]).areas(frame.area());
+        let [list_area, detail_area] = Layout::horizontal([
+            Constraint::Percentage(40),
+            Constraint::Percentage(60),
+        ]).areas(body);
+
+        let count = |tab: Tab, total: usize| if tab == self.tab {
+            format!("{}/{}", self.visible.len(), total)
+        } else {
+            total.to_string()
+        };
+        let tabs = Tabs::new(vec![
+            format!(" Rules ({}) ", count(Tab::Rules, self.rules.len())),
+            format!(" Findings ({}) ", count(Tab::Findings, self.findings.len())),
+        ])
+            .select(match self.tab {
+                Tab::Rules => 0,
+                Tab::Findings => 1,
+            })
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_widget(tabs, tabs_area);
+
+        let items: Vec<ListItem> = self.visible.iter().map(|&index| match self.tab {
+            Tab::Rules => {
+                let rule = &self.rules[index];
+                let severity = rule.severity.parse().ok();
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<9}", rule.severity), severity_style(severity)),
+                    Span::raw(rule.name.as_str()),
+                ]))
+            }
+            Tab::Findings => {
+                let finding = &self.findings[index];
+                let severity = finding.severity.map_or("-", |severity| severity.as_str());
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<9}", severity), severity_style(finding.severity)),
+                    Span::raw(finding_label(finding)),
+                ]))
+            }
+        }).collect();
+        let list_title = match (self.tab, &self.results_source) {
+            (Tab::Rules, _) => " Rules ".to_string(),
+            (Tab::Findings, Some(source)) => format!(" {} ", source),
+            (Tab::Findings, None) => " No results file found ".to_string(),
+        };
+        let list = List::new(items)
+            .block(Block::bordered().title(list_title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        self.page = list_area.height.saturating_sub(2).max(1) as usize;
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+
+        let detail = match self.list.selected().and_then(|selected| self.visible.get(selected)) {
+            Some(&index) => match self.tab {
+                Tab::Rules => rule_detail(&self.rules[index]),
+                Tab::Findings => finding_detail(&self.findings[index]),
+            },
+            None => vec![Line::raw("Nothing matches the current filter")],
+        };
+        let detail = Paragraph::new(detail)
+            .block(Block::bordered().title(" Details "))
+            .wrap(Wrap { trim: false })
+            .scroll((self.detail_scroll, 0));
+        frame.render_widget(detail, detail_area);
+
+        frame.render_widget(Paragraph::new(self.status_line()), status_area);
+    }
+
+    fn status_line(&self) -> Line<'_> {
+        if let Some((field, text)) = &self.input {
+            let prompt = match field {
+                InputField::Search => "Search: ",
+                InputField::Tag => "Tag: ",
+            };
+            return Line::from(vec![Span::raw(prompt).bold(), Span::raw(text.as_str()), Span::raw("▏")]);
+        }
+        if self.filter.is_empty() {
+            return Line::raw(KEY_HELP).dark_gray();
+        }
+
+        let mut filters = Vec::new();
+        if !self.filter.search.is_empty() {
+            filters.push(format!("search \"{}\"", self.filter.search));
+        }
+        if !self.filter.tag.is_empty() {
+            filters.push(format!("tag {}", self.filter.tag));
+        }
+        if let Some(severity) = self.filter.min_severity {
+            filters.push(format!("severity >= {}", severity));
+        }
+        Line::from(vec![
+            Span::raw(format!("Filter: {}", filters.join(", "))).bold(),
+            Span::raw("  (Esc clears)").dark_gray(),
+        ])
+    }
+}
+
+fn severity_style(severity: Option<Severity>) -> Style {
+    match severity {
+        Some(Severity::Critical) => Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+        Some(Severity::High) => Style::new().fg(Color::Red),
+        Some(Severity::Medium) => Style::new().fg(Color::Yellow),
+        Some(Severity::Low) => Style::new().fg(Color::Cyan),
+        Some(Severity::Info) => Style::new(),
+        None => Style::new().fg(Color::DarkGray),
+    }
+}
+
+/// `RuleA, RuleB  /path/to/file` for the findings list.
+fn finding_label(finding: &Finding) -> String {
+    let rules = match finding.rules.as_slice() {
+        [] => "(no rule)".to_string(),
+        rules => rules.join(", "),
+    };
+    match &finding.path {
+        Some(path) => format!("{}  {}", rules, path),
+        None => rules,
+    }
+}
+
+fn field<'a>(name: &'a str, value: impl Into<String>) -> Line<'a> {
+    Line::from(vec![Span::raw(format!("{:<12}", name)).bold(), Span::raw(value.into())])
+}
+
+fn rule_detail(rule: &YaraRule) -> Vec<Line<'_>> {
+    let mut lines = vec![
+        field("Name", rule.name.as_str()),
+        field("ID", rule.id.as_str()),
+        Line::from(vec![
+            Span::raw(format!("{:<12}", "Severity")).bold(),
+            Span::styled(rule.severity.as_str(), severity_style(rule.severity.parse().ok())),
+        ]),
+        field("Author", rule.author.as_str()),
+        field("Description", rule.description.as_str()),
+        field("Tags", rule.tags.join(", ")),
+        field("Tier", rule.scan_tier.as_str()),
+        field("Source", rule.source.as_str()),
+        field("Version", rule.version.as_str()),
+        field("Updated", rule.updated_at.format("%Y-%m-%d %H:%M:%S UTC").to_string()),
+    ];
+    for (name, values) in [
+        ("Tactics", &rule.mitre_tactics),
+        ("Techniques", &rule.mitre_techniques),
+        ("Actors", &rule.threat_actors),
+        ("Malware", &rule.malware_families),
+    ] {
+        if !values.is_empty() {
+            lines.push(field(name, values.join(", ")));
+        }
+    }
+
+    lines.push(Line::default());
+    lines.extend(rule.content.lines().map(Line::raw));
+    lines
+}
+
+fn finding_detail(finding: &Finding) -> Vec<Line<'_>> {
+    let optional = |value: Option<&str>| value.unwrap_or("-").to_string();
+    let mut lines = vec![
+        field("Path", optional(finding.path.as_deref())),
+        Line::from(vec![
+            Span::raw(format!("{:<12}", "Severity")).bold(),
+            Span::styled(
+                finding.severity.map_or("unmapped", |severity| severity.as_str()),
+                severity_style(finding.severity),
+            ),
+        ]),
+        field("Score", finding.score.map_or("-".to_string(), |score| score.to_string())),
+        field("Risk", finding.risk_score.map_or("-".to_string(), |risk| risk.to_string())),
+        field("Rules", finding.rules.join(", ")),
+        field("Module", optional(finding.module.as_deref())),
+        field("Time", finding.timestamp.map_or("-".to_string(), |time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())),
+    ];
+    if let Some(archive) = &finding.archive_file {
+        lines.push(field("Archive", archive.as_str()));
+    }
+    for matched in &finding.matched_strings {
+        lines.push(field("Matched", matched.as_str()));
+    }
+
+    lines.push(Line::default());
+    let raw = serde_json::to_string_pretty(&finding.raw).unwrap_or_default();
+    lines.extend(raw.lines().map(|line| Line::raw(line.to_string())));
+    lines
+}
@@ -0,0 +1,186 @@
+use crate::config::{default_max_walk_depth, ScanConfig};
+use anyhow::{Context, Result};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Limits for `walk_files`.
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    /// Directories deeper than this below the root are not entered.
+    pub max_depth: usize,
+    /// Skip symlinks whose target resolves outside the walk root.
+    pub skip_outside_root: bool,
+}
+
+impl WalkOptions {
+    pub fn from_config(config: &ScanConfig) -> Self {
+        Self {
+            max_depth: config.max_walk_depth,
+            skip_outside_root: config.skip_symlinks_outside_root,
+        }
+    }
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: default_max_walk_depth(),
+            skip_outside_root: true,
+        }
+    }
+}
+
+/// Identity of a file or directory that is stable across the different
+/// paths (symlinks, `..`) that can reach it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum FileId {
+    #[cfg(unix)]
+    Inode { dev: u64, ino: u64 },
+    #[cfg(not(unix))]
+    Canonical(PathBuf),
+}
+
+fn file_id(path: &Path) -> std::io::Result<FileId> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = std::fs::metadata(path)?;
+        Ok(FileId::Inode { dev: metadata.dev(), ino: metadata.ino() })
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(FileId::Canonical(std::fs::canonicalize(path)?))
+    }
+}
+
+/// Lists every file below `root`, following symlinks without looping.
+///
+/// Each directory is entered at most once, keyed by device/inode on Unix and
+/// by canonical path elsewhere, so symlink cycles end the branch instead of
+/// recursing forever. Files are listed once by the same key; of several paths
+/// to one file, one that isn't itself a symlink is preferred. Unreadable
+/// entries are logged and skipped.
+pub fn walk_files(root: &Path, options: &WalkOptions) -> Result<Vec<PathBuf>> {
+    let canonical_root = std::fs::canonicalize(root)
+        .with_context(|| format!("Failed to resolve walk root: {}", root.display()))?;
+
+    if canonical_root.is_file() {
+        return Ok(vec![root.to_path_buf()]);
+    }
+
+    let mut visited = HashSet::new();
+    visited.insert(file_id(root).context("Failed to read walk root")?);
+
+    // Smallest (is symlink, path) seen for each file
+    let mut files: HashMap<FileId, (bool, PathBuf)> = HashMap::new();
+    let mut pending = vec![(root.to_path_buf(), 0usize)];
+
+    while let Some((dir, depth)) = pending.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("Skipping unreadable directory {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_symlink = entry.file_type().is_ok_and(|t| t.is_symlink());
+
+            if is_symlink && options.skip_outside_root {
+                match std::fs::canonicalize(&path) {
+                    Ok(target) if target.starts_with(&canonical_root) => {}
+                    Ok(target) => {
+                        log::debug!("Skipping symlink outside scan root: {} -> {}", path.display(), target.display());
+                        continue;
+                    }
+                    Err(e) => {
+                        log::debug!("Skipping dangling symlink {}: {}", path.display(), e);
+                        continue;
+                    }
+                }
+            }
+
+            // Follows symlinks, so a link to a directory is walked like one
+            let Ok(metadata) = std::fs::metadata(&path) else { continue };
+
+            if metadata.is_dir() {
+                if depth >= options.max_depth {
+                    log::debug!("Not descending past max depth {}: {}", options.max_depth, path.display());
+                    continue;
+                }
+                match file_id(&path) {
+                    Ok(id) => {
+                        if visited.insert(id) {
+                            pending.push((path, depth + 1));
+                        } else {
+                            log::debug!("Skipping already visited directory (symlink loop?): {}", path.display());
+                        }
+                    }
+                    Err(e) => log::warn!("Skipping directory {}: {}", path.display(), e),
+                }
+            } else if metadata.is_file() {
+                let id = match file_id(&path) {
+                    Ok(id) => id,
+                    Err(e) => {
+                        log::warn!("Skipping file {}: {}", path.display(), e);
+                        continue;
+                    }
+                };
+                let candidate = (is_symlink, path);
+                match files.entry(id) {
+                    Entry::Vacant(entry) => {
+                        entry.insert(candidate);
+                    }
+                    Entry::Occupied(mut entry) => {
+                        log::debug!("Listing {} once, also reached as {}", entry.get().1.display(), candidate.1.display());
+                        if candidate < *entry.get() {
+                            entry.insert(candidate);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut files: Vec<PathBuf> = files.into_values().map(|(_, path)| path).collect();
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn a_self_referential_symlink_ends_the_walk_and_depth_is_respected() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("sub/deep")).unwrap();
+        std::fs::write(root.join("file.txt"), "a").unwrap();
+        std::fs::write(root.join("sub/nested.txt"), "b").unwrap();
+        std::fs::write(root.join("sub/deep/too_deep.txt"), "c").unwrap();
+        std::os::unix::fs::symlink(root, root.join("loop")).unwrap();
+
+        let options = WalkOptions { max_depth: 1, ..WalkOptions::default() };
+        let files = walk_files(root, &options).unwrap();
+
+        assert_eq!(files, [root.join("file.txt"), root.join("sub/nested.txt")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_file_reached_through_a_symlink_is_listed_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("file.txt"), "a").unwrap();
+        std::os::unix::fs::symlink(root.join("file.txt"), root.join("alias.txt")).unwrap();
+
+        let files = walk_files(root, &WalkOptions::default()).unwrap();
+
+        assert_eq!(files, [root.join("file.txt")]);
+    }
+}